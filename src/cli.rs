@@ -1,6 +1,6 @@
 //! Defines command-line interface options using `clap` for the RuNeVis application.
 
-use clap::Parser;
+use clap::{ArgGroup, Parser};
 use std::path::PathBuf;
 
 /// A CLI tool for inspecting NetCDF files
@@ -11,6 +11,7 @@ use std::path::PathBuf;
     name = "RuNeVis",
     about = "App for working with NetCDF files"
 )]
+#[command(group(ArgGroup::new("moment").args(["std", "var"])))]
 pub struct Args {
     /// Path to the NetCDF file
     #[arg(short, long)]
@@ -36,6 +37,18 @@ pub struct Args {
     #[arg(long, value_parser = parse_mean_arg)]
     pub max: Option<(String, String)>,
 
+    /// Compute the standard deviation for a variable over a specific dimension, formatted as <var>:<dim>
+    #[arg(long, value_parser = parse_mean_arg, conflicts_with = "var")]
+    pub std: Option<(String, String)>,
+
+    /// Compute the variance for a variable over a specific dimension, formatted as <var>:<dim>
+    #[arg(long, value_parser = parse_mean_arg)]
+    pub var: Option<(String, String)>,
+
+    /// Delta degrees of freedom of --std and --var: divide by N - ddof (1 for the sample estimate)
+    #[arg(long, default_value_t = 0, requires = "moment")]
+    pub ddof: u32,
+
     /// Path to save result as NetCDF. If not set, prints to terminal.
     #[arg(long)]
     pub output_netcdf: Option<PathBuf>,
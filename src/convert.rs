@@ -0,0 +1,358 @@
+//! Conversion between NetCDF files and Zarr stores
+//!
+//! [`netcdf_to_zarr`] copies every numeric variable of a NetCDF file, coordinate
+//! variables included, into a Zarr store: dimension names become
+//! `_ARRAY_DIMENSIONS` (v2) or `dimension_names` (v3), variable attributes become
+//! array attributes, `_FillValue` becomes the array fill value, global
+//! attributes go to the root group and NetCDF-4 groups become Zarr groups
+//! with their attributes. [`zarr_to_netcdf`] does the reverse.
+//! [`ConversionOptions`] selects the chunking and compression of the target.
+//!
+//! Both directions keep the element type of the source on disk and copy values
+//! exactly: floats travel as `f64`, signed integers as `i64` and unsigned
+//! integers and booleans as `u64`. Variables are copied block by block, with
+//! blocks made of whole target chunks and bounded by
+//! [`ConversionOptions::memory_budget`], so variables larger than memory
+//! convert too. NetCDF variables of character, string or user-defined types
+//! have no Zarr counterpart and are listed as skipped in the
+//! [`ConversionReport`].
+
+use crate::data_source::{DataReader, DataSourceConverter};
+use crate::errors::Result;
+use crate::netcdf_io::NetCDFDataSource;
+use crate::zarr_io::dtype::DataKind;
+use crate::zarr_io::{
+    ChunkGrid, ChunkOrder, Compressor, Element, WriteOptions, ZarrDataSource, ZarrReader,
+    ZarrWriter,
+};
+use ndarray::ArrayD;
+use netcdf::NcTypeDescriptor;
+use std::collections::HashMap;
+
+/// Default memory for the values of one block copied by a conversion (256 MiB)
+pub const DEFAULT_CONVERSION_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Size of the values copied per element, which travel as 64-bit numbers
+const COPY_ELEMENT_SIZE: usize = 8;
+
+/// Chunking and compression of the arrays written by a conversion
+#[derive(Debug, Clone)]
+pub struct ConversionOptions {
+    /// Chunk shape of every array of matching rank; whole-array chunks
+    /// (Zarr) or the library default layout (NetCDF) otherwise
+    pub chunk_shape: Option<Vec<usize>>,
+    /// Chunk shapes of individual arrays, overriding `chunk_shape`
+    pub array_chunks: HashMap<String, Vec<usize>>,
+    /// Compressor of the Zarr arrays; `None` stores chunks uncompressed
+    pub compressor: Option<Compressor>,
+    /// Deflate level (1-9) of the NetCDF variables; `None` disables compression
+    pub deflate_level: Option<i32>,
+    /// Consolidate the metadata of the Zarr store once every array is written
+    pub consolidate: bool,
+    /// Memory in bytes for the values of one copied block
+    ///
+    /// Blocks hold at least one target chunk. Zarr arrays without a chunk
+    /// shape are written as a single chunk only when they fit in the budget,
+    /// and otherwise take the chunking of their NetCDF variable.
+    pub memory_budget: usize,
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        Self {
+            chunk_shape: None,
+            array_chunks: HashMap::new(),
+            compressor: None,
+            deflate_level: None,
+            consolidate: true,
+            memory_budget: DEFAULT_CONVERSION_BUDGET,
+        }
+    }
+}
+
+impl ConversionOptions {
+    /// Chunk shape of an array of the given rank
+    fn chunks_for(&self, array_name: &str, ndim: usize) -> Option<Vec<usize>> {
+        self.array_chunks
+            .get(array_name)
+            .or(self.chunk_shape.as_ref())
+            .filter(|chunks| chunks.len() == ndim && ndim > 0)
+            .cloned()
+    }
+
+    /// Number of copied elements that fit in the memory budget
+    fn max_block_elements(&self) -> usize {
+        (self.memory_budget / COPY_ELEMENT_SIZE).max(1)
+    }
+}
+
+/// Outcome of a conversion
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConversionReport {
+    /// Names of the arrays or variables written to the target
+    pub converted: Vec<String>,
+    /// Variables left out of the target, with their type
+    pub skipped: Vec<(String, String)>,
+}
+
+impl ConversionReport {
+    /// Whether every variable of the source was written to the target
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.skipped.is_empty()
+    }
+}
+
+/// Copy every numeric variable and the global attributes of a NetCDF file into a Zarr store
+///
+/// Groups become Zarr groups of the same path with their attributes, and the
+/// variables inside them arrays named by their path, e.g. `forecast/temperature`.
+/// Variables without a `_FillValue` get no fill value (`null` in Zarr v2).
+/// Variables of character, string or user-defined types are skipped.
+///
+/// # Returns
+///
+/// The names of the converted arrays and of the skipped variables.
+///
+/// # Errors
+///
+/// Returns an error if reading a variable or writing an array fails.
+pub async fn netcdf_to_zarr(
+    source: &NetCDFDataSource,
+    target: &ZarrWriter,
+    options: &ConversionOptions,
+) -> Result<ConversionReport> {
+    log::info!("Converting NetCDF file {:?} to Zarr", source.path());
+    target
+        .create_group("", Some(source.global_attributes()?))
+        .await?;
+    for group in source.groups()? {
+        target
+            .create_group(&group, Some(source.group_attributes(&group)?))
+            .await?;
+    }
+
+    let mut report = ConversionReport::default();
+    for name in source.list_arrays().await? {
+        let metadata = source.get_metadata(&name).await?;
+        let Some(dtype) = source.data_type(&name)? else {
+            log::warn!("Skipped variable '{}' of type {}", name, metadata.dtype);
+            report.skipped.push((name, metadata.dtype));
+            continue;
+        };
+
+        let shape = metadata.shape;
+        let chunk_shape = match options.chunks_for(&name, shape.len()) {
+            Some(chunks) => Some(chunks),
+            None if shape.iter().product::<usize>() <= options.max_block_elements() => None,
+            None => Some(match source.chunking(&name)? {
+                Some(chunks) => chunks,
+                None => {
+                    copy_block_shape(&shape, &vec![1; shape.len()], options.max_block_elements())
+                }
+            }),
+        };
+        let block_shape = copy_block_shape(
+            &shape,
+            chunk_shape.as_deref().unwrap_or(&shape),
+            options.max_block_elements(),
+        );
+
+        let mut attributes = metadata.attributes;
+        attributes.remove("_FillValue");
+        let write_options = WriteOptions {
+            chunk_shape,
+            dtype,
+            fill_value: source.fill_value(&name)?,
+            compressor: options.compressor.clone(),
+            attributes: Some(attributes),
+            dimension_names: Some(metadata.dimensions),
+            ..WriteOptions::default()
+        };
+        target.create_array(&name, &shape, &write_options).await?;
+
+        let blocks = ChunkGrid::new(shape, block_shape, ".", ChunkOrder::C)?;
+        for index in blocks.chunk_indices() {
+            let region = blocks.chunk_region(&index);
+            match dtype.kind {
+                DataKind::Float => copy_to_zarr::<f64>(source, target, &name, &region).await?,
+                DataKind::Int => copy_to_zarr::<i64>(source, target, &name, &region).await?,
+                DataKind::UInt | DataKind::Bool => {
+                    copy_to_zarr::<u64>(source, target, &name, &region).await?
+                }
+            }
+        }
+        report.converted.push(name);
+    }
+
+    if options.consolidate {
+        target.consolidate_metadata().await?;
+    }
+    log::info!("Converted {} variables", report.converted.len());
+    Ok(report)
+}
+
+/// Copy every array and group of a Zarr store into a NetCDF file
+///
+/// Groups become NetCDF-4 groups of the same path with their attributes, and
+/// arrays inside them variables of those groups. Dimensions are defined in
+/// the root group and shared between variables by name; an array whose
+/// dimension conflicts with an existing one of another length gets its own
+/// `<array>_<dimension>` dimension, with `/` in the array path replaced by `_`.
+/// Variables keep the element type of their array, booleans becoming unsigned
+/// bytes. A `_FillValue` attribute, or else the fill value recorded in the
+/// array metadata (0 and NaN included), is recorded as the `_FillValue` of the
+/// variable; arrays with a `null` fill value get none.
+///
+/// # Returns
+///
+/// The names of the written variables; every array has a NetCDF counterpart,
+/// so none is skipped.
+///
+/// # Errors
+///
+/// Returns an error if reading an array or writing a variable fails.
+pub async fn zarr_to_netcdf(
+    source: &ZarrReader,
+    target: &NetCDFDataSource,
+    options: &ConversionOptions,
+) -> Result<ConversionReport> {
+    log::info!("Converting Zarr store to NetCDF file {:?}", target.path());
+    target.put_global_attributes(&source.get_group_attributes("").await?)?;
+    // Parents are listed before their subgroups
+    for group in source.list_groups().await? {
+        target.create_group(&group, &source.get_group_attributes(&group).await?)?;
+    }
+
+    let mut report = ConversionReport::default();
+    for array_name in source.list_arrays().await? {
+        let metadata = source.get_array_metadata(&array_name).await?;
+
+        let dimensions: HashMap<String, usize> = target.dimensions()?.into_iter().collect();
+        let dim_names: Vec<String> = metadata
+            .dimensions
+            .iter()
+            .zip(&metadata.shape)
+            .map(|(dim, &len)| match dimensions.get(dim) {
+                Some(&existing) if existing != len => {
+                    format!("{}_{}", array_name.replace('/', "_"), dim)
+                }
+                _ => dim.clone(),
+            })
+            .collect();
+
+        let mut attributes = metadata.attributes;
+        let fill_value = match attributes.remove("_FillValue") {
+            Some(value) => value.as_f64(),
+            None => source.get_declared_fill_value(&array_name).await?,
+        };
+
+        let dtype = source.get_data_type(&array_name).await?;
+        let chunk_shape = options.chunks_for(&array_name, metadata.shape.len());
+        target.define_variable(
+            &array_name,
+            &metadata.shape,
+            dtype,
+            &dim_names,
+            chunk_shape.as_deref(),
+            options.deflate_level,
+            fill_value,
+            &attributes,
+        )?;
+
+        let block_shape = copy_block_shape(
+            &metadata.shape,
+            chunk_shape.as_deref().unwrap_or(&metadata.chunks),
+            options.max_block_elements(),
+        );
+        let blocks = ChunkGrid::new(metadata.shape, block_shape, ".", ChunkOrder::C)?;
+        for index in blocks.chunk_indices() {
+            let region = blocks.chunk_region(&index);
+            match dtype.kind {
+                DataKind::Float => {
+                    copy_to_netcdf::<f64>(source, target, &array_name, &region).await?
+                }
+                DataKind::Int => {
+                    copy_to_netcdf::<i64>(source, target, &array_name, &region).await?
+                }
+                DataKind::UInt | DataKind::Bool => {
+                    copy_to_netcdf::<u64>(source, target, &array_name, &region).await?
+                }
+            }
+        }
+        report.converted.push(array_name);
+    }
+
+    log::info!("Converted {} arrays", report.converted.len());
+    Ok(report)
+}
+
+/// Copy one region of a NetCDF variable into the Zarr array of the same name
+async fn copy_to_zarr<T: Element + NcTypeDescriptor>(
+    source: &NetCDFDataSource,
+    target: &ZarrWriter,
+    name: &str,
+    region: &[(usize, usize)],
+) -> Result<()> {
+    let block: ArrayD<T> = source.read_region(name, region)?;
+    target.write_slice_as(name, region, &block).await
+}
+
+/// Copy one region of a Zarr array into the NetCDF variable of the same path
+async fn copy_to_netcdf<T: Element + NcTypeDescriptor>(
+    source: &ZarrReader,
+    target: &NetCDFDataSource,
+    name: &str,
+    region: &[(usize, usize)],
+) -> Result<()> {
+    let block: ArrayD<T> = source.read_slice_as(name, region).await?;
+    target.write_region(name, region, &block)
+}
+
+/// Shape of the blocks copied at a time: whole chunks, grown from the last
+/// dimension outwards while they hold at most `max_elements` elements
+///
+/// A block only grows along a dimension once it spans every later dimension
+/// entirely, so blocks are contiguous in C order. A single chunk larger than
+/// `max_elements` is still copied whole.
+fn copy_block_shape(shape: &[usize], chunks: &[usize], max_elements: usize) -> Vec<usize> {
+    let mut block: Vec<usize> = chunks
+        .iter()
+        .zip(shape)
+        .map(|(&chunk, &len)| chunk.min(len).max(1))
+        .collect();
+    for axis in (0..block.len()).rev() {
+        let chunk = block[axis];
+        let others: usize = block.iter().product::<usize>() / chunk;
+        let count = (max_elements / (others * chunk)).max(1);
+        block[axis] = (count * chunk).min(shape[axis].max(1));
+        if block[axis] < shape[axis] {
+            break;
+        }
+    }
+    block
+}
+
+impl DataSourceConverter<NetCDFDataSource> for ZarrDataSource {
+    /// Copy a NetCDF file into this store with the default [`ConversionOptions`]
+    fn convert_from(&self, other: &NetCDFDataSource) -> Result<()> {
+        futures::executor::block_on(netcdf_to_zarr(
+            other,
+            &self.writer,
+            &ConversionOptions::default(),
+        ))
+        .map(|_| ())
+    }
+}
+
+impl DataSourceConverter<ZarrDataSource> for NetCDFDataSource {
+    /// Copy a Zarr store into this file with the default [`ConversionOptions`]
+    fn convert_from(&self, other: &ZarrDataSource) -> Result<()> {
+        futures::executor::block_on(zarr_to_netcdf(
+            &other.reader,
+            self,
+            &ConversionOptions::default(),
+        ))
+        .map(|_| ())
+    }
+}
@@ -3,11 +3,14 @@
 //! This module provides trait-based abstractions that allow interoperability
 //! between NetCDF and Zarr data sources following the Interface Segregation Principle.
 
-use crate::errors::Result;
+use crate::errors::{Result, RuNeVisError};
+use crate::statistics::StatResult;
 use ndarray::ArrayD;
 use std::collections::HashMap;
 use serde_json::Value as JsonValue;
 use async_trait::async_trait;
+use futures::Stream;
+use std::pin::Pin;
 
 /// Metadata for array-like data
 #[derive(Debug, Clone)]
@@ -19,6 +22,56 @@ pub struct DataArrayMetadata {
     pub attributes: HashMap<String, JsonValue>,
 }
 
+impl DataArrayMetadata {
+    /// Get the axis index of a named dimension
+    ///
+    /// # Errors
+    ///
+    /// Returns `DimensionNotFound` if the array has no dimension with this name.
+    pub fn dimension_index(&self, dim_name: &str) -> Result<usize> {
+        self.dimensions
+            .iter()
+            .position(|d| d == dim_name)
+            .ok_or_else(|| RuNeVisError::DimensionNotFound {
+                var: self.name.clone(),
+                dim: dim_name.to_string(),
+            })
+    }
+}
+
+/// A chunk of an array yielded by [`StreamingDataReader::stream_chunks`]
+///
+/// `data` holds the N-D values of the chunk, clipped to the array bounds for
+/// edge chunks, so out-of-core code can place it with `offset`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataChunk {
+    /// Position of the chunk in the chunk grid
+    pub grid_index: Vec<usize>,
+    /// Array index of the first element of the chunk
+    pub offset: Vec<usize>,
+    /// Values of the chunk
+    pub data: ArrayD<f32>,
+}
+
+impl DataChunk {
+    /// Shape of the chunk
+    pub fn shape(&self) -> &[usize] {
+        self.data.shape()
+    }
+
+    /// Half-open `(start, end)` array range covered by the chunk in every dimension
+    pub fn region(&self) -> Vec<(usize, usize)> {
+        self.offset
+            .iter()
+            .zip(self.shape())
+            .map(|(&start, &len)| (start, start + len))
+            .collect()
+    }
+}
+
+/// Stream of array chunks in storage order
+pub type DataChunkStream = Pin<Box<dyn Stream<Item = Result<DataChunk>> + Send + 'static>>;
+
 /// Basic data source interface for reading arrays
 #[async_trait]
 pub trait DataReader {
@@ -39,6 +92,13 @@ pub trait DataReader {
         array_name: &str,
         slice_ranges: &[(usize, usize)],
     ) -> Result<ArrayD<f32>>;
+    
+    /// Get the shape of the chunks an array is stored in, `None` if it is not chunked
+    ///
+    /// Reductions read by slice align their slices to whole chunks when given one.
+    async fn chunk_shape(&self, _array_name: &str) -> Result<Option<Vec<usize>>> {
+        Ok(None)
+    }
 }
 
 /// Lazy loading interface for deferred array loading
@@ -56,6 +116,9 @@ pub trait StreamingDataReader: DataReader {
     type ChunkStream;
     
     /// Create a stream of data chunks for processing large arrays
+    ///
+    /// Chunks are yielded one at a time in storage (C) order of the chunk grid,
+    /// each carrying its grid index and offset in the array.
     fn stream_chunks(&self, array_name: &str) -> Self::ChunkStream;
 }
 
@@ -81,6 +144,28 @@ pub trait DataWriter {
         original_array_name: &str,
         source_metadata: Option<&DataArrayMetadata>,
     ) -> Result<()>;
+
+    /// Write a [`StatResult`] under its [`output_name`](StatResult::output_name)
+    ///
+    /// The `operation` attribute records the operation with its delta degrees
+    /// of freedom where they apply, e.g. `standard_deviation (ddof=1)`. The
+    /// NetCDF and Zarr writers instead record the bare operation name next to
+    /// a numeric `ddof` attribute.
+    async fn write_stat_result(
+        &self,
+        result: &StatResult<f32>,
+        source_metadata: Option<&DataArrayMetadata>,
+    ) -> Result<()> {
+        self.write_statistical_result(
+            &result.output_name(),
+            &result.data,
+            &result.remaining_dimensions,
+            &result.operation.to_string(),
+            &result.variable_name,
+            source_metadata,
+        )
+        .await
+    }
 }
 
 /// Interface segregation: combine only needed capabilities
@@ -2,8 +2,8 @@
 //!
 //! A comprehensive Rust library for analyzing NetCDF (Network Common Data Form) and
 //! Zarr files. RuNeVis provides functionality for computing statistics like means, sums,
-//! minimums, and maximums over specified dimensions of NetCDF variables and Zarr arrays
-//! using parallel processing.
+//! minimums, maximums, medians, standard deviations and variances over specified
+//! dimensions of NetCDF variables and Zarr arrays using parallel processing.
 //!
 //! ## Key Features
 //!
@@ -20,8 +20,10 @@
 //!
 //! - [`metadata`]: NetCDF file inspection and variable description
 //! - [`statistics`]: Statistical computations and parallel reductions for NetCDF and Zarr
-//! - [`netcdf_io`]: NetCDF file I/O operations and data slicing
+//! - [`data_source`]: Format-agnostic reader and writer traits, implemented for NetCDF and Zarr
+//! - [`netcdf_io`]: NetCDF file I/O operations, data slicing and the NetCDF data source
 //! - [`zarr_io`]: Zarr array I/O operations with cloud storage support
+//! - [`convert`]: NetCDF to Zarr conversion and back
 //! - [`parallel`]: Parallel processing configuration
 //! - [`errors`]: Centralized error handling
 //!
@@ -60,6 +62,7 @@
 //! and provides clear error reporting for debugging and analysis.
 
 // Core modules
+pub mod convert;
 pub mod data_source;
 pub mod errors;
 pub mod metadata;
@@ -72,7 +75,12 @@ pub mod zarr_io;
 mod cli;
 mod utils;
 
+// Command-line options, exported for the `RuNeVis` binary only
+#[doc(hidden)]
+pub use cli::Args;
+
 // Direct re-exports for the public API
+pub use convert::*;
 pub use data_source::*;
 pub use errors::*;
 pub use metadata::*;
@@ -84,12 +92,12 @@ pub use zarr_io::*;
 // High-level convenience API
 pub mod prelude {
     //! Commonly used imports for convenience
-    pub use crate::data_source::{DataReader, DataWriter, LazyDataReader, StreamingDataReader, DataArrayMetadata, AdvancedDataSource};
+    pub use crate::data_source::{DataReader, DataWriter, LazyDataReader, StreamingDataReader, DataArrayMetadata, AdvancedDataSource, DataChunk};
     pub use crate::errors::{Result, RuNeVisError};
-    pub use crate::netcdf_io::NetCDFWriter;
+    pub use crate::netcdf_io::{NetCDFDataSource, NetCDFWriter};
     pub use crate::parallel::ParallelConfig;
-    pub use crate::statistics::{StatOperation, StatisticalReduction};
-    pub use crate::zarr_io::{ArrayMetadata, ZarrReader, ZarrSource, ZarrWriter, ZarrDataSource, LazyArray};
+    pub use crate::statistics::{reduce_over_dimension, StatOperation, StatResult, StatisticalReduction};
+    pub use crate::zarr_io::{ArrayMetadata, ZarrReader, ZarrSource, ZarrWriter, ZarrDataSource, LazyArray, WriteOptions, ZarrFormat};
 }
 
 // Backwards compatibility re-exports
@@ -5,15 +5,9 @@ use clap::Parser;
 use netcdf::open;
 use std::path::Path;
 
-mod cli;
-mod errors;
-mod metadata;
-mod netcdf_io;
-mod parallel;
-mod statistics;
-
-use cli::Args;
-use parallel::ParallelConfig;
+use ru_ne_vis::parallel::ParallelConfig;
+use ru_ne_vis::statistics::StatOperation;
+use ru_ne_vis::{metadata, netcdf_io, statistics, Args};
 
 fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     // Parse command-line arguments
@@ -53,6 +47,13 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         args.file.display()
     );
 
+    // Standard deviations and variances share one code path
+    let moment = match (&args.std, &args.var) {
+        (Some(spec), _) => Some((spec, StatOperation::Std { ddof: args.ddof }, "standard deviation")),
+        (None, Some(spec)) => Some((spec, StatOperation::Var { ddof: args.ddof }, "variance")),
+        (None, None) => None,
+    };
+
     // Handle different operations based on command-line options
     if args.list_vars {
         // List variables and dimensions in a clean format
@@ -189,6 +190,37 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         } else {
             println!("Computed median array:\n{:#?}", result);
         }
+    } else if let Some(((var, dim), operation, label)) = moment {
+        // Compute standard deviation or variance over specified dimension
+        let reduce = match operation {
+            StatOperation::Std { .. } => statistics::std_over_dimension,
+            _ => statistics::var_over_dimension,
+        };
+        let (result, dim_names, new_var_name) = reduce(&file, var, dim, args.ddof)
+            .map_err(|e| format!("Failed computing {} for variable '{}': {}", label, var, e))?;
+
+        if let Some(output_path) = &args.output_netcdf {
+            let output_path = Path::new(output_path);
+            netcdf_io::write_moment_to_netcdf(
+                &result,
+                &dim_names,
+                &new_var_name,
+                var,
+                &file,
+                output_path,
+                operation,
+            )
+            .map_err(|e| {
+                format!(
+                    "Failed writing to NetCDF '{}': {}",
+                    output_path.display(),
+                    e
+                )
+            })?;
+            println!("✅ Result saved to {}", output_path.display());
+        } else {
+            println!("Computed {} array (ddof={}):\n{:#?}", label, args.ddof, result);
+        }
     } else if let Some(var_name) = args.describe {
         // Describe a specific variable's details
         metadata::describe_variable(&file, &var_name)
@@ -2,13 +2,28 @@
 //!
 //! This module provides functions for extracting data slices from NetCDF files
 //! and writing computed statistical results to new NetCDF files with proper
-//! metadata preservation.
+//! metadata preservation. [`NetCDFDataSource`] exposes a NetCDF file through
+//! the [`data_source`](crate::data_source) traits shared with Zarr.
 
 use crate::cli::SliceSpec;
+use crate::data_source::{
+    AdvancedDataSource, DataArrayMetadata, DataChunk, DataChunkStream, DataReader, DataWriter,
+    FullDataSource, LazyDataReader, StreamingDataReader,
+};
 use crate::errors::{Result, RuNeVisError};
+use crate::statistics::{StatOperation, StatResult};
+use crate::zarr_io::check_slice_ranges;
+use crate::zarr_io::chunks::{ChunkGrid, ChunkOrder};
+use crate::zarr_io::dtype::{DataKind, Endianness};
+use crate::zarr_io::DataType;
+use async_trait::async_trait;
 use chrono::Utc;
-use ndarray::ArrayD;
-use netcdf::{create, AttributeValue, File};
+use ndarray::{ArrayD, IxDyn};
+use netcdf::types::{FloatType, IntType, NcVariableType};
+use netcdf::{create, AttributeValue, Extent, File, NcTypeDescriptor, Variable, VariableMut};
+use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::{fs, path::Path};
 
 /// Unified NetCDF writer for statistical results
@@ -33,6 +48,19 @@ impl<'a> NetCDFWriter<'a> {
         dim_names: &[String],
         var_name: &str,
         original_var_name: &str,
+    ) -> Result<()> {
+        self.write_result_with_ddof(data, dim_names, var_name, original_var_name, None)
+    }
+
+    /// Write statistical result to NetCDF file, recording the delta degrees of
+    /// freedom of a standard deviation or variance in a `ddof` attribute
+    pub fn write_result_with_ddof(
+        &self,
+        data: &ArrayD<f32>,
+        dim_names: &[String],
+        var_name: &str,
+        original_var_name: &str,
+        ddof: Option<u32>,
     ) -> Result<()> {
         if self.output_path.exists() {
             fs::remove_file(self.output_path)?;
@@ -68,6 +96,9 @@ impl<'a> NetCDFWriter<'a> {
         if let Some(fv) = fill_value {
             new_var.put_attribute("_FillValue", fv)?;
         }
+        if let Some(ddof) = ddof {
+            new_var.put_attribute("ddof", ddof_attribute(ddof)?)?;
+        }
 
         new_var.put(data.view(), ..)?;
 
@@ -105,7 +136,7 @@ impl<'a> NetCDFWriter<'a> {
                     new_var.put_attribute(attr.name(), vals)?;
                 }
                 _ => {
-                    println!("⚠ Skipped unsupported attribute type for '{}'", attr.name());
+                    log::warn!("Skipped unsupported attribute type for '{}'", attr.name());
                 }
             }
         }
@@ -185,6 +216,28 @@ pub fn write_max_to_netcdf(
     writer.write_result(data, dim_names, var_name, original_var_name)
 }
 
+/// Writes a computed standard deviation or variance to a new NetCDF file with attributes copied.
+///
+/// The delta degrees of freedom of `operation` are recorded in a `ddof` attribute.
+pub fn write_moment_to_netcdf(
+    data: &ArrayD<f32>,
+    dim_names: &[String],
+    var_name: &str,
+    original_var_name: &str,
+    input_file: &File,
+    output_path: &Path,
+    operation: StatOperation,
+) -> Result<()> {
+    let writer = NetCDFWriter::new(input_file, output_path);
+    writer.write_result_with_ddof(
+        data,
+        dim_names,
+        var_name,
+        original_var_name,
+        operation.ddof(),
+    )
+}
+
 /// Extracts a slice of data from a variable based on the provided slice specification.
 pub fn extract_slice(file: &File, slice_spec: SliceSpec) -> Result<()> {
     let var =
@@ -365,3 +418,883 @@ pub fn extract_slice(file: &File, slice_spec: SliceSpec) -> Result<()> {
 
     Ok(())
 }
+
+/// NetCDF file exposed through the format-agnostic [`data_source`](crate::data_source) traits
+///
+/// Every variable is an array of the source, described by its real dimension
+/// names and attributes; variables of NetCDF-4 groups are named by their
+/// path, e.g. `forecast/temperature`. The file is opened for each operation,
+/// so writes made through [`DataWriter`] are visible to the next read.
+#[derive(Debug, Clone)]
+pub struct NetCDFDataSource {
+    path: PathBuf,
+}
+
+impl NetCDFDataSource {
+    /// Open an existing NetCDF file
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let source = Self { path: path.into() };
+        source.file()?;
+        Ok(source)
+    }
+
+    /// Create an empty NetCDF file, replacing any existing file at `path`
+    pub fn create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        let mut file = create(&path)?;
+        file.add_attribute(
+            "history",
+            format!("Created by RuNeVis on {}", Utc::now().to_rfc3339()),
+        )?;
+        Ok(Self { path })
+    }
+
+    /// Path of the NetCDF file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Names and lengths of the dimensions of the root group
+    pub fn dimensions(&self) -> Result<Vec<(String, usize)>> {
+        let file = self.file()?;
+        let dimensions = file
+            .dimensions()
+            .map(|dim| (dim.name().to_string(), dim.len()))
+            .collect();
+        Ok(dimensions)
+    }
+
+    /// Global attributes of the file
+    pub fn global_attributes(&self) -> Result<HashMap<String, JsonValue>> {
+        let file = self.file()?;
+        attributes_to_json(file.attributes())
+    }
+
+    /// Add or replace global attributes of the file
+    pub fn put_global_attributes(&self, attributes: &HashMap<String, JsonValue>) -> Result<()> {
+        let mut file = netcdf::append(&self.path)?;
+        let mut names: Vec<&String> = attributes.keys().collect();
+        names.sort();
+        for name in names {
+            match json_to_attribute(&attributes[name]) {
+                Some(value) => {
+                    file.add_attribute(name, value)?;
+                }
+                None => log::warn!("Skipped unsupported attribute type for '{}'", name),
+            }
+        }
+        Ok(())
+    }
+
+    /// Paths of the groups below the root, parents before their subgroups,
+    /// e.g. `forecast` then `forecast/surface`; classic files have none
+    pub fn groups(&self) -> Result<Vec<String>> {
+        let file = self.file()?;
+        Ok(group_paths(&file))
+    }
+
+    /// Attributes of a group; `""` addresses the root group
+    pub fn group_attributes(&self, group_path: &str) -> Result<HashMap<String, JsonValue>> {
+        if group_path.is_empty() {
+            return self.global_attributes();
+        }
+        let file = self.file()?;
+        let group = file
+            .group(group_path)?
+            .ok_or_else(|| RuNeVisError::Generic(format!("Group not found: '{}'", group_path)))?;
+        attributes_to_json(group.attributes())
+    }
+
+    /// Create a group, and any missing parent groups, and add attributes to it
+    pub fn create_group(
+        &self,
+        group_path: &str,
+        attributes: &HashMap<String, JsonValue>,
+    ) -> Result<()> {
+        let mut file = netcdf::append(&self.path)?;
+        if file.group(group_path)?.is_none() {
+            file.add_group(group_path)?;
+        }
+        let mut group = file
+            .group_mut(group_path)?
+            .ok_or_else(|| RuNeVisError::Generic(format!("Group not found: '{}'", group_path)))?;
+
+        let mut names: Vec<&String> = attributes.keys().collect();
+        names.sort();
+        for name in names {
+            match json_to_attribute(&attributes[name]) {
+                Some(value) => {
+                    group.add_attribute(name, value)?;
+                }
+                None => log::warn!("Skipped unsupported attribute type for '{}'", name),
+            }
+        }
+        Ok(())
+    }
+
+    fn file(&self) -> Result<File> {
+        Ok(netcdf::open(&self.path)?)
+    }
+
+    fn metadata(&self, array_name: &str) -> Result<DataArrayMetadata> {
+        let file = self.file()?;
+        let mut metadata = variable_metadata(&find_variable(&file, array_name)?)?;
+        // Variables of groups keep their path
+        metadata.name = array_name.to_string();
+        Ok(metadata)
+    }
+
+    /// Read the half-open `(start, end)` region of a variable as values of type `T`
+    ///
+    /// Values are converted from the variable type by the NetCDF library.
+    pub(crate) fn read_region<T: NcTypeDescriptor + Copy>(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+    ) -> Result<ArrayD<T>> {
+        let file = self.file()?;
+        let var = find_variable(&file, array_name)?;
+        let shape: Vec<usize> = var
+            .dimensions()
+            .iter()
+            .map(netcdf::Dimension::len)
+            .collect();
+        check_slice_ranges(array_name, &shape, slice_ranges)?;
+        read_hyperslab(&var, slice_ranges)
+    }
+
+    /// Chunk grid used to stream a variable
+    ///
+    /// Chunked (NetCDF-4) variables stream their storage chunks; contiguous
+    /// variables stream one index of their first dimension at a time.
+    fn chunk_grid(&self, array_name: &str) -> Result<ChunkGrid> {
+        let file = self.file()?;
+        let var = find_variable(&file, array_name)?;
+        let shape: Vec<usize> = var
+            .dimensions()
+            .iter()
+            .map(netcdf::Dimension::len)
+            .collect();
+        let chunks = match var.chunking()? {
+            Some(chunks) => chunks,
+            None => shape
+                .iter()
+                .enumerate()
+                .map(|(axis, &len)| if axis == 0 { 1 } else { len })
+                .collect(),
+        };
+        let chunks = chunks.into_iter().map(|c| c.max(1)).collect();
+        ChunkGrid::new(shape, chunks, ".", ChunkOrder::C)
+    }
+
+    /// Element type of a variable, `None` for character, string and
+    /// user-defined types
+    pub(crate) fn data_type(&self, array_name: &str) -> Result<Option<DataType>> {
+        let file = self.file()?;
+        Ok(netcdf_data_type(
+            &find_variable(&file, array_name)?.vartype(),
+        ))
+    }
+
+    /// Numeric `_FillValue` attribute of a variable, `None` without one
+    pub(crate) fn fill_value(&self, array_name: &str) -> Result<Option<f64>> {
+        let file = self.file()?;
+        let var = find_variable(&file, array_name)?;
+        let Some(value) = var.attribute_value("_FillValue") else {
+            return Ok(None);
+        };
+        let fill = match value? {
+            AttributeValue::Uchar(v) => f64::from(v),
+            AttributeValue::Schar(v) => f64::from(v),
+            AttributeValue::Ushort(v) => f64::from(v),
+            AttributeValue::Short(v) => f64::from(v),
+            AttributeValue::Uint(v) => f64::from(v),
+            AttributeValue::Int(v) => f64::from(v),
+            AttributeValue::Ulonglong(v) => v as f64,
+            AttributeValue::Longlong(v) => v as f64,
+            AttributeValue::Float(v) => f64::from(v),
+            AttributeValue::Double(v) => v,
+            _ => return Ok(None),
+        };
+        Ok(Some(fill))
+    }
+
+    /// Storage chunk shape of a variable, `None` for contiguous variables
+    pub(crate) fn chunking(&self, array_name: &str) -> Result<Option<Vec<usize>>> {
+        let file = self.file()?;
+        Ok(find_variable(&file, array_name)?.chunking()?)
+    }
+
+    /// Add a new variable of `f32` values with its dimensions and attributes,
+    /// and write `data` to it
+    ///
+    /// A numeric `_FillValue` attribute becomes the fill value of the variable.
+    /// See [`define_variable`](Self::define_variable) for the layout.
+    pub(crate) fn write_variable(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        dim_names: &[String],
+        chunk_shape: Option<&[usize]>,
+        deflate_level: Option<i32>,
+        attributes: &HashMap<String, JsonValue>,
+    ) -> Result<()> {
+        let fill_value = attributes.get("_FillValue").and_then(JsonValue::as_f64);
+        self.define_variable(
+            array_name,
+            data.shape(),
+            DataType::FLOAT32,
+            dim_names,
+            chunk_shape,
+            deflate_level,
+            fill_value,
+            attributes,
+        )?;
+        let full: Vec<(usize, usize)> = data.shape().iter().map(|&len| (0, len)).collect();
+        self.write_region(array_name, &full, data)
+    }
+
+    /// Add a new variable of `shape` with its dimensions and attributes,
+    /// without writing any data
+    ///
+    /// The variable holds elements of `dtype` (booleans become unsigned
+    /// bytes), and `fill_value`, when given, is written as a `_FillValue`
+    /// attribute of that type; a `_FillValue` among `attributes` is ignored.
+    /// Dimensions that already exist in the file are reused and must have the
+    /// length of the matching axis. `deflate_level` (1-9) compresses the
+    /// variable with zlib and the shuffle filter.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn define_variable(
+        &self,
+        array_name: &str,
+        shape: &[usize],
+        dtype: DataType,
+        dim_names: &[String],
+        chunk_shape: Option<&[usize]>,
+        deflate_level: Option<i32>,
+        fill_value: Option<f64>,
+        attributes: &HashMap<String, JsonValue>,
+    ) -> Result<()> {
+        if dim_names.len() != shape.len() {
+            return Err(RuNeVisError::Generic(format!(
+                "Array '{}' has {} dimensions but {} dimension names were given",
+                array_name,
+                shape.len(),
+                dim_names.len()
+            )));
+        }
+
+        let mut file = netcdf::append(&self.path)?;
+        for (dim_name, &len) in dim_names.iter().zip(shape) {
+            match file.dimension(dim_name).map(|dim| dim.len()) {
+                Some(existing) if existing == len => {}
+                Some(existing) => {
+                    return Err(RuNeVisError::Generic(format!(
+                        "Dimension '{}' has length {}, but array '{}' needs {}",
+                        dim_name, existing, array_name, len
+                    )))
+                }
+                None => {
+                    file.add_dimension(dim_name, len)?;
+                }
+            }
+        }
+
+        let dims: Vec<&str> = dim_names.iter().map(String::as_str).collect();
+        let mut var = match (dtype.kind, dtype.size) {
+            (DataKind::Bool, _) | (DataKind::UInt, 1) => {
+                file.add_variable::<u8>(array_name, &dims)?
+            }
+            (DataKind::Int, 1) => file.add_variable::<i8>(array_name, &dims)?,
+            (DataKind::Int, 2) => file.add_variable::<i16>(array_name, &dims)?,
+            (DataKind::Int, 4) => file.add_variable::<i32>(array_name, &dims)?,
+            (DataKind::Int, 8) => file.add_variable::<i64>(array_name, &dims)?,
+            (DataKind::UInt, 2) => file.add_variable::<u16>(array_name, &dims)?,
+            (DataKind::UInt, 4) => file.add_variable::<u32>(array_name, &dims)?,
+            (DataKind::UInt, 8) => file.add_variable::<u64>(array_name, &dims)?,
+            (DataKind::Float, 8) => file.add_variable::<f64>(array_name, &dims)?,
+            _ => file.add_variable::<f32>(array_name, &dims)?,
+        };
+
+        // Layout and attributes must be set before any data is written
+        if let Some(chunks) = chunk_shape.filter(|_| !shape.is_empty()) {
+            // NetCDF chunks may not be larger than the dimensions
+            let chunks: Vec<usize> = chunks
+                .iter()
+                .zip(shape)
+                .map(|(&chunk, &len)| chunk.min(len).max(1))
+                .collect();
+            var.set_chunking(&chunks)?;
+        }
+        if let Some(level) = deflate_level {
+            var.set_compression(level, true)?;
+        }
+        if let Some(fill) = fill_value {
+            var.put_attribute("_FillValue", fill_value_attribute(fill, dtype))?;
+        }
+
+        let mut names: Vec<&String> = attributes.keys().collect();
+        names.sort();
+        for name in names
+            .into_iter()
+            .filter(|name| *name != "_ARRAY_DIMENSIONS" && *name != "_FillValue")
+        {
+            match json_to_attribute(&attributes[name]) {
+                Some(value) => {
+                    var.put_attribute(name, value)?;
+                }
+                None => log::warn!("Skipped unsupported attribute type for '{}'", name),
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `data` into the half-open `(start, end)` region of a variable
+    ///
+    /// Values are converted to the variable type by the NetCDF library.
+    pub(crate) fn write_region<T: NcTypeDescriptor + Copy>(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+        data: &ArrayD<T>,
+    ) -> Result<()> {
+        let mut file = netcdf::append(&self.path)?;
+        let mut var =
+            file.variable_mut(array_name)
+                .ok_or_else(|| RuNeVisError::VariableNotFound {
+                    var: array_name.to_string(),
+                })?;
+        write_hyperslab(&mut var, slice_ranges, data)
+    }
+}
+
+#[async_trait]
+impl DataReader for NetCDFDataSource {
+    type ArrayType = ArrayD<f32>;
+
+    async fn list_arrays(&self) -> Result<Vec<String>> {
+        let file = self.file()?;
+        let mut arrays: Vec<String> = file.variables().map(|var| var.name().to_string()).collect();
+        for path in group_paths(&file) {
+            if let Some(group) = file.group(&path)? {
+                arrays.extend(
+                    group
+                        .variables()
+                        .map(|var| format!("{}/{}", path, var.name())),
+                );
+            }
+        }
+        Ok(arrays)
+    }
+
+    async fn get_metadata(&self, array_name: &str) -> Result<DataArrayMetadata> {
+        self.metadata(array_name)
+    }
+
+    async fn read_array(&self, array_name: &str) -> Result<ArrayD<f32>> {
+        let shape = self.metadata(array_name)?.shape;
+        let full: Vec<(usize, usize)> = shape.iter().map(|&len| (0, len)).collect();
+        self.read_region(array_name, &full)
+    }
+
+    async fn read_slice(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+    ) -> Result<ArrayD<f32>> {
+        self.read_region(array_name, slice_ranges)
+    }
+
+    async fn chunk_shape(&self, array_name: &str) -> Result<Option<Vec<usize>>> {
+        self.chunking(array_name)
+    }
+}
+
+#[async_trait]
+impl LazyDataReader for NetCDFDataSource {
+    type LazyArray = NetCDFLazyArray;
+
+    async fn lazy_load(&self, array_name: &str) -> Result<Self::LazyArray> {
+        Ok(NetCDFLazyArray {
+            source: self.clone(),
+            metadata: self.metadata(array_name)?,
+            loaded: None,
+        })
+    }
+}
+
+#[async_trait]
+impl StreamingDataReader for NetCDFDataSource {
+    type ChunkStream = DataChunkStream;
+
+    fn stream_chunks(&self, array_name: &str) -> Self::ChunkStream {
+        let array_name = array_name.to_string();
+        let source = self.clone();
+
+        Box::pin(async_stream::stream! {
+            let grid = match source.chunk_grid(&array_name) {
+                Ok(grid) => grid,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            for index in grid.chunk_indices() {
+                match source.read_region::<f32>(&array_name, &grid.chunk_region(&index)) {
+                    Ok(data) => yield Ok(DataChunk {
+                        offset: grid.chunk_origin(&index),
+                        grid_index: index,
+                        data,
+                    }),
+                    Err(e) => yield Err(e),
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl DataWriter for NetCDFDataSource {
+    /// Add a variable to the file
+    ///
+    /// Dimension names are taken from a `_ARRAY_DIMENSIONS` attribute (the
+    /// Zarr convention) when present, and are `dim_<i>` otherwise, falling
+    /// back to `<array_name>_dim_<i>` when `dim_<i>` exists with another length.
+    async fn write_array(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        chunk_shape: Option<Vec<usize>>,
+        attributes: Option<HashMap<String, JsonValue>>,
+    ) -> Result<()> {
+        let attributes = attributes.unwrap_or_default();
+        let dim_names = {
+            let file = self.file()?;
+            dimension_names(&file, array_name, data.shape(), &attributes)
+        };
+        self.write_variable(
+            array_name,
+            data,
+            &dim_names,
+            chunk_shape.as_deref(),
+            None,
+            &attributes,
+        )
+    }
+
+    async fn write_statistical_result(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        dim_names: &[String],
+        operation: &str,
+        original_array_name: &str,
+        source_metadata: Option<&DataArrayMetadata>,
+    ) -> Result<()> {
+        self.write_statistical_result_with_ddof(
+            array_name,
+            data,
+            dim_names,
+            operation,
+            None,
+            original_array_name,
+            source_metadata,
+        )
+    }
+
+    async fn write_stat_result(
+        &self,
+        result: &StatResult<f32>,
+        source_metadata: Option<&DataArrayMetadata>,
+    ) -> Result<()> {
+        self.write_statistical_result_with_ddof(
+            &result.output_name(),
+            &result.data,
+            &result.remaining_dimensions,
+            result.operation.as_str(),
+            result.operation.ddof(),
+            &result.variable_name,
+            source_metadata,
+        )
+    }
+}
+
+impl NetCDFDataSource {
+    /// Write a statistical result as a new variable, recording the delta
+    /// degrees of freedom of a standard deviation or variance in a `ddof`
+    /// attribute
+    #[allow(clippy::too_many_arguments)]
+    fn write_statistical_result_with_ddof(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        dim_names: &[String],
+        operation: &str,
+        ddof: Option<u32>,
+        original_array_name: &str,
+        source_metadata: Option<&DataArrayMetadata>,
+    ) -> Result<()> {
+        let mut attributes = HashMap::new();
+        attributes.insert("operation".to_string(), json!(operation));
+        if let Some(ddof) = ddof {
+            attributes.insert("ddof".to_string(), json!(ddof_attribute(ddof)?));
+        }
+        attributes.insert("source_array".to_string(), json!(original_array_name));
+        attributes.insert("dimensions".to_string(), json!(dim_names));
+
+        if let Some(metadata) = source_metadata {
+            attributes.insert("source_shape".to_string(), json!(metadata.shape));
+            attributes.insert("source_dtype".to_string(), json!(metadata.dtype));
+            for name in ["units", "long_name", "standard_name", "_FillValue"] {
+                if let Some(value) = metadata.attributes.get(name) {
+                    attributes.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+
+        self.write_variable(array_name, data, dim_names, None, None, &attributes)
+    }
+}
+
+impl FullDataSource for NetCDFDataSource {}
+impl AdvancedDataSource for NetCDFDataSource {}
+
+/// Lazy reference to a NetCDF variable
+///
+/// Holds only the metadata until [`NetCDFLazyArray::load`] reads the whole
+/// variable; [`NetCDFLazyArray::get`] reads windows of it directly from the file.
+#[derive(Debug, Clone)]
+pub struct NetCDFLazyArray {
+    source: NetCDFDataSource,
+    metadata: DataArrayMetadata,
+    loaded: Option<ArrayD<f32>>,
+}
+
+impl NetCDFLazyArray {
+    /// Get variable metadata without loading data
+    pub fn metadata(&self) -> &DataArrayMetadata {
+        &self.metadata
+    }
+
+    /// Read a window of the variable
+    ///
+    /// `slice_ranges` holds one half-open `(start, end)` range per dimension.
+    pub async fn get(&self, slice_ranges: &[(usize, usize)]) -> Result<ArrayD<f32>> {
+        check_slice_ranges(&self.metadata.name, &self.metadata.shape, slice_ranges)?;
+        match &self.loaded {
+            Some(loaded) => Ok(loaded
+                .slice_each_axis(|ax| {
+                    let (start, end) = slice_ranges[ax.axis.index()];
+                    ndarray::Slice::from(start..end)
+                })
+                .to_owned()),
+            None => self.source.read_region(&self.metadata.name, slice_ranges),
+        }
+    }
+
+    /// Load the variable data if not already loaded
+    pub async fn load(&mut self) -> Result<&ArrayD<f32>> {
+        if self.loaded.is_none() {
+            let data = self.source.read_array(&self.metadata.name).await?;
+            self.loaded = Some(data);
+        }
+        Ok(self.loaded.as_ref().unwrap())
+    }
+
+    /// Check if data is loaded
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.is_some()
+    }
+
+    /// Get shape without loading data
+    pub fn shape(&self) -> &[usize] {
+        &self.metadata.shape
+    }
+}
+
+/// Read the half-open `(start, end)` hyperslab of a variable as values of type `T`
+///
+/// The ranges must lie within the variable bounds.
+pub(crate) fn read_hyperslab<T: NcTypeDescriptor + Copy>(
+    var: &Variable,
+    slice_ranges: &[(usize, usize)],
+) -> Result<ArrayD<T>> {
+    let region_shape: Vec<usize> = slice_ranges
+        .iter()
+        .map(|&(start, end)| end - start)
+        .collect();
+    if region_shape.contains(&0) {
+        return Ok(ArrayD::from_shape_vec(IxDyn(&region_shape), Vec::new())?);
+    }
+    let values = if slice_ranges.is_empty() {
+        var.get_values::<T, _>(..)?
+    } else {
+        var.get_values::<T, _>(extents(slice_ranges))?
+    };
+    Ok(ArrayD::from_shape_vec(IxDyn(&region_shape), values)?)
+}
+
+/// Write `data` into the half-open `(start, end)` hyperslab of a variable
+///
+/// `data` must have the shape of the hyperslab, which must lie within the
+/// variable bounds. Values are converted to the variable type by the NetCDF
+/// library.
+pub(crate) fn write_hyperslab<T: NcTypeDescriptor + Copy>(
+    var: &mut VariableMut<'_>,
+    slice_ranges: &[(usize, usize)],
+    data: &ArrayD<T>,
+) -> Result<()> {
+    if data.is_empty() {
+        return Ok(());
+    }
+    let data = data.as_standard_layout();
+    if slice_ranges.is_empty() {
+        var.put(data.view(), ..)?;
+    } else {
+        var.put(data.view(), extents(slice_ranges))?;
+    }
+    Ok(())
+}
+
+/// NetCDF extents of half-open `(start, end)` ranges
+fn extents(slice_ranges: &[(usize, usize)]) -> Vec<Extent> {
+    slice_ranges
+        .iter()
+        .map(|&(start, end)| Extent::from(start..end))
+        .collect()
+}
+
+fn find_variable<'f>(file: &'f File, name: &str) -> Result<Variable<'f>> {
+    file.variable(name)
+        .ok_or_else(|| RuNeVisError::VariableNotFound {
+            var: name.to_string(),
+        })
+}
+
+/// Paths of the groups of a NetCDF-4 file, parents before their subgroups
+fn group_paths(file: &File) -> Vec<String> {
+    fn collect(group: &netcdf::Group, prefix: &str, paths: &mut Vec<String>) {
+        for child in group.groups() {
+            let path = if prefix.is_empty() {
+                child.name()
+            } else {
+                format!("{}/{}", prefix, child.name())
+            };
+            paths.push(path.clone());
+            collect(&child, &path, paths);
+        }
+    }
+
+    let mut paths = Vec::new();
+    if let Some(root) = file.root() {
+        collect(&root, "", &mut paths);
+    }
+    paths
+}
+
+/// Metadata of a variable with its dimension names and attributes
+fn variable_metadata(var: &Variable) -> Result<DataArrayMetadata> {
+    let attributes = attributes_to_json(var.attributes())?;
+    Ok(DataArrayMetadata {
+        name: var.name().to_string(),
+        shape: var
+            .dimensions()
+            .iter()
+            .map(netcdf::Dimension::len)
+            .collect(),
+        dtype: dtype_name(&var.vartype()),
+        dimensions: var
+            .dimensions()
+            .iter()
+            .map(|d| d.name().to_string())
+            .collect(),
+        attributes,
+    })
+}
+
+/// Element type of a numeric NetCDF variable type, `None` for character,
+/// string and user-defined types
+pub(crate) fn netcdf_data_type(vartype: &NcVariableType) -> Option<DataType> {
+    let (kind, size) = match vartype {
+        NcVariableType::Int(IntType::I8) => (DataKind::Int, 1),
+        NcVariableType::Int(IntType::I16) => (DataKind::Int, 2),
+        NcVariableType::Int(IntType::I32) => (DataKind::Int, 4),
+        NcVariableType::Int(IntType::I64) => (DataKind::Int, 8),
+        NcVariableType::Int(IntType::U8) => (DataKind::UInt, 1),
+        NcVariableType::Int(IntType::U16) => (DataKind::UInt, 2),
+        NcVariableType::Int(IntType::U32) => (DataKind::UInt, 4),
+        NcVariableType::Int(IntType::U64) => (DataKind::UInt, 8),
+        NcVariableType::Float(FloatType::F32) => (DataKind::Float, 4),
+        NcVariableType::Float(FloatType::F64) => (DataKind::Float, 8),
+        _ => return None,
+    };
+    Some(DataType {
+        kind,
+        size,
+        endianness: Endianness::Little,
+    })
+}
+
+/// NumPy-style name of a NetCDF variable type, as reported for Zarr arrays
+///
+/// Numeric types map to their type string (`<i2`, `<f4`, ...), characters to
+/// `|S1`, strings to `|O` and user-defined types to raw `|V<size>` elements.
+fn dtype_name(vartype: &NcVariableType) -> String {
+    match netcdf_data_type(vartype) {
+        Some(dtype) => dtype.to_type_string(),
+        None => match vartype {
+            NcVariableType::Char => "|S1".to_string(),
+            NcVariableType::String => "|O".to_string(),
+            other => format!("|V{}", other.size()),
+        },
+    }
+}
+
+/// JSON values of attributes
+fn attributes_to_json<'a>(
+    attributes: impl Iterator<Item = netcdf::Attribute<'a>>,
+) -> Result<HashMap<String, JsonValue>> {
+    let mut values = HashMap::new();
+    for attr in attributes {
+        values.insert(attr.name().to_string(), attribute_to_json(attr.value()?));
+    }
+    Ok(values)
+}
+
+/// Default dimension names of a new variable, see [`NetCDFDataSource::write_array`]
+fn dimension_names(
+    file: &File,
+    array_name: &str,
+    shape: &[usize],
+    attributes: &HashMap<String, JsonValue>,
+) -> Vec<String> {
+    if let Some(JsonValue::Array(names)) = attributes.get("_ARRAY_DIMENSIONS") {
+        let names: Vec<String> = names
+            .iter()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect();
+        if names.len() == shape.len() {
+            return names;
+        }
+    }
+
+    shape
+        .iter()
+        .enumerate()
+        .map(|(axis, &len)| {
+            let name = format!("dim_{}", axis);
+            match file.dimension(&name).map(|dim| dim.len()) {
+                Some(existing) if existing != len => format!("{}_dim_{}", array_name, axis),
+                _ => name,
+            }
+        })
+        .collect()
+}
+
+fn attribute_to_json(value: AttributeValue) -> JsonValue {
+    match value {
+        AttributeValue::Str(v) => json!(v),
+        AttributeValue::Strs(v) => json!(v),
+        AttributeValue::Float(v) => json!(v),
+        AttributeValue::Floats(v) => json!(v),
+        AttributeValue::Double(v) => json!(v),
+        AttributeValue::Doubles(v) => json!(v),
+        AttributeValue::Int(v) => json!(v),
+        AttributeValue::Ints(v) => json!(v),
+        AttributeValue::Short(v) => json!(v),
+        AttributeValue::Shorts(v) => json!(v),
+        AttributeValue::Schar(v) => json!(v),
+        AttributeValue::Schars(v) => json!(v),
+        AttributeValue::Uchar(v) => json!(v),
+        AttributeValue::Uchars(v) => json!(v),
+        AttributeValue::Ushort(v) => json!(v),
+        AttributeValue::Ushorts(v) => json!(v),
+        AttributeValue::Uint(v) => json!(v),
+        AttributeValue::Uints(v) => json!(v),
+        AttributeValue::Longlong(v) => json!(v),
+        AttributeValue::Longlongs(v) => json!(v),
+        AttributeValue::Ulonglong(v) => json!(v),
+        AttributeValue::Ulonglongs(v) => json!(v),
+    }
+}
+
+/// `_FillValue` attribute of a variable with elements of `dtype`, which must have that type
+fn fill_value_attribute(fill: f64, dtype: DataType) -> AttributeValue {
+    match (dtype.kind, dtype.size) {
+        (DataKind::Bool, _) | (DataKind::UInt, 1) => AttributeValue::Uchar(fill as u8),
+        (DataKind::Int, 1) => AttributeValue::Schar(fill as i8),
+        (DataKind::Int, 2) => AttributeValue::Short(fill as i16),
+        (DataKind::Int, 4) => AttributeValue::Int(fill as i32),
+        (DataKind::Int, 8) => AttributeValue::Longlong(fill as i64),
+        (DataKind::UInt, 2) => AttributeValue::Ushort(fill as u16),
+        (DataKind::UInt, 4) => AttributeValue::Uint(fill as u32),
+        (DataKind::UInt, 8) => AttributeValue::Ulonglong(fill as u64),
+        (DataKind::Float, 8) => AttributeValue::Double(fill),
+        _ => AttributeValue::Float(fill as f32),
+    }
+}
+
+/// Value of the `ddof` attribute, stored as a NetCDF `int` by every writer
+fn ddof_attribute(ddof: u32) -> Result<i32> {
+    i32::try_from(ddof).map_err(|_| {
+        RuNeVisError::StatisticsError(format!("ddof {} does not fit a NetCDF int attribute", ddof))
+    })
+}
+
+/// NetCDF attribute holding a JSON value, `None` for objects, nulls and mixed arrays
+fn json_to_attribute(value: &JsonValue) -> Option<AttributeValue> {
+    match value {
+        JsonValue::String(s) => Some(AttributeValue::Str(s.clone())),
+        JsonValue::Bool(b) => Some(AttributeValue::Int(i32::from(*b))),
+        JsonValue::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => Some(match i32::try_from(i) {
+                Ok(i) => AttributeValue::Int(i),
+                Err(_) => AttributeValue::Longlong(i),
+            }),
+            (None, Some(u)) => Some(AttributeValue::Ulonglong(u)),
+            (None, None) => Some(AttributeValue::Double(n.as_f64()?)),
+        },
+        JsonValue::Array(items) if !items.is_empty() => {
+            if let Some(strings) = items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+            {
+                return Some(AttributeValue::Strs(strings));
+            }
+            if let Some(ints) = items
+                .iter()
+                .map(JsonValue::as_i64)
+                .collect::<Option<Vec<_>>>()
+            {
+                return Some(
+                    match ints
+                        .iter()
+                        .map(|&i| i32::try_from(i).ok())
+                        .collect::<Option<Vec<_>>>()
+                    {
+                        Some(ints) => AttributeValue::Ints(ints),
+                        None => AttributeValue::Longlongs(ints),
+                    },
+                );
+            }
+            if let Some(uints) = items
+                .iter()
+                .map(JsonValue::as_u64)
+                .collect::<Option<Vec<_>>>()
+            {
+                return Some(AttributeValue::Ulonglongs(uints));
+            }
+            items
+                .iter()
+                .map(JsonValue::as_f64)
+                .collect::<Option<Vec<_>>>()
+                .map(AttributeValue::Doubles)
+        }
+        _ => None,
+    }
+}
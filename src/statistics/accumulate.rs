@@ -0,0 +1,693 @@
+//! Out-of-core reductions by chunk-wise accumulation
+//!
+//! A [`ChunkAccumulator`] keeps one partial state per element of the reduced
+//! output (sum and count for means and sums, running minima and maxima,
+//! [`Moments`] for standard deviations and variances, [`QuantileSketch`]es
+//! for medians) and folds in the chunks of an array one at a time. Memory use
+//! grows with the output shape and one chunk, not with the length of the
+//! reduced dimension, so a mean over time of a store far larger than RAM runs
+//! with [`streaming_reduce_over_dimension`] in memory independent of the
+//! number of time steps. The partial results are checked against a memory
+//! budget ([`DEFAULT_MEMORY_BUDGET`] by default) before any chunk is read;
+//! median sketches take up to a few kilobytes per output element. Arrays that
+//! can be read by region are instead reduced block by block within the
+//! budget, exactly while a lane along the reduced dimension fits in it.
+
+use super::moments::Moments;
+use super::operations::{StatOperation, StatResult, StatisticalReduction};
+use super::sketch::QuantileSketch;
+use crate::data_source::{DataChunk, DataChunkStream, StreamingDataReader};
+use crate::errors::{Result, RuNeVisError};
+use crate::zarr_io::chunks::{ChunkGrid, ChunkOrder};
+use futures::StreamExt;
+use ndarray::{ArrayD, Axis, IxDyn, Slice, Zip};
+use rayon::prelude::*;
+
+/// Partial results of a reduction, one entry per output element
+#[derive(Debug, Clone)]
+enum PartialState {
+    /// Sum and count of the finite values, for means and sums
+    Sum {
+        sum: ArrayD<f64>,
+        count: ArrayD<u64>,
+    },
+    /// Smallest finite value, `+inf` while none was seen
+    Min(ArrayD<f32>),
+    /// Largest finite value, `-inf` while none was seen
+    Max(ArrayD<f32>),
+    /// Running moments of the finite values, for standard deviations and variances
+    Moments(ArrayD<Moments>),
+    /// Sketch of the finite values, for medians
+    Quantiles(ArrayD<QuantileSketch>),
+}
+
+/// Accumulates a reduction along one axis of an array from its chunks
+///
+/// Non-finite values are skipped, like in the in-memory reductions. Chunks
+/// can be added in any order, and accumulators fed with different chunks of
+/// the same array can be merged, e.g. one per worker.
+#[derive(Debug, Clone)]
+pub struct ChunkAccumulator {
+    shape: Vec<usize>,
+    axis: usize,
+    operation: StatOperation,
+    state: PartialState,
+}
+
+impl ChunkAccumulator {
+    /// Create an accumulator reducing an array of `shape` along `axis`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the axis is out of bounds for the array.
+    pub fn new(shape: &[usize], axis: usize, operation: StatOperation) -> Result<Self> {
+        if axis >= shape.len() {
+            return Err(RuNeVisError::StatisticsError(format!(
+                "Axis {axis} is out of bounds for array with {} dimensions",
+                shape.len()
+            )));
+        }
+
+        let mut out_shape = shape.to_vec();
+        out_shape.remove(axis);
+        let out_dim = IxDyn(&out_shape);
+        let state = match operation {
+            StatOperation::Mean | StatOperation::Sum => PartialState::Sum {
+                sum: ArrayD::zeros(out_dim.clone()),
+                count: ArrayD::zeros(out_dim),
+            },
+            StatOperation::Min => PartialState::Min(ArrayD::from_elem(out_dim, f32::INFINITY)),
+            StatOperation::Max => PartialState::Max(ArrayD::from_elem(out_dim, f32::NEG_INFINITY)),
+            StatOperation::Std { .. } | StatOperation::Var { .. } => {
+                PartialState::Moments(ArrayD::from_elem(out_dim, Moments::new()))
+            }
+            StatOperation::Median => {
+                PartialState::Quantiles(ArrayD::from_elem(out_dim, QuantileSketch::default()))
+            }
+        };
+
+        Ok(Self {
+            shape: shape.to_vec(),
+            axis,
+            operation,
+            state,
+        })
+    }
+
+    /// The operation being accumulated
+    pub fn operation(&self) -> StatOperation {
+        self.operation
+    }
+
+    /// Shape of the reduced output
+    pub fn output_shape(&self) -> Vec<usize> {
+        let mut out_shape = self.shape.clone();
+        out_shape.remove(self.axis);
+        out_shape
+    }
+
+    /// Upper estimate of the memory held by the partial results, in bytes
+    ///
+    /// Median sketches are counted at their largest size.
+    pub fn state_bytes(&self) -> usize {
+        self.output_shape().iter().product::<usize>() * state_bytes_per_element(self.operation)
+    }
+
+    /// Fold a chunk into the partial results
+    ///
+    /// Every lane of the chunk along the reduced axis updates one output
+    /// element; lanes are processed in parallel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chunk does not lie within the array.
+    pub fn add_chunk(&mut self, chunk: &DataChunk) -> Result<()> {
+        let region = chunk.region();
+        let in_bounds = region.len() == self.shape.len()
+            && region
+                .iter()
+                .zip(&self.shape)
+                .all(|(&(_, end), &len)| end <= len);
+        if !in_bounds {
+            return Err(RuNeVisError::StatisticsError(format!(
+                "Chunk at {:?} with shape {:?} does not fit an array of shape {:?}",
+                chunk.offset,
+                chunk.shape(),
+                self.shape
+            )));
+        }
+
+        let mut out_region = region;
+        out_region.remove(self.axis);
+        let slice = |ax: ndarray::AxisDescription| {
+            let (start, end) = out_region[ax.axis.index()];
+            Slice::from(start..end)
+        };
+        let lanes = chunk.data.lanes(Axis(self.axis));
+
+        match &mut self.state {
+            PartialState::Sum { sum, count } => {
+                Zip::from(sum.slice_each_axis_mut(slice))
+                    .and(count.slice_each_axis_mut(slice))
+                    .and(lanes)
+                    .par_for_each(|sum, count, lane| {
+                        for &value in lane.iter().filter(|v| v.is_finite()) {
+                            *sum += f64::from(value);
+                            *count += 1;
+                        }
+                    });
+            }
+            PartialState::Min(min) => {
+                Zip::from(min.slice_each_axis_mut(slice))
+                    .and(lanes)
+                    .par_for_each(|min, lane| {
+                        for &value in lane.iter().filter(|v| v.is_finite()) {
+                            *min = min.min(value);
+                        }
+                    });
+            }
+            PartialState::Max(max) => {
+                Zip::from(max.slice_each_axis_mut(slice))
+                    .and(lanes)
+                    .par_for_each(|max, lane| {
+                        for &value in lane.iter().filter(|v| v.is_finite()) {
+                            *max = max.max(value);
+                        }
+                    });
+            }
+            PartialState::Moments(moments) => {
+                Zip::from(moments.slice_each_axis_mut(slice))
+                    .and(lanes)
+                    .par_for_each(|moments, lane| {
+                        // Welford within the chunk, Chan to combine with earlier chunks
+                        let mut chunk_moments = Moments::new();
+                        for &value in lane.iter().filter(|v| v.is_finite()) {
+                            chunk_moments.add(f64::from(value));
+                        }
+                        moments.merge(&chunk_moments);
+                    });
+            }
+            PartialState::Quantiles(sketches) => {
+                Zip::from(sketches.slice_each_axis_mut(slice))
+                    .and(lanes)
+                    .par_for_each(|sketch, lane| {
+                        for &value in lane.iter().filter(|v| v.is_finite()) {
+                            sketch.add(f64::from(value));
+                        }
+                    });
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge the partial results of another accumulator of the same reduction
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the accumulators reduce different arrays, axes or operations.
+    pub fn merge(&mut self, other: &ChunkAccumulator) -> Result<()> {
+        if self.shape != other.shape || self.axis != other.axis || self.operation != other.operation
+        {
+            return Err(RuNeVisError::StatisticsError(format!(
+                "Cannot merge a {} over axis {} of {:?} into a {} over axis {} of {:?}",
+                other.operation.as_str(),
+                other.axis,
+                other.shape,
+                self.operation.as_str(),
+                self.axis,
+                self.shape
+            )));
+        }
+
+        match (&mut self.state, &other.state) {
+            (
+                PartialState::Sum { sum, count },
+                PartialState::Sum {
+                    sum: other_sum,
+                    count: other_count,
+                },
+            ) => {
+                *sum += other_sum;
+                *count += other_count;
+            }
+            (PartialState::Min(min), PartialState::Min(other_min)) => {
+                Zip::from(min)
+                    .and(other_min)
+                    .for_each(|a, &b| *a = a.min(b));
+            }
+            (PartialState::Max(max), PartialState::Max(other_max)) => {
+                Zip::from(max)
+                    .and(other_max)
+                    .for_each(|a, &b| *a = a.max(b));
+            }
+            (PartialState::Moments(moments), PartialState::Moments(other_moments)) => {
+                Zip::from(moments)
+                    .and(other_moments)
+                    .for_each(|a, b| a.merge(b));
+            }
+            (PartialState::Quantiles(sketches), PartialState::Quantiles(other_sketches)) => {
+                Zip::from(sketches)
+                    .and(other_sketches)
+                    .par_for_each(|a, b| a.merge(b));
+            }
+            _ => unreachable!("accumulators of the same operation share a state kind"),
+        }
+        Ok(())
+    }
+
+    /// Final values of the reduction
+    ///
+    /// Output elements without any finite value are NaN, except for sums,
+    /// which are 0. Standard deviations and variances are also NaN with no
+    /// more than `ddof` values.
+    pub fn finish(self) -> ArrayD<f32> {
+        match self.state {
+            PartialState::Sum { sum, count } => match self.operation {
+                StatOperation::Sum => sum.mapv(|s| s as f32),
+                _ => Zip::from(&sum).and(&count).map_collect(|&s, &n| {
+                    if n > 0 {
+                        (s / n as f64) as f32
+                    } else {
+                        f32::NAN
+                    }
+                }),
+            },
+            PartialState::Min(min) => min.mapv(|x| if x == f32::INFINITY { f32::NAN } else { x }),
+            PartialState::Max(max) => {
+                max.mapv(|x| if x == f32::NEG_INFINITY { f32::NAN } else { x })
+            }
+            PartialState::Moments(moments) => {
+                let ddof = self.operation.ddof().unwrap_or(0);
+                let std = matches!(self.operation, StatOperation::Std { .. });
+                moments.mapv(|m| {
+                    let value = if std { m.std(ddof) } else { m.variance(ddof) };
+                    value.map_or(f32::NAN, |v| v as f32)
+                })
+            }
+            PartialState::Quantiles(mut sketches) => Zip::from(&mut sketches)
+                .par_map_collect(|sketch| sketch.quantile(0.5).map_or(f32::NAN, |q| q as f32)),
+        }
+    }
+}
+
+/// Upper estimate of the partial results of `operation` for one output element, in bytes
+fn state_bytes_per_element(operation: StatOperation) -> usize {
+    match operation {
+        StatOperation::Mean | StatOperation::Sum => {
+            std::mem::size_of::<f64>() + std::mem::size_of::<u64>()
+        }
+        StatOperation::Min | StatOperation::Max => std::mem::size_of::<f32>(),
+        StatOperation::Std { .. } | StatOperation::Var { .. } => std::mem::size_of::<Moments>(),
+        StatOperation::Median => QuantileSketch::default().max_bytes(),
+    }
+}
+
+/// Upper estimate of the partial results of reducing an array of `shape`
+/// along `axis`, in bytes, without allocating them
+pub(crate) fn partial_state_bytes(shape: &[usize], axis: usize, operation: StatOperation) -> usize {
+    let output_elements: usize = shape
+        .iter()
+        .enumerate()
+        .filter(|&(dim, _)| dim != axis)
+        .map(|(_, &len)| len)
+        .product();
+    output_elements * state_bytes_per_element(operation)
+}
+
+/// Default memory budget of a reduction: 1 GiB of `f32` data
+///
+/// Arrays larger than the budget are reduced block by block.
+pub const DEFAULT_MEMORY_BUDGET: usize = 1024 * 1024 * 1024;
+
+/// Reduction of an array read in blocks that fit a memory budget
+///
+/// Blocks are made of whole storage chunks where they fit. While a single lane
+/// along the reduced axis fits in the budget, blocks span that axis whole and
+/// each is reduced in memory into its slice of the output, so every operation,
+/// medians included, matches the in-memory reduction. Otherwise the axis is
+/// split too and the blocks are folded one at a time into a single
+/// [`ChunkAccumulator`], whose partial results may take at most half the
+/// budget, the rest going to the blocks; medians are then estimated with a
+/// [`QuantileSketch`] and a warning is logged.
+pub(crate) struct BlockReduction {
+    grid: ChunkGrid,
+    axis: usize,
+    operation: StatOperation,
+    concurrent_blocks: usize,
+    target: BlockTarget,
+}
+
+/// Where the blocks of a [`BlockReduction`] end up
+enum BlockTarget {
+    /// Output filled block by block with exact reductions
+    Exact(ArrayD<f32>),
+    /// Partial results of blocks that split the reduced axis
+    Accumulated(ChunkAccumulator),
+}
+
+impl BlockReduction {
+    /// Plan the reduction of an array of `shape` along `axis` in `memory_budget` bytes
+    ///
+    /// Up to `max_concurrent_blocks` blocks are sized to be in memory at once,
+    /// besides the output. Blocks are made of whole chunks of the storage
+    /// `chunks` where given, so no chunk is read by two blocks unless a
+    /// single chunk exceeds the budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the axis is out of bounds, the array is empty, or
+    /// the axis must be split and half the budget cannot hold the partial
+    /// results.
+    pub(crate) fn new(
+        shape: &[usize],
+        axis: usize,
+        chunks: Option<&[usize]>,
+        operation: StatOperation,
+        memory_budget: usize,
+        max_concurrent_blocks: usize,
+    ) -> Result<Self> {
+        if axis >= shape.len() {
+            return Err(RuNeVisError::StatisticsError(format!(
+                "Axis {axis} is out of bounds for array with {} dimensions",
+                shape.len()
+            )));
+        }
+        if shape.contains(&0) {
+            return Err(RuNeVisError::StatisticsError(format!(
+                "Cannot reduce an empty array of shape {shape:?} block by block"
+            )));
+        }
+
+        let budget_elements = memory_budget / std::mem::size_of::<f32>();
+        let split_axis = shape[axis] > budget_elements;
+        let (target, block_elements, concurrent_blocks) = if !split_axis {
+            let concurrent_blocks =
+                (budget_elements / shape[axis]).clamp(1, max_concurrent_blocks.max(1));
+            let mut out_shape = shape.to_vec();
+            out_shape.remove(axis);
+            let output = ArrayD::from_elem(IxDyn(&out_shape), f32::NAN);
+            (
+                BlockTarget::Exact(output),
+                budget_elements / concurrent_blocks,
+                concurrent_blocks,
+            )
+        } else {
+            let accumulator = ChunkAccumulator::new(shape, axis, operation)?;
+            let state_bytes = accumulator.state_bytes();
+            if state_bytes > memory_budget / 2 {
+                return Err(RuNeVisError::StatisticsError(format!(
+                    "A memory budget of {memory_budget} bytes cannot hold the {state_bytes} bytes of partial results of the {} along axis {axis} besides the blocks read",
+                    operation.as_str()
+                )));
+            }
+            if operation == StatOperation::Median {
+                log::warn!(
+                    "A lane of {} values along axis {axis} exceeds the memory budget of {memory_budget} bytes: medians are estimated",
+                    shape[axis]
+                );
+            }
+            let block_bytes = memory_budget - state_bytes;
+            (
+                BlockTarget::Accumulated(accumulator),
+                block_bytes / std::mem::size_of::<f32>(),
+                1,
+            )
+        };
+
+        let block_shape = reduction_block_shape(shape, axis, chunks, block_elements, split_axis);
+        Ok(Self {
+            grid: ChunkGrid::new(shape.to_vec(), block_shape, ".", ChunkOrder::C)?,
+            axis,
+            operation,
+            concurrent_blocks,
+            target,
+        })
+    }
+
+    /// Plan the reduction of an array of `shape` along the dimension `dim_name`
+    /// at `axis`, or `None` if its `f32` data fits in `memory_budget` and is
+    /// read whole
+    ///
+    /// Arguments are otherwise those of [`BlockReduction::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the array must be read in blocks and
+    /// [`BlockReduction::new`] fails.
+    pub(crate) fn plan(
+        shape: &[usize],
+        axis: usize,
+        dim_name: &str,
+        chunks: Option<&[usize]>,
+        operation: StatOperation,
+        memory_budget: usize,
+        max_concurrent_blocks: usize,
+    ) -> Result<Option<Self>> {
+        let operation_name = operation.as_str();
+        let data_bytes = shape.iter().product::<usize>() * std::mem::size_of::<f32>();
+        if data_bytes <= memory_budget || shape.contains(&0) {
+            log::info!(
+                "Loading data array with shape {shape:?} to compute {operation_name} over dimension '{dim_name}'"
+            );
+            return Ok(None);
+        }
+
+        let reduction = Self::new(
+            shape,
+            axis,
+            chunks,
+            operation,
+            memory_budget,
+            max_concurrent_blocks,
+        )?;
+        log::info!(
+            "Array of {} MiB exceeds the memory budget of {} MiB: computing {operation_name} over dimension '{dim_name}' in slices of shape {:?}",
+            data_bytes / (1024 * 1024),
+            memory_budget / (1024 * 1024),
+            reduction.block_shape()
+        );
+        Ok(Some(reduction))
+    }
+
+    /// Shape of the blocks
+    pub(crate) fn block_shape(&self) -> &[usize] {
+        self.grid.chunks()
+    }
+
+    /// Regions of the blocks, in C order of the block grid
+    pub(crate) fn regions(&self) -> Vec<Vec<(usize, usize)>> {
+        self.grid
+            .chunk_indices()
+            .iter()
+            .map(|index| self.grid.chunk_region(index))
+            .collect()
+    }
+
+    /// Fold in the values of the block covering `region`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the block does not match a region of the array.
+    pub(crate) fn add_block(
+        &mut self,
+        region: &[(usize, usize)],
+        block: ArrayD<f32>,
+    ) -> Result<()> {
+        match &mut self.target {
+            BlockTarget::Exact(output) => {
+                let reduced = block.reduce_along_axis(self.axis, self.operation)?;
+                let mut out_region = region.to_vec();
+                out_region.remove(self.axis);
+                output
+                    .slice_each_axis_mut(|ax| {
+                        let (start, end) = out_region[ax.axis.index()];
+                        Slice::from(start..end)
+                    })
+                    .assign(&reduced);
+            }
+            BlockTarget::Accumulated(accumulator) => {
+                let chunk = DataChunk {
+                    grid_index: region
+                        .iter()
+                        .zip(self.grid.chunks())
+                        .map(|(&(start, _), &len)| start / len)
+                        .collect(),
+                    offset: region.iter().map(|&(start, _)| start).collect(),
+                    data: block,
+                };
+                accumulator.add_chunk(&chunk)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read every block with `read`, as many at a time on the rayon pool as
+    /// fit in the budget together, and return the reduced output
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading or reducing a block fails.
+    pub(crate) fn reduce_with<F>(mut self, read: F) -> Result<ArrayD<f32>>
+    where
+        F: Fn(&[(usize, usize)]) -> Result<ArrayD<f32>> + Sync,
+    {
+        let regions = self.regions();
+        for batch in regions.chunks(self.concurrent_blocks) {
+            let blocks = batch
+                .par_iter()
+                .map(|region| read(region))
+                .collect::<Result<Vec<_>>>()?;
+            for (region, block) in batch.iter().zip(blocks) {
+                self.add_block(region, block)?;
+            }
+        }
+        Ok(self.finish())
+    }
+
+    /// Final values of the reduction
+    pub(crate) fn finish(self) -> ArrayD<f32> {
+        match self.target {
+            BlockTarget::Exact(output) => output,
+            BlockTarget::Accumulated(accumulator) => accumulator.finish(),
+        }
+    }
+}
+
+/// Names of the dimensions left after reducing the one at `axis`
+pub(crate) fn kept_dimensions(dimensions: Vec<String>, axis: usize) -> Vec<String> {
+    dimensions
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, name)| if i == axis { None } else { Some(name) })
+        .collect()
+}
+
+/// Shape of the blocks of a [`BlockReduction`]
+///
+/// The other dimensions are cut from the outermost inwards, keeping inner
+/// dimensions whole for contiguous reads, then the reduced axis if
+/// `split_axis`, until a block holds at most `max_elements` values. Cuts
+/// first keep whole `chunks`, like the blocks of a conversion, and only go
+/// below one chunk, down to a single value, when a chunk alone is too large.
+fn reduction_block_shape(
+    shape: &[usize],
+    axis: usize,
+    chunks: Option<&[usize]>,
+    max_elements: usize,
+    split_axis: bool,
+) -> Vec<usize> {
+    let mut block = shape.to_vec();
+    let others = (0..shape.len()).filter(|&dim| dim != axis);
+    let dims: Vec<usize> = others.chain(Some(axis).filter(|_| split_axis)).collect();
+
+    if let Some(chunks) = chunks.filter(|chunks| chunks.len() == shape.len()) {
+        for &dim in &dims {
+            let total: usize = block.iter().product();
+            if total <= max_elements {
+                return block;
+            }
+            let chunk = chunks[dim].clamp(1, shape[dim]);
+            let others = total / block[dim];
+            block[dim] = ((max_elements / others / chunk).max(1) * chunk).min(shape[dim]);
+        }
+    }
+    for &dim in &dims {
+        let total: usize = block.iter().product();
+        if total <= max_elements {
+            break;
+        }
+        let others = total / block[dim];
+        block[dim] = (max_elements / others).clamp(1, block[dim]);
+    }
+    block
+}
+
+/// Computes a statistic over a named dimension chunk by chunk
+///
+/// The partial results may take up to [`DEFAULT_MEMORY_BUDGET`], as described
+/// in [`streaming_reduce_over_dimension_with_budget`].
+///
+/// # Errors
+///
+/// Returns an error if the array or dimension is not found, if the partial
+/// results exceed the budget, or if reading a chunk fails.
+pub async fn streaming_reduce_over_dimension<R>(
+    reader: &R,
+    array_name: &str,
+    dim_name: &str,
+    operation: StatOperation,
+) -> Result<StatResult<f32>>
+where
+    R: StreamingDataReader<ChunkStream = DataChunkStream> + Sync + ?Sized,
+{
+    streaming_reduce_over_dimension_with_budget(
+        reader,
+        array_name,
+        dim_name,
+        operation,
+        DEFAULT_MEMORY_BUDGET,
+    )
+    .await
+}
+
+/// Computes a statistic over a named dimension chunk by chunk within a memory budget
+///
+/// The chunks of `stream_chunks` are folded into a [`ChunkAccumulator`] as
+/// they arrive, so only one chunk and the partial results are in memory at a
+/// time. The partial results grow with the output, from 4 bytes per element
+/// for minima and maxima to several kilobytes for median sketches, and must
+/// fit in `memory_budget` bytes; this is checked before any chunk is read.
+/// Means, sums, minima and maxima match the in-memory reductions up to
+/// floating-point rounding; medians are estimated with a [`QuantileSketch`]
+/// and exact while each output element has fewer than 200 finite values.
+///
+/// # Errors
+///
+/// Returns an error if the array or dimension is not found, if the partial
+/// results exceed `memory_budget`, or if reading a chunk fails.
+pub async fn streaming_reduce_over_dimension_with_budget<R>(
+    reader: &R,
+    array_name: &str,
+    dim_name: &str,
+    operation: StatOperation,
+    memory_budget: usize,
+) -> Result<StatResult<f32>>
+where
+    R: StreamingDataReader<ChunkStream = DataChunkStream> + Sync + ?Sized,
+{
+    let metadata = reader.get_metadata(array_name).await?;
+    let axis_index = metadata.dimension_index(dim_name)?;
+    let state_bytes = partial_state_bytes(&metadata.shape, axis_index, operation);
+    if state_bytes > memory_budget {
+        return Err(RuNeVisError::StatisticsError(format!(
+            "A memory budget of {memory_budget} bytes cannot hold the {state_bytes} bytes of partial results of the {} over dimension '{dim_name}'",
+            operation.as_str()
+        )));
+    }
+    let mut accumulator = ChunkAccumulator::new(&metadata.shape, axis_index, operation)?;
+
+    log::info!(
+        "Streaming {} over dimension '{dim_name}' of '{array_name}' with shape {:?}",
+        operation.as_str(),
+        metadata.shape
+    );
+    let mut chunks = reader.stream_chunks(array_name);
+    while let Some(chunk) = chunks.next().await {
+        accumulator.add_chunk(&chunk?)?;
+    }
+
+    let kept_dim_names: Vec<String> = metadata
+        .dimensions
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, name)| if i == axis_index { None } else { Some(name) })
+        .collect();
+
+    Ok(StatResult::new(
+        accumulator.finish(),
+        kept_dim_names,
+        operation,
+        array_name.to_string(),
+        dim_name.to_string(),
+    ))
+}
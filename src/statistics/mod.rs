@@ -1,25 +1,41 @@
 //! Statistical computations and parallel reduction operations
 //!
-//! This module provides functions for computing statistical reductions (mean, sum, min, max)
+//! This module provides functions for computing statistical reductions (mean, sum, min, max,
+//! median, standard deviation, variance)
 //! over specified dimensions of `NetCDF` variables and Zarr arrays using parallel processing.
+//! [`reduce_over_dimension`] works on any [`DataReader`](crate::data_source::DataReader),
+//! whatever the file format, and [`streaming_reduce_over_dimension`] reduces arrays
+//! chunk by chunk in constant memory.
 //!
 //! # Organization
 //!
 //! This module is organized into submodules:
+//! - [`accumulate`]: Chunk-wise accumulators for out-of-core reductions
+//! - [`moments`]: Mergeable running moments for standard deviations and variances
 //! - [`operations`]: Core statistical operations and traits
 //! - [`parallel`]: Parallel computation implementations
+//! - [`sketch`]: Mergeable quantile sketches for streaming medians
+//! - [`source`]: Format-agnostic reductions over any data source
 //! - [`netcdf`]: NetCDF-specific statistical functions
-//! - [`zarr`]: Zarr-specific statistical functions (future implementation)
+//! - [`zarr`]: Zarr-specific statistical functions
 
+pub mod accumulate;
+pub mod moments;
 pub mod netcdf;
 pub mod operations;
 pub mod parallel;
+pub mod sketch;
+pub mod source;
 pub mod zarr;
 
 // Re-export the main types and functions for convenience
-pub use netcdf::{max_over_dimension, mean_over_dimension, median_over_dimension, min_over_dimension, sum_over_dimension};
+pub use accumulate::{streaming_reduce_over_dimension, streaming_reduce_over_dimension_with_budget, ChunkAccumulator, DEFAULT_MEMORY_BUDGET};
+pub use netcdf::{max_over_dimension, mean_over_dimension, median_over_dimension, min_over_dimension, stat_over_dimension_with_budget, std_over_dimension, sum_over_dimension, var_over_dimension};
 pub use operations::{StatOperation, StatResult, StatisticalReduction};
-pub use parallel::{parallel_max_axis, parallel_mean_axis, parallel_median_axis, parallel_min_axis, parallel_sum_axis};
+pub use moments::Moments;
+pub use sketch::{QuantileSketch, DEFAULT_COMPRESSION};
+pub use source::{reduce_over_dimension, reduce_over_dimension_with_budget};
+pub use parallel::{parallel_max_axis, parallel_mean_axis, parallel_median_axis, parallel_min_axis, parallel_std_axis, parallel_sum_axis, parallel_var_axis};
 
 // Legacy functions for backwards compatibility
 pub use netcdf::{reduce_max, reduce_min};
@@ -0,0 +1,72 @@
+//! Mergeable running moments
+//!
+//! [`Moments`] tracks the count, mean and sum of squared deviations of a
+//! stream of values with Welford's update, and combines partial results of
+//! disjoint parts of the data with Chan's pairwise formula. Neither step
+//! subtracts large nearly equal sums, so variances stay accurate for data far
+//! from zero, and partial results of parallel workers or chunks merge exactly.
+
+/// Running count, mean and sum of squared deviations of a stream of values
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Moments {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    /// Create empty moments
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values added
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Mean of the values, `None` if there are none
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Add a value (Welford's update)
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Add the values summarised by other moments (Chan's update)
+    pub fn merge(&mut self, other: &Moments) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = other.count as f64 / count as f64;
+        self.mean += delta * weight;
+        self.m2 += other.m2 + delta * delta * self.count as f64 * weight;
+        self.count = count;
+    }
+
+    /// Variance with `ddof` delta degrees of freedom, i.e. divided by `count - ddof`
+    ///
+    /// Returns `None` unless more than `ddof` values were added.
+    pub fn variance(&self, ddof: u32) -> Option<f64> {
+        let ddof = u64::from(ddof);
+        (self.count > ddof).then(|| self.m2.max(0.0) / (self.count - ddof) as f64)
+    }
+
+    /// Standard deviation with `ddof` delta degrees of freedom
+    ///
+    /// Returns `None` unless more than `ddof` values were added.
+    pub fn std(&self, ddof: u32) -> Option<f64> {
+        self.variance(ddof).map(f64::sqrt)
+    }
+}
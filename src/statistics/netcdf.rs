@@ -1,9 +1,13 @@
 //! NetCDF-specific statistical functions
 //!
 //! This module provides statistical computation functions specifically for NetCDF variables.
+//! Variables larger than a memory budget ([`DEFAULT_MEMORY_BUDGET`] unless given to
+//! [`stat_over_dimension_with_budget`]) are reduced out of core, hyperslab by hyperslab.
 
+use super::accumulate::{kept_dimensions, BlockReduction, DEFAULT_MEMORY_BUDGET};
 use super::operations::{StatOperation, StatisticalReduction};
 use crate::errors::{Result, RuNeVisError};
+use crate::netcdf_io::read_hyperslab;
 use ndarray::{ArrayD, Axis};
 use netcdf::{File, Variable};
 
@@ -35,6 +39,9 @@ pub fn mean_over_dimension(
 
 /// Computes median over a specified dimension for a NetCDF variable using parallel processing
 ///
+/// Medians of variables larger than [`DEFAULT_MEMORY_BUDGET`] are exact unless
+/// `dim_name` must be split, see [`BlockReduction`].
+///
 /// # Arguments
 ///
 /// * `file` - The NetCDF file containing the variable
@@ -137,15 +144,86 @@ pub fn max_over_dimension(
     compute_stat_over_dimension(file, var_name, dim_name, StatOperation::Max)
 }
 
-/// Generic function to compute statistics over a dimension
+/// Computes standard deviation over a specified dimension for a NetCDF variable using parallel processing
 ///
-/// This is the core implementation that handles loading data from NetCDF
-/// and delegating to the appropriate statistical computation.
-fn compute_stat_over_dimension(
+/// # Arguments
+///
+/// * `file` - The NetCDF file containing the variable
+/// * `var_name` - Name of the variable to compute statistics for
+/// * `dim_name` - Name of the dimension to reduce over
+/// * `ddof` - Delta degrees of freedom: the sum of squared deviations is divided by `n - ddof`
+///
+/// # Returns
+///
+/// A tuple containing:
+/// - The computed standard deviation data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated variable name for the result
+///
+/// # Errors
+///
+/// Returns an error if the variable or dimension is not found, or if computation fails.
+pub fn std_over_dimension(
+    file: &File,
+    var_name: &str,
+    dim_name: &str,
+    ddof: u32,
+) -> Result<(ArrayD<f32>, Vec<String>, String)> {
+    compute_stat_over_dimension(file, var_name, dim_name, StatOperation::Std { ddof })
+}
+
+/// Computes variance over a specified dimension for a NetCDF variable using parallel processing
+///
+/// # Arguments
+///
+/// * `file` - The NetCDF file containing the variable
+/// * `var_name` - Name of the variable to compute statistics for
+/// * `dim_name` - Name of the dimension to reduce over
+/// * `ddof` - Delta degrees of freedom: the sum of squared deviations is divided by `n - ddof`
+///
+/// # Returns
+///
+/// A tuple containing:
+/// - The computed variance data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated variable name for the result
+///
+/// # Errors
+///
+/// Returns an error if the variable or dimension is not found, or if computation fails.
+pub fn var_over_dimension(
+    file: &File,
+    var_name: &str,
+    dim_name: &str,
+    ddof: u32,
+) -> Result<(ArrayD<f32>, Vec<String>, String)> {
+    compute_stat_over_dimension(file, var_name, dim_name, StatOperation::Var { ddof })
+}
+
+/// Computes a statistic over a dimension of a NetCDF variable within a memory budget
+///
+/// Variables whose `f32` data fits in `memory_budget` bytes are loaded whole.
+/// Larger ones are read as hyperslabs, several in parallel, and reduced as
+/// described in [`BlockReduction`].
+///
+/// # Returns
+///
+/// A tuple containing:
+/// - The computed data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated variable name for the result
+///
+/// # Errors
+///
+/// Returns an error if the variable or dimension is not found, if the
+/// dimension must be split and half the budget cannot hold the partial
+/// results, or if computation fails.
+pub fn stat_over_dimension_with_budget(
     file: &File,
     var_name: &str,
     dim_name: &str,
     operation: StatOperation,
+    memory_budget: usize,
 ) -> Result<(ArrayD<f32>, Vec<String>, String)> {
     let var = file
         .variable(var_name)
@@ -172,26 +250,40 @@ fn compute_stat_over_dimension(
         .iter()
         .map(netcdf::Dimension::len)
         .collect();
-    let data_vec = var.get_values::<f32, _>(..)?;
-
-    println!("🚀 Loading data array with shape: {shape:?}");
-    let data = ArrayD::from_shape_vec(shape, data_vec)?;
-
-    let operation_name = operation.as_str();
-
-    println!("⚡ Computing {operation_name} using parallel processing over dimension '{dim_name}'");
 
-    let result_array = data.reduce_along_axis(axis_index, operation)?;
+    let plan = BlockReduction::plan(
+        &shape,
+        axis_index,
+        dim_name,
+        var.chunking()?.as_deref(),
+        operation,
+        memory_budget,
+        rayon::current_num_threads(),
+    )?;
+    let result_array = match plan {
+        None => {
+            let data_vec = var.get_values::<f32, _>(..)?;
+            ArrayD::from_shape_vec(shape, data_vec)?.reduce_along_axis(axis_index, operation)?
+        }
+        Some(reduction) => reduction.reduce_with(|region| read_hyperslab(&var, region))?,
+    };
 
-    let kept_dim_names: Vec<String> = dim_names
-        .into_iter()
-        .enumerate()
-        .filter_map(|(i, name)| if i == axis_index { None } else { Some(name) })
-        .collect();
+    let new_var_name = format!("{var_name}_{}_over_{dim_name}", operation.as_str());
 
-    let new_var_name = format!("{var_name}_{operation_name}_over_{dim_name}");
+    Ok((result_array, kept_dimensions(dim_names, axis_index), new_var_name))
+}
 
-    Ok((result_array.into_dyn(), kept_dim_names, new_var_name))
+/// Generic function to compute statistics over a dimension
+///
+/// This is the core implementation that handles loading data from NetCDF
+/// and delegating to the appropriate statistical computation.
+fn compute_stat_over_dimension(
+    file: &File,
+    var_name: &str,
+    dim_name: &str,
+    operation: StatOperation,
+) -> Result<(ArrayD<f32>, Vec<String>, String)> {
+    stat_over_dimension_with_budget(file, var_name, dim_name, operation, DEFAULT_MEMORY_BUDGET)
 }
 
 /// Generic minimum reduction function for f64 data
@@ -4,6 +4,7 @@
 
 use crate::errors::{Result, RuNeVisError};
 use ndarray::ArrayD;
+use std::fmt;
 
 /// Supported statistical operations
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,6 +19,10 @@ pub enum StatOperation {
     Max,
     /// Median value
     Median,
+    /// Standard deviation, divided by `n - ddof` (`ddof = 1` for the sample estimate)
+    Std { ddof: u32 },
+    /// Variance, divided by `n - ddof` (`ddof = 1` for the sample estimate)
+    Var { ddof: u32 },
 }
 
 impl StatOperation {
@@ -30,13 +35,34 @@ impl StatOperation {
             Self::Min => "minimum",
             Self::Max => "maximum",
             Self::Median => "median",
+            Self::Std { .. } => "standard_deviation",
+            Self::Var { .. } => "variance",
+        }
+    }
+
+    /// Delta degrees of freedom of a standard deviation or variance
+    #[must_use]
+    pub const fn ddof(self) -> Option<u32> {
+        match self {
+            Self::Std { ddof } | Self::Var { ddof } => Some(ddof),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StatOperation {
+    /// The operation name, with the delta degrees of freedom where they apply,
+    /// e.g. `variance (ddof=1)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ddof() {
+            Some(ddof) => write!(f, "{} (ddof={ddof})", self.as_str()),
+            None => f.write_str(self.as_str()),
         }
     }
 }
 
 /// Result of a statistical computation
 #[derive(Debug)]
-#[allow(dead_code)]
 pub struct StatResult<T> {
     /// The computed data array
     pub data: ArrayD<T>,
@@ -80,6 +106,17 @@ impl<T> StatResult<T> {
     pub fn ndim(&self) -> usize {
         self.data.ndim()
     }
+
+    /// Name for the result variable, e.g. `temperature_mean_over_time`
+    #[must_use]
+    pub fn output_name(&self) -> String {
+        format!(
+            "{}_{}_over_{}",
+            self.variable_name,
+            self.operation.as_str(),
+            self.dimension_name
+        )
+    }
 }
 
 /// Trait for types that can perform statistical reductions along an axis
@@ -110,6 +147,8 @@ impl StatisticalReduction<f32> for ArrayD<f32> {
             StatOperation::Sum => super::parallel::parallel_sum_axis(self, axis),
             StatOperation::Min => super::parallel::parallel_min_axis(self, axis),
             StatOperation::Max => super::parallel::parallel_max_axis(self, axis),
+            StatOperation::Std { ddof } => super::parallel::parallel_std_axis(self, axis, ddof),
+            StatOperation::Var { ddof } => super::parallel::parallel_var_axis(self, axis, ddof),
         }
     }
 }
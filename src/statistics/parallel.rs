@@ -2,83 +2,47 @@
 //!
 //! This module contains the actual parallel computation logic for statistical reductions.
 
-use crate::errors::Result;
-use ndarray::{ArrayD, Axis};
+use super::moments::Moments;
+use crate::errors::{Result, RuNeVisError};
+use ndarray::{ArrayD, ArrayView1, Axis, Zip};
 use rayon::prelude::*;
 
 /// Computes mean along an axis using parallel processing
 ///
-/// This function converts data to f64 for computation to avoid precision loss,
-/// then uses parallel processing to compute the mean along the specified axis.
+/// Every lane along the axis is summed in `f64` to avoid precision loss,
+/// reading the `f32` values in place, and lanes are processed in parallel.
 ///
 /// # Errors
 ///
-/// Returns an error if array reshaping fails or if the axis is invalid.
+/// Returns an error if the axis is invalid.
 pub fn parallel_mean_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>> {
-    // Convert f32 data to f64 for computation to avoid precision loss
-    let data_f64: Vec<f64> = data.iter().map(|&x| f64::from(x)).collect();
-    let data_f64_array = ArrayD::from_shape_vec(data.raw_dim(), data_f64)?;
-
-    let original_shape = data.shape();
-    let axis_len = original_shape[axis];
-
-    // Use reduce with a custom mean operation that tracks count
-    let mut new_shape = original_shape.to_vec();
-    new_shape.remove(axis);
-    let output_size: usize = new_shape.iter().product();
-
-    println!(
-        "⚡ Processing {output_size} elements across {} CPU cores",
+    check_axis(data, axis)?;
+    log::debug!(
+        "Processing {} elements across {} CPU cores",
+        output_size(data, axis),
         rayon::current_num_threads()
     );
 
-    // Create output vector for parallel computation with mean calculation
-    let result: Vec<f32> = (0..output_size)
-        .into_par_iter()
-        .map(|flat_idx| {
-            // Convert flat index back to multi-dimensional coordinates
-            let mut coords = vec![0; original_shape.len()];
-            let mut remaining = flat_idx;
-
-            // Fill coordinates, skipping the axis we're averaging over
-            let mut coord_idx = 0;
-            for (dim_idx, &_dim_size) in original_shape.iter().enumerate() {
-                if dim_idx != axis {
-                    let stride = new_shape[coord_idx + 1..].iter().product::<usize>();
-                    coords[dim_idx] = remaining / stride;
-                    remaining %= stride;
-                    coord_idx += 1;
-                }
-            }
-
-            // Compute mean along the specified axis
-            let mut sum = 0.0_f64;
-            let mut count = 0_i32;
-
-            for i in 0..axis_len {
-                coords[axis] = i;
-                if let Some(value) = data_f64_array.get(coords.as_slice()) {
-                    if value.is_finite() {
-                        // Skip NaN and infinite values
-                        sum += value;
-                        count += 1;
-                    }
-                }
-            }
+    let result = Zip::from(data.lanes(Axis(axis))).par_map_collect(|lane| {
+        let mut sum = 0.0_f64;
+        let mut count = 0_u64;
+        // Skip NaN and infinite values
+        for &value in lane.iter().filter(|v| v.is_finite()) {
+            sum += f64::from(value);
+            count += 1;
+        }
 
-            if count > 0 {
-                #[allow(clippy::cast_possible_truncation)]
-                {
-                    (sum / f64::from(count)) as f32
-                }
-            } else {
-                f32::NAN // Return NaN if all values were invalid
+        if count > 0 {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+            {
+                (sum / count as f64) as f32
             }
-        })
-        .collect();
+        } else {
+            f32::NAN // Return NaN if all values were invalid
+        }
+    });
 
-    // Reshape the result back to the expected dimensions
-    Ok(ArrayD::from_shape_vec(new_shape, result)?)
+    Ok(result)
 }
 
 /// Computes sum along an axis using ndarray's parallel `fold_axis` for better performance
@@ -87,6 +51,7 @@ pub fn parallel_mean_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>
 ///
 /// Returns an error if the axis is invalid.
 pub fn parallel_sum_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>> {
+    check_axis(data, axis)?;
     // Use ndarray's parallel fold_axis for optimal performance
     let axis_obj = Axis(axis);
     let result = data.fold_axis(axis_obj, 0.0_f32, |&acc, &x| {
@@ -106,6 +71,7 @@ pub fn parallel_sum_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>>
 ///
 /// Returns an error if the axis is invalid.
 pub fn parallel_min_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>> {
+    check_axis(data, axis)?;
     // Use ndarray's parallel fold_axis for optimal performance
     let axis_obj = Axis(axis);
     let result = data.fold_axis(axis_obj, f32::INFINITY, |&acc, &x| {
@@ -127,6 +93,7 @@ pub fn parallel_min_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>>
 ///
 /// Returns an error if the axis is invalid.
 pub fn parallel_max_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>> {
+    check_axis(data, axis)?;
     // Use ndarray's parallel fold_axis for optimal performance
     let axis_obj = Axis(axis);
     let result = data.fold_axis(axis_obj, f32::NEG_INFINITY, |&acc, &x| {
@@ -142,76 +109,138 @@ pub fn parallel_max_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>>
     Ok(final_result.into_dyn())
 }
 
+/// Computes median along an axis using parallel processing
+///
+/// The finite values of every lane are copied into a buffer of the lane
+/// length and the median found by quickselect; lanes are processed in
+/// parallel. Medians of an even count are the mean of the two middle values.
+///
+/// # Errors
+///
+/// Returns an error if the axis is invalid.
 pub fn parallel_median_axis(data: &ArrayD<f32>, axis: usize) -> Result<ArrayD<f32>> {
-    // Convert f32 data to f64 for computation to avoid precision loss
-    let data_f64: Vec<f64> = data.iter().map(|&x| f64::from(x)).collect();
-    let data_f64_array = ArrayD::from_shape_vec(data.raw_dim(), data_f64)?;
+    check_axis(data, axis)?;
+    log::debug!(
+        "Processing {} elements across {} CPU cores",
+        output_size(data, axis),
+        rayon::current_num_threads()
+    );
 
-    let original_shape = data.shape();
-    let axis_len = original_shape[axis];
+    let result = Zip::from(data.lanes(Axis(axis))).par_map_collect(|lane| {
+        // Collect all valid values along the axis into a vec
+        let mut values: Vec<f64> = lane
+            .iter()
+            .filter(|v| v.is_finite())
+            .map(|&v| f64::from(v))
+            .collect();
+
+        // If the entire vec is empty, return nan
+        if values.is_empty() {
+            return f32::NAN;
+        }
 
-    // Use reduce with a custom median operation that tracks count
-    let mut new_shape = original_shape.to_vec();
-    new_shape.remove(axis);
-    let output_size: usize = new_shape.iter().product();
+        let mid = values.len() / 2;
+        // Performs quickselect algorithm to find median value in average O(n) time, O(n^2) worst case
+        values.select_nth_unstable_by(mid, |a: &f64, b: &f64| a.partial_cmp(b).unwrap());
+        let median = values[mid];
+        if values.len() % 2 == 0 {
+            let max_left = values[..mid]
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max);
+            ((max_left + median) / 2.0) as f32
+        } else {
+            median as f32
+        }
+    });
 
-    println!(
-        "⚡ Processing {output_size} elements across {} CPU cores",
-        rayon::current_num_threads()
-    );
+    Ok(result)
+}
 
-    // Create output vector for parallel computation with mean calculation
-    let result: Vec<f32> = (0..output_size)
-        .into_par_iter()
-        .map(|flat_idx| {
-            // Convert flat index back to multi-dimensional coordinates
-            let mut coords = vec![0; original_shape.len()];
-            let mut remaining = flat_idx;
-
-            // Fill coordinates, skipping the axis we're averaging over
-            let mut coord_idx = 0;
-            for (dim_idx, &_dim_size) in original_shape.iter().enumerate() {
-                if dim_idx != axis {
-                    let stride = new_shape[coord_idx + 1..].iter().product::<usize>();
-                    coords[dim_idx] = remaining / stride;
-                    remaining %= stride;
-                    coord_idx += 1;
-                }
-            }            
-
-            // Collect all valid values along the axis into a vec
-            let mut values = Vec::with_capacity(axis_len);
-            for i in 0..axis_len {
-                coords[axis] = i;
-                if let Some(value) = data_f64_array.get(coords.as_slice()) {
-                    if value.is_finite() {
-                        values.push(*value);
-                    }
-                }
-            }
-            
-            // If the entire vec is empty, return nan
-            if values.is_empty() {
-                return f32::NAN; 
-            }
+/// Computes the variance along an axis with `ddof` delta degrees of freedom
+///
+/// Each lane is summarised with Welford's algorithm in `f64`. When there are
+/// fewer lanes than threads, lanes are split into blocks whose moments are
+/// computed in parallel and combined with Chan's merge, so reductions to a few
+/// values use every core too. Elements with no more than `ddof` finite values
+/// are NaN.
+///
+/// # Errors
+///
+/// Returns an error if the axis is invalid.
+pub fn parallel_var_axis(data: &ArrayD<f32>, axis: usize, ddof: u32) -> Result<ArrayD<f32>> {
+    check_axis(data, axis)?;
+    let moments = parallel_moments_axis(data, axis);
+    #[allow(clippy::cast_possible_truncation)]
+    let result = moments.mapv(|m| m.variance(ddof).map_or(f32::NAN, |v| v as f32));
+    Ok(result)
+}
 
-            let mid = values.len() / 2;
-            // Performs quickselect algorithm to find median value in average O(n) time, O(n^2) worst case 
-            // let (_, median, _) = values.select_nth_unstable_by(|a: &f64, b: &f64| a.partial_cmp(b).unwrap());
-            values.select_nth_unstable_by(mid, |a: &f64, b: &f64| a.partial_cmp(b).unwrap());
-            let median = values[mid];
-            if values.len() % 2 == 0 {
-                let max_left = values[..mid]
-                    .iter()
-                    .cloned()
-                    .fold(f64::NEG_INFINITY, f64::max);
-                ((max_left + median) / 2.0) as f32
-            } else {
-                median as f32
-            }
+/// Computes the standard deviation along an axis with `ddof` delta degrees of freedom
+///
+/// See [`parallel_var_axis`] for the algorithm.
+///
+/// # Errors
+///
+/// Returns an error if the axis is invalid.
+pub fn parallel_std_axis(data: &ArrayD<f32>, axis: usize, ddof: u32) -> Result<ArrayD<f32>> {
+    check_axis(data, axis)?;
+    let moments = parallel_moments_axis(data, axis);
+    #[allow(clippy::cast_possible_truncation)]
+    let result = moments.mapv(|m| m.std(ddof).map_or(f32::NAN, |v| v as f32));
+    Ok(result)
+}
+
+/// Moments of the finite values of every lane along an axis
+fn parallel_moments_axis(data: &ArrayD<f32>, axis: usize) -> ArrayD<Moments> {
+    let lanes = data.lanes(Axis(axis));
+    let threads = rayon::current_num_threads();
+    let output_size = output_size(data, axis);
+
+    log::debug!("Processing {output_size} elements across {threads} CPU cores");
+
+    if output_size >= threads {
+        Zip::from(lanes).par_map_collect(lane_moments)
+    } else {
+        let block = data.len_of(Axis(axis)).div_ceil(threads).max(1);
+        Zip::from(lanes).map_collect(|lane| {
+            lane.axis_chunks_iter(Axis(0), block)
+                .into_par_iter()
+                .map(lane_moments)
+                .reduce(Moments::new, |mut a, b| {
+                    a.merge(&b);
+                    a
+                })
         })
-        .collect();
+    }
+}
+
+/// Welford moments of the finite values of a lane
+fn lane_moments(lane: ArrayView1<f32>) -> Moments {
+    let mut moments = Moments::new();
+    for &value in lane.iter().filter(|v| v.is_finite()) {
+        moments.add(f64::from(value));
+    }
+    moments
+}
+
+/// Number of elements of the reduction of `data` along `axis`
+fn output_size(data: &ArrayD<f32>, axis: usize) -> usize {
+    data.shape()
+        .iter()
+        .enumerate()
+        .filter(|&(dim, _)| dim != axis)
+        .map(|(_, &len)| len)
+        .product()
+}
 
-    // Reshape the result back to the expected dimensions
-    Ok(ArrayD::from_shape_vec(new_shape, result)?)
+/// Checks that `axis` is an axis of `data`
+fn check_axis(data: &ArrayD<f32>, axis: usize) -> Result<()> {
+    if axis >= data.ndim() {
+        return Err(RuNeVisError::StatisticsError(format!(
+            "Axis {axis} is out of bounds for array with {} dimensions",
+            data.ndim()
+        )));
+    }
+    Ok(())
 }
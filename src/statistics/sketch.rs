@@ -0,0 +1,149 @@
+//! Mergeable quantile sketch
+//!
+//! A merging t-digest: values are summarised by weighted centroids, kept small
+//! near the tails and allowed to grow towards the median. Sketches of disjoint
+//! parts of the data merge into a sketch of the whole, which lets chunked
+//! reductions estimate medians without holding every value.
+
+/// Default compression of a [`QuantileSketch`]
+///
+/// Up to twice this many values are summarised exactly.
+pub const DEFAULT_COMPRESSION: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// Approximate quantiles of a stream of values in bounded memory
+///
+/// The sketch holds O(`compression`) centroids whatever the number of values.
+/// While it has seen fewer than `2 * compression` values no centroids are
+/// merged, so quantiles are exact and match those of the sorted values.
+#[derive(Debug, Clone)]
+pub struct QuantileSketch {
+    centroids: Vec<Centroid>,
+    /// Values added since the last compression
+    buffer: Vec<f64>,
+    count: u64,
+    compression: f64,
+}
+
+impl Default for QuantileSketch {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION)
+    }
+}
+
+impl QuantileSketch {
+    /// Create an empty sketch; larger `compression` trades memory for accuracy
+    pub fn new(compression: f64) -> Self {
+        Self {
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0,
+            compression: compression.max(1.0),
+        }
+    }
+
+    /// Number of values summarised by the sketch
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Check whether the sketch has seen no values
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Upper estimate of the memory held by the sketch, in bytes
+    ///
+    /// The buffer holds fewer than `4 * compression` values and compression
+    /// keeps the centroids to about `2 * compression`.
+    pub fn max_bytes(&self) -> usize {
+        let compression = self.compression.ceil() as usize;
+        std::mem::size_of::<Self>()
+            + 4 * compression * std::mem::size_of::<f64>()
+            + 2 * compression * std::mem::size_of::<Centroid>()
+    }
+
+    /// Add a value; NaN is ignored
+    pub fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.buffer.push(value);
+        self.count += 1;
+        if self.buffer.len() as f64 >= 4.0 * self.compression {
+            self.compress();
+        }
+    }
+
+    /// Add the values summarised by another sketch
+    pub fn merge(&mut self, other: &QuantileSketch) {
+        self.centroids.extend_from_slice(&other.centroids);
+        self.buffer.extend_from_slice(&other.buffer);
+        self.count += other.count;
+        self.compress();
+    }
+
+    /// Estimate the `q` quantile (`0.0..=1.0`), `None` for an empty sketch
+    ///
+    /// Ranks between centroids are interpolated linearly, so the median of an
+    /// even number of exactly summarised values is the mean of the middle two.
+    pub fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.compress();
+        let first = self.centroids.first()?;
+        let target = q.clamp(0.0, 1.0) * (self.count as f64 - 1.0);
+
+        // Rank of the centre of each centroid, counted from 0
+        let mut previous = (first.weight - 1.0) / 2.0;
+        if target <= previous {
+            return Some(first.mean);
+        }
+        let mut cumulative = first.weight;
+        for pair in self.centroids.windows(2) {
+            let centre = cumulative + (pair[1].weight - 1.0) / 2.0;
+            if target <= centre {
+                let fraction = (target - previous) / (centre - previous);
+                return Some(pair[0].mean + fraction * (pair[1].mean - pair[0].mean));
+            }
+            previous = centre;
+            cumulative += pair[1].weight;
+        }
+        self.centroids.last().map(|last| last.mean)
+    }
+
+    /// Fold the buffered values into the centroids
+    ///
+    /// Neighbouring centroids are merged while their combined weight stays
+    /// within the t-digest bound `4 * n * q * (1 - q) / compression`.
+    fn compress(&mut self) {
+        let mut items = std::mem::take(&mut self.centroids);
+        items.extend(
+            self.buffer
+                .drain(..)
+                .map(|mean| Centroid { mean, weight: 1.0 }),
+        );
+        items.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let total: f64 = items.iter().map(|c| c.weight).sum();
+        let mut merged: Vec<Centroid> = Vec::with_capacity(items.len());
+        let mut before = 0.0;
+        for item in items {
+            if let Some(current) = merged.last_mut() {
+                let combined = current.weight + item.weight;
+                let q = (before + combined / 2.0) / total;
+                if combined <= 4.0 * total * q * (1.0 - q) / self.compression {
+                    current.mean += (item.mean - current.mean) * item.weight / combined;
+                    current.weight = combined;
+                    continue;
+                }
+                before += current.weight;
+            }
+            merged.push(item);
+        }
+        self.centroids = merged;
+    }
+}
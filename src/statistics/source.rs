@@ -0,0 +1,109 @@
+//! Format-agnostic statistical reductions
+//!
+//! Reductions written against the [`DataReader`] trait, so the same call
+//! computes statistics over a named dimension of a NetCDF variable
+//! ([`NetCDFDataSource`](crate::netcdf_io::NetCDFDataSource)) or a Zarr array
+//! ([`ZarrReader`](crate::zarr_io::ZarrReader), [`ZarrDataSource`](crate::zarr_io::ZarrDataSource)).
+//! Arrays larger than a memory budget are read slice by slice.
+
+use super::accumulate::{kept_dimensions, BlockReduction, DEFAULT_MEMORY_BUDGET};
+use super::operations::{StatOperation, StatResult, StatisticalReduction};
+use crate::data_source::DataReader;
+use crate::errors::Result;
+
+/// Computes a statistic over a named dimension of an array of any data source
+///
+/// Arrays larger than [`DEFAULT_MEMORY_BUDGET`] are reduced slice by slice,
+/// as described in [`reduce_over_dimension_with_budget`].
+///
+/// # Arguments
+///
+/// * `reader` - The data source holding the array
+/// * `array_name` - Name of the array (or NetCDF variable) to reduce
+/// * `dim_name` - Name of the dimension to reduce over
+/// * `operation` - The statistic to compute
+///
+/// # Returns
+///
+/// A [`StatResult`] holding the reduced data and the names of the remaining
+/// dimensions.
+///
+/// # Errors
+///
+/// Returns an error if the array or dimension is not found, or if reading or
+/// computation fails.
+pub async fn reduce_over_dimension<R>(
+    reader: &R,
+    array_name: &str,
+    dim_name: &str,
+    operation: StatOperation,
+) -> Result<StatResult<f32>>
+where
+    R: DataReader + Sync + ?Sized,
+{
+    reduce_over_dimension_with_budget(
+        reader,
+        array_name,
+        dim_name,
+        operation,
+        DEFAULT_MEMORY_BUDGET,
+    )
+    .await
+}
+
+/// Computes a statistic over a named dimension of an array within a memory budget
+///
+/// Arrays whose `f32` data fits in `memory_budget` bytes are read whole.
+/// Larger ones are read with [`DataReader::read_slice`] one slice at a time
+/// and reduced as described in [`BlockReduction`].
+///
+/// # Errors
+///
+/// Returns an error if the array or dimension is not found, if the dimension
+/// must be split and half the budget cannot hold the partial results, or if
+/// reading or computation fails.
+pub async fn reduce_over_dimension_with_budget<R>(
+    reader: &R,
+    array_name: &str,
+    dim_name: &str,
+    operation: StatOperation,
+    memory_budget: usize,
+) -> Result<StatResult<f32>>
+where
+    R: DataReader + Sync + ?Sized,
+{
+    let metadata = reader.get_metadata(array_name).await?;
+    let axis_index = metadata.dimension_index(dim_name)?;
+    let chunks = reader.chunk_shape(array_name).await?;
+
+    let plan = BlockReduction::plan(
+        &metadata.shape,
+        axis_index,
+        dim_name,
+        chunks.as_deref(),
+        operation,
+        memory_budget,
+        1,
+    )?;
+    let result_array = match plan {
+        None => reader
+            .read_array(array_name)
+            .await?
+            .reduce_along_axis(axis_index, operation)?,
+        Some(mut reduction) => {
+            for region in reduction.regions() {
+                let block = reader.read_slice(array_name, &region).await?;
+                reduction.add_block(&region, block)?;
+            }
+            reduction.finish()
+        }
+    };
+
+    Ok(StatResult::new(
+        result_array,
+        kept_dimensions(metadata.dimensions, axis_index),
+        operation,
+        array_name.to_string(),
+        dim_name.to_string(),
+    ))
+}
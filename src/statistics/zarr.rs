@@ -1,83 +1,199 @@
-//! Zarr-specific statistical functions (future implementation)
+//! Zarr-specific statistical functions
 //!
-//! This module will provide statistical computation functions specifically for Zarr arrays.
-//! Currently, it contains placeholder functions for future development.
+//! Counterparts of the [`netcdf`](super::netcdf) functions for Zarr arrays.
+//! Means, sums, extrema, standard deviations and variances fold the chunks of
+//! [`stream_chunks`](crate::data_source::StreamingDataReader::stream_chunks)
+//! into partial results with [`streaming_reduce_over_dimension`], so stores
+//! larger than memory are reduced one chunk at a time. Medians, and reductions
+//! whose partial results exceed [`DEFAULT_MEMORY_BUDGET`], go through
+//! [`reduce_over_dimension`] instead.
 
-use crate::errors::{Result, RuNeVisError};
+use super::accumulate::{
+    partial_state_bytes, streaming_reduce_over_dimension, DEFAULT_MEMORY_BUDGET,
+};
+use super::operations::{StatOperation, StatResult};
+use super::source::reduce_over_dimension;
+use crate::data_source::DataArrayMetadata;
+use crate::errors::Result;
+use crate::zarr_io::ZarrReader;
 use ndarray::ArrayD;
 
 /// Computes mean over a specified dimension for a Zarr array using parallel processing
 ///
-/// # Note
+/// # Returns
 ///
-/// This function is a placeholder for future Zarr support implementation.
+/// A tuple containing:
+/// - The computed mean data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated array name for the result
 ///
 /// # Errors
 ///
-/// Currently returns a "not implemented" error.
-#[allow(dead_code)]
-pub fn zarr_mean_over_dimension(
-    _array_name: &str,
-    _dim_name: &str,
+/// Returns an error if the array or dimension is not found, or if computation fails.
+pub async fn zarr_mean_over_dimension(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
 ) -> Result<(ArrayD<f32>, Vec<String>, String)> {
-    Err(RuNeVisError::Generic(
-        "Zarr statistical operations are not yet implemented".to_string(),
-    ))
+    zarr_stat_over_dimension(reader, array_name, dim_name, StatOperation::Mean).await
+}
+
+/// Computes median over a specified dimension for a Zarr array using parallel processing
+///
+/// Medians are exact unless `dim_name` must be split, see
+/// [`BlockReduction`](super::accumulate::BlockReduction).
+///
+/// # Returns
+///
+/// A tuple containing:
+/// - The computed median data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated array name for the result
+///
+/// # Errors
+///
+/// Returns an error if the array or dimension is not found, or if computation fails.
+pub async fn zarr_median_over_dimension(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
+) -> Result<(ArrayD<f32>, Vec<String>, String)> {
+    zarr_stat_over_dimension(reader, array_name, dim_name, StatOperation::Median).await
 }
 
 /// Computes sum over a specified dimension for a Zarr array using parallel processing
 ///
-/// # Note
+/// # Returns
 ///
-/// This function is a placeholder for future Zarr support implementation.
+/// A tuple containing:
+/// - The computed sum data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated array name for the result
 ///
 /// # Errors
 ///
-/// Currently returns a "not implemented" error.
-#[allow(dead_code)]
-pub fn zarr_sum_over_dimension(
-    _array_name: &str,
-    _dim_name: &str,
+/// Returns an error if the array or dimension is not found, or if computation fails.
+pub async fn zarr_sum_over_dimension(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
 ) -> Result<(ArrayD<f32>, Vec<String>, String)> {
-    Err(RuNeVisError::Generic(
-        "Zarr statistical operations are not yet implemented".to_string(),
-    ))
+    zarr_stat_over_dimension(reader, array_name, dim_name, StatOperation::Sum).await
 }
 
 /// Computes minimum over a specified dimension for a Zarr array using parallel processing
 ///
-/// # Note
+/// # Returns
 ///
-/// This function is a placeholder for future Zarr support implementation.
+/// A tuple containing:
+/// - The computed minimum data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated array name for the result
 ///
 /// # Errors
 ///
-/// Currently returns a "not implemented" error.
-#[allow(dead_code)]
-pub fn zarr_min_over_dimension(
-    _array_name: &str,
-    _dim_name: &str,
+/// Returns an error if the array or dimension is not found, or if computation fails.
+pub async fn zarr_min_over_dimension(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
 ) -> Result<(ArrayD<f32>, Vec<String>, String)> {
-    Err(RuNeVisError::Generic(
-        "Zarr statistical operations are not yet implemented".to_string(),
-    ))
+    zarr_stat_over_dimension(reader, array_name, dim_name, StatOperation::Min).await
 }
 
 /// Computes maximum over a specified dimension for a Zarr array using parallel processing
 ///
-/// # Note
+/// # Returns
 ///
-/// This function is a placeholder for future Zarr support implementation.
+/// A tuple containing:
+/// - The computed maximum data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated array name for the result
 ///
 /// # Errors
 ///
-/// Currently returns a "not implemented" error.
-#[allow(dead_code)]
-pub fn zarr_max_over_dimension(
-    _array_name: &str,
-    _dim_name: &str,
+/// Returns an error if the array or dimension is not found, or if computation fails.
+pub async fn zarr_max_over_dimension(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
 ) -> Result<(ArrayD<f32>, Vec<String>, String)> {
-    Err(RuNeVisError::Generic(
-        "Zarr statistical operations are not yet implemented".to_string(),
-    ))
+    zarr_stat_over_dimension(reader, array_name, dim_name, StatOperation::Max).await
+}
+
+/// Computes standard deviation over a specified dimension for a Zarr array using parallel processing
+///
+/// The sum of squared deviations is divided by `n - ddof`.
+///
+/// # Returns
+///
+/// A tuple containing:
+/// - The computed standard deviation data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated array name for the result
+///
+/// # Errors
+///
+/// Returns an error if the array or dimension is not found, or if computation fails.
+pub async fn zarr_std_over_dimension(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
+    ddof: u32,
+) -> Result<(ArrayD<f32>, Vec<String>, String)> {
+    zarr_stat_over_dimension(reader, array_name, dim_name, StatOperation::Std { ddof }).await
+}
+
+/// Computes variance over a specified dimension for a Zarr array using parallel processing
+///
+/// The sum of squared deviations is divided by `n - ddof`.
+///
+/// # Returns
+///
+/// A tuple containing:
+/// - The computed variance data as an ArrayD<f32>
+/// - Vector of remaining dimension names
+/// - Generated array name for the result
+///
+/// # Errors
+///
+/// Returns an error if the array or dimension is not found, or if computation fails.
+pub async fn zarr_var_over_dimension(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
+    ddof: u32,
+) -> Result<(ArrayD<f32>, Vec<String>, String)> {
+    zarr_stat_over_dimension(reader, array_name, dim_name, StatOperation::Var { ddof }).await
+}
+
+async fn zarr_stat_over_dimension(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
+    operation: StatOperation,
+) -> Result<(ArrayD<f32>, Vec<String>, String)> {
+    let result = zarr_reduce(reader, array_name, dim_name, operation).await?;
+    let output_name = result.output_name();
+    Ok((result.data, result.remaining_dimensions, output_name))
+}
+
+/// Reduces chunk by chunk where the partial results fit in the memory budget,
+/// and medians, or reductions whose partial results do not fit, slice by slice
+async fn zarr_reduce(
+    reader: &ZarrReader,
+    array_name: &str,
+    dim_name: &str,
+    operation: StatOperation,
+) -> Result<StatResult<f32>> {
+    let metadata: DataArrayMetadata = reader.get_array_metadata(array_name).await?.into();
+    let axis_index = metadata.dimension_index(dim_name)?;
+    let streamable = operation != StatOperation::Median
+        && partial_state_bytes(&metadata.shape, axis_index, operation) <= DEFAULT_MEMORY_BUDGET;
+
+    if streamable {
+        streaming_reduce_over_dimension(reader, array_name, dim_name, operation).await
+    } else {
+        reduce_over_dimension(reader, array_name, dim_name, operation).await
+    }
 }
@@ -0,0 +1,189 @@
+//! Blosc container format
+//!
+//! Blosc splits a buffer into blocks, optionally shuffles the bytes (or bits)
+//! of each block and compresses the blocks with an internal codec. Buffers are
+//! encoded and decoded by the reference c-blosc library, built from source by
+//! `blosc-src`, so every internal codec (BloscLZ, LZ4, LZ4HC, Zlib and Zstd),
+//! both shuffles, split blocks and memcpyed buffers behave as in numcodecs.
+
+use crate::errors::{Result, RuNeVisError};
+use blosc_src::{
+    blosc_cbuffer_validate, blosc_compress_ctx, blosc_decompress_ctx, BLOSC_MAX_OVERHEAD,
+};
+use std::ffi::CString;
+use std::os::raw::c_int;
+
+const HEADER_LEN: usize = 16;
+
+/// Shuffle filter applied to each block before compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BloscShuffle {
+    /// No shuffling
+    NoShuffle,
+    /// Byte shuffle: group the n-th byte of every element together
+    Shuffle,
+    /// Bit shuffle: group the n-th bit of every element together
+    BitShuffle,
+}
+
+impl BloscShuffle {
+    /// Parse the numcodecs integer encoding (`-1` auto, `0` none, `1` byte, `2` bit)
+    pub fn from_code(code: i64) -> Result<Self> {
+        match code {
+            // numcodecs resolves AUTOSHUFFLE to byte shuffle for multi-byte types
+            -1 | 1 => Ok(Self::Shuffle),
+            0 => Ok(Self::NoShuffle),
+            2 => Ok(Self::BitShuffle),
+            other => Err(RuNeVisError::ZarrError(format!(
+                "Unsupported Blosc shuffle mode {}",
+                other
+            ))),
+        }
+    }
+
+    /// Parse the shuffle name used by the Zarr v3 `blosc` codec
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "noshuffle" => Some(Self::NoShuffle),
+            "shuffle" => Some(Self::Shuffle),
+            "bitshuffle" => Some(Self::BitShuffle),
+            _ => None,
+        }
+    }
+
+    /// Get the shuffle name used by the Zarr v3 `blosc` codec
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::NoShuffle => "noshuffle",
+            Self::Shuffle => "shuffle",
+            Self::BitShuffle => "bitshuffle",
+        }
+    }
+
+    /// Get the numcodecs integer encoding
+    #[must_use]
+    pub const fn code(self) -> i64 {
+        match self {
+            Self::NoShuffle => 0,
+            Self::Shuffle => 1,
+            Self::BitShuffle => 2,
+        }
+    }
+}
+
+/// Decompress a Blosc buffer
+pub fn decompress(src: &[u8]) -> Result<Vec<u8>> {
+    if src.len() < HEADER_LEN {
+        return Err(blosc_error("buffer is shorter than the Blosc header"));
+    }
+
+    // c-blosc expects the buffer to end where the header says it does
+    let cbytes = u32::from_le_bytes([src[12], src[13], src[14], src[15]]) as usize;
+    let src = src
+        .get(..cbytes)
+        .ok_or_else(|| blosc_error("buffer is truncated"))?;
+
+    let mut nbytes = 0usize;
+    // SAFETY: `src` is valid for `src.len()` bytes, which c-blosc checks the
+    // sizes recorded in the header against
+    let status = unsafe { blosc_cbuffer_validate(src.as_ptr().cast(), src.len(), &mut nbytes) };
+    if status < 0 {
+        return Err(blosc_error("invalid or truncated Blosc header"));
+    }
+    if nbytes == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut output = vec![0u8; nbytes];
+    // SAFETY: the header was validated against `src`, and `output` has room
+    // for the `nbytes` it declares
+    let written = unsafe {
+        blosc_decompress_ctx(
+            src.as_ptr().cast(),
+            output.as_mut_ptr().cast(),
+            output.len(),
+            1,
+        )
+    };
+    if usize::try_from(written).ok() != Some(nbytes) {
+        return Err(blosc_error("corrupt compressed data"));
+    }
+    Ok(output)
+}
+
+/// Compress a buffer into the Blosc format
+///
+/// `cname` selects the internal codec (`blosclz`, `lz4`, `lz4hc`, `zlib` or
+/// `zstd`) and `blocksize` may be zero to let the encoder choose.
+pub fn compress(
+    src: &[u8],
+    typesize: usize,
+    cname: &str,
+    clevel: u8,
+    shuffle: BloscShuffle,
+    blocksize: usize,
+) -> Result<Vec<u8>> {
+    let typesize = if (1..=255).contains(&typesize) {
+        typesize
+    } else {
+        1
+    };
+    let compressor = CString::new(cname)
+        .map_err(|_| RuNeVisError::ZarrError(format!("Invalid Blosc compressor {:?}", cname)))?;
+
+    let mut output = vec![0u8; src.len() + BLOSC_MAX_OVERHEAD as usize];
+    // SAFETY: `src` and `output` are valid for the lengths passed, and
+    // `compressor` is a NUL-terminated string outliving the call
+    let written = unsafe {
+        blosc_compress_ctx(
+            c_int::from(clevel.min(9)),
+            shuffle.code() as c_int,
+            typesize,
+            src.len(),
+            src.as_ptr().cast(),
+            output.as_mut_ptr().cast(),
+            output.len(),
+            compressor.as_ptr(),
+            blocksize,
+            1,
+        )
+    };
+    match usize::try_from(written) {
+        Ok(len) if len > 0 => {
+            output.truncate(len);
+            Ok(output)
+        }
+        _ => Err(RuNeVisError::ZarrError(format!(
+            "Blosc encoding with compressor '{}' failed (code {})",
+            cname, written
+        ))),
+    }
+}
+
+/// Group the n-th byte of every element together
+pub(crate) fn byte_shuffle(src: &[u8], typesize: usize) -> Vec<u8> {
+    let nelements = src.len() / typesize;
+    let mut dest = src.to_vec();
+    for i in 0..nelements {
+        for j in 0..typesize {
+            dest[j * nelements + i] = src[i * typesize + j];
+        }
+    }
+    dest
+}
+
+pub(crate) fn byte_unshuffle(src: &[u8], typesize: usize) -> Vec<u8> {
+    let nelements = src.len() / typesize;
+    let mut dest = src.to_vec();
+    for i in 0..nelements {
+        for j in 0..typesize {
+            dest[i * typesize + j] = src[j * nelements + i];
+        }
+    }
+    dest
+}
+
+fn blosc_error(message: &str) -> RuNeVisError {
+    RuNeVisError::ZarrError(format!("Blosc decoding failed: {}", message))
+}
@@ -0,0 +1,150 @@
+//! Least-recently-used cache of decoded chunks
+//!
+//! [`LazyArray`](super::LazyArray) keeps the chunks it has decoded so that
+//! repeated, overlapping window reads do not hit the store again. The cache
+//! is bounded by the size of the decoded `f32` data rather than by a number
+//! of chunks, so the memory budget holds for any chunk shape.
+
+use ndarray::ArrayD;
+use std::collections::{BTreeMap, HashMap};
+
+/// Default memory budget of a chunk cache: 256 MiB of decoded data
+pub const DEFAULT_CACHE_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Usage counters of a chunk cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Chunk lookups answered from the cache
+    pub hits: u64,
+    /// Chunk lookups that had to read the store
+    pub misses: u64,
+    /// Number of cached chunks
+    pub chunks: usize,
+    /// Bytes of decoded data held by the cache
+    pub bytes: usize,
+    /// Maximum number of bytes the cache may hold
+    pub budget: usize,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    data: ArrayD<f32>,
+    last_used: u64,
+}
+
+/// Decoded chunks keyed by chunk grid index, evicted least recently used first
+#[derive(Debug)]
+pub(crate) struct ChunkCache {
+    entries: HashMap<Vec<usize>, CacheEntry>,
+    /// Grid indices ordered by their last use
+    recency: BTreeMap<u64, Vec<usize>>,
+    clock: u64,
+    bytes: usize,
+    budget: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl ChunkCache {
+    pub(crate) fn new(budget: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            bytes: 0,
+            budget,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Look up a chunk, marking it as most recently used
+    pub(crate) fn get(&mut self, index: &[usize]) -> Option<&ArrayD<f32>> {
+        self.clock += 1;
+        let Some(entry) = self.entries.get_mut(index) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.recency.remove(&entry.last_used);
+        self.recency.insert(self.clock, index.to_vec());
+        entry.last_used = self.clock;
+        Some(&entry.data)
+    }
+
+    /// Check whether a chunk is cached, without counting a lookup
+    pub(crate) fn contains(&self, index: &[usize]) -> bool {
+        self.entries.contains_key(index)
+    }
+
+    /// Add a chunk, evicting the least recently used ones to stay within budget
+    ///
+    /// Chunks larger than the whole budget are not cached.
+    pub(crate) fn insert(&mut self, index: Vec<usize>, data: ArrayD<f32>) {
+        let size = chunk_bytes(&data);
+        if size > self.budget {
+            return;
+        }
+        self.remove(&index);
+        while self.bytes + size > self.budget {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.bytes -= chunk_bytes(&evicted.data);
+            }
+        }
+
+        self.clock += 1;
+        self.bytes += size;
+        self.recency.insert(self.clock, index.clone());
+        self.entries.insert(
+            index,
+            CacheEntry {
+                data,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    /// Change the memory budget, evicting chunks if it shrinks
+    pub(crate) fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        while self.bytes > self.budget {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.bytes -= chunk_bytes(&evicted.data);
+            }
+        }
+    }
+
+    /// Drop every cached chunk, keeping the counters
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.bytes = 0;
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            chunks: self.entries.len(),
+            bytes: self.bytes,
+            budget: self.budget,
+        }
+    }
+
+    fn remove(&mut self, index: &[usize]) {
+        if let Some(entry) = self.entries.remove(index) {
+            self.recency.remove(&entry.last_used);
+            self.bytes -= chunk_bytes(&entry.data);
+        }
+    }
+}
+
+fn chunk_bytes(data: &ArrayD<f32>) -> usize {
+    data.len() * std::mem::size_of::<f32>()
+}
@@ -0,0 +1,350 @@
+//! Zarr chunk grid handling
+//!
+//! This module maps between array coordinates and the chunks of a Zarr array,
+//! following the Zarr v2 and v3 storage specifications: chunk keys, edge
+//! chunks and the C/F memory order of the elements inside each chunk.
+
+use crate::errors::{Result, RuNeVisError};
+use ndarray::{ArrayD, IxDyn, ShapeBuilder, Slice};
+
+/// Memory layout of the elements stored inside a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChunkOrder {
+    /// Row-major order (last dimension varies fastest)
+    #[default]
+    C,
+    /// Column-major order (first dimension varies fastest)
+    F,
+}
+
+impl ChunkOrder {
+    /// Parse the `order` field of a `.zarray` document
+    pub fn parse(order: &str) -> Result<Self> {
+        match order {
+            "C" => Ok(Self::C),
+            "F" => Ok(Self::F),
+            other => Err(RuNeVisError::ZarrError(format!(
+                "Unsupported chunk memory order '{}'",
+                other
+            ))),
+        }
+    }
+
+    /// Get the string representation used in `.zarray` documents
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::C => "C",
+            Self::F => "F",
+        }
+    }
+}
+
+/// Scheme used to build chunk keys from chunk grid indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChunkKeyEncoding {
+    /// Zarr v2 keys: `0.2.1` (or `0/2/1`), `0` for zero-dimensional arrays
+    #[default]
+    V2,
+    /// Zarr v3 `default` keys: `c/0/2/1` (or `c.0.2.1`), `c` for zero-dimensional arrays
+    Default,
+}
+
+impl ChunkKeyEncoding {
+    /// Parse the `name` of a v3 `chunk_key_encoding`
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "v2" => Ok(Self::V2),
+            "default" => Ok(Self::Default),
+            other => Err(RuNeVisError::ZarrError(format!(
+                "Unsupported chunk key encoding '{}'",
+                other
+            ))),
+        }
+    }
+
+    /// Get the `name` used in v3 `chunk_key_encoding` metadata
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::V2 => "v2",
+            Self::Default => "default",
+        }
+    }
+}
+
+/// Regular chunk grid of a Zarr array
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkGrid {
+    shape: Vec<usize>,
+    chunks: Vec<usize>,
+    separator: String,
+    order: ChunkOrder,
+    key_encoding: ChunkKeyEncoding,
+}
+
+impl ChunkGrid {
+    /// Create a new chunk grid
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `chunks` does not have one non-zero entry per
+    /// dimension of `shape`, or if the separator is not `.` or `/`.
+    pub fn new(
+        shape: Vec<usize>,
+        chunks: Vec<usize>,
+        separator: &str,
+        order: ChunkOrder,
+    ) -> Result<Self> {
+        if shape.len() != chunks.len() {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Chunk shape {:?} does not match array shape {:?}",
+                chunks, shape
+            )));
+        }
+        if chunks.contains(&0) {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Chunk shape {:?} contains a zero-length dimension",
+                chunks
+            )));
+        }
+        if separator != "." && separator != "/" {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Unsupported dimension separator '{}'",
+                separator
+            )));
+        }
+
+        Ok(Self {
+            shape,
+            chunks,
+            separator: separator.to_string(),
+            order,
+            key_encoding: ChunkKeyEncoding::V2,
+        })
+    }
+
+    /// The same grid over an array of a different shape, e.g. after a resize
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `shape` does not have one entry per chunk dimension.
+    pub fn with_shape(&self, shape: Vec<usize>) -> Result<Self> {
+        if shape.len() != self.chunks.len() {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Shape {:?} does not match chunk shape {:?}",
+                shape, self.chunks
+            )));
+        }
+        Ok(Self {
+            shape,
+            ..self.clone()
+        })
+    }
+
+    /// Use the given chunk key encoding instead of the v2 one
+    #[must_use]
+    pub fn with_key_encoding(mut self, key_encoding: ChunkKeyEncoding) -> Self {
+        self.key_encoding = key_encoding;
+        self
+    }
+
+    /// Shape of the array covered by the grid
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Shape of a single chunk
+    pub fn chunks(&self) -> &[usize] {
+        &self.chunks
+    }
+
+    /// Separator placed between chunk indices in chunk keys
+    pub fn separator(&self) -> &str {
+        &self.separator
+    }
+
+    /// Memory order of the elements inside each chunk
+    pub fn order(&self) -> ChunkOrder {
+        self.order
+    }
+
+    /// Scheme used to build chunk keys
+    pub fn key_encoding(&self) -> ChunkKeyEncoding {
+        self.key_encoding
+    }
+
+    /// Number of elements in a full chunk
+    pub fn chunk_len(&self) -> usize {
+        self.chunks.iter().product()
+    }
+
+    /// Number of chunks along each dimension
+    pub fn grid_shape(&self) -> Vec<usize> {
+        self.shape
+            .iter()
+            .zip(&self.chunks)
+            .map(|(&n, &c)| n.div_ceil(c))
+            .collect()
+    }
+
+    /// Storage key of the chunk at `index` in the chunk grid, e.g. `0.2.1` or `c/0/2/1`
+    pub fn chunk_key(&self, index: &[usize]) -> String {
+        let indices = index.iter().map(|i| i.to_string());
+        match self.key_encoding {
+            // Zero-dimensional arrays store their single chunk under "0"
+            ChunkKeyEncoding::V2 if index.is_empty() => "0".to_string(),
+            ChunkKeyEncoding::V2 => indices.collect::<Vec<_>>().join(&self.separator),
+            ChunkKeyEncoding::Default => std::iter::once("c".to_string())
+                .chain(indices)
+                .collect::<Vec<_>>()
+                .join(&self.separator),
+        }
+    }
+
+    /// Array coordinate of the first element of the chunk at `index`
+    pub fn chunk_origin(&self, index: &[usize]) -> Vec<usize> {
+        index
+            .iter()
+            .zip(&self.chunks)
+            .map(|(&i, &c)| i * c)
+            .collect()
+    }
+
+    /// Array region `(start, end)` per dimension covered by the chunk at `index`,
+    /// clipped to the array bounds for edge chunks
+    pub fn chunk_region(&self, index: &[usize]) -> Vec<(usize, usize)> {
+        index
+            .iter()
+            .zip(self.chunks.iter().zip(&self.shape))
+            .map(|(&i, (&c, &n))| {
+                let start = i * c;
+                (start, (start + c).min(n))
+            })
+            .collect()
+    }
+
+    /// Grid indices of every chunk, in C order
+    pub fn chunk_indices(&self) -> Vec<Vec<usize>> {
+        let full: Vec<(usize, usize)> = self.shape.iter().map(|&n| (0, n)).collect();
+        self.chunks_intersecting(&full)
+    }
+
+    /// Grid indices of the chunks that intersect the given region, in C order
+    ///
+    /// `ranges` holds one half-open `(start, end)` range per dimension.
+    pub fn chunks_intersecting(&self, ranges: &[(usize, usize)]) -> Vec<Vec<usize>> {
+        if ranges.iter().any(|&(start, end)| start >= end) {
+            return Vec::new();
+        }
+
+        let bounds: Vec<(usize, usize)> = ranges
+            .iter()
+            .zip(&self.chunks)
+            .map(|(&(start, end), &c)| (start / c, (end - 1) / c))
+            .collect();
+
+        let mut indices = Vec::new();
+        let mut current: Vec<usize> = bounds.iter().map(|&(first, _)| first).collect();
+        loop {
+            indices.push(current.clone());
+
+            // Advance the last dimension fastest, carrying into earlier ones
+            let mut dim = current.len();
+            loop {
+                if dim == 0 {
+                    return indices;
+                }
+                dim -= 1;
+                if current[dim] < bounds[dim].1 {
+                    current[dim] += 1;
+                    break;
+                }
+                current[dim] = bounds[dim].0;
+            }
+        }
+    }
+
+    /// Build a full chunk-shaped array from decoded chunk elements
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of elements does not match the chunk shape.
+    pub fn chunk_from_values<T>(&self, values: Vec<T>) -> Result<ArrayD<T>> {
+        if values.len() != self.chunk_len() {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Chunk holds {} elements, expected {} for chunk shape {:?}",
+                values.len(),
+                self.chunk_len(),
+                self.chunks
+            )));
+        }
+
+        let array = match self.order {
+            ChunkOrder::C => ArrayD::from_shape_vec(IxDyn(&self.chunks), values)?,
+            ChunkOrder::F => ArrayD::from_shape_vec(IxDyn(&self.chunks).f(), values)?,
+        };
+        Ok(array)
+    }
+
+    /// Cut the chunk at `index` out of a full array, padding edge chunks with `fill_value`
+    pub fn extract_chunk<T: Clone>(
+        &self,
+        data: &ArrayD<T>,
+        index: &[usize],
+        fill_value: T,
+    ) -> ArrayD<T> {
+        let mut chunk = ArrayD::from_elem(IxDyn(&self.chunks), fill_value);
+        let chunk_ranges: Vec<(usize, usize)> = self
+            .chunk_origin(index)
+            .iter()
+            .zip(&self.chunks)
+            .map(|(&origin, &len)| (origin, origin + len))
+            .collect();
+        copy_overlap(data, &vec![0; data.ndim()], &mut chunk, &chunk_ranges);
+        chunk
+    }
+
+    /// Flatten a full chunk-shaped array into elements in storage order
+    pub fn chunk_to_values<T: Clone>(&self, chunk: &ArrayD<T>) -> Vec<T> {
+        match self.order {
+            ChunkOrder::C => chunk.iter().cloned().collect(),
+            ChunkOrder::F => chunk.t().iter().cloned().collect(),
+        }
+    }
+}
+
+/// Copy the part of `source` that overlaps the region covered by `target`
+///
+/// `source_origin` is the array coordinate of the first element of `source`
+/// (e.g. a decoded chunk) and `target_ranges` the array region that `target`
+/// represents.
+pub fn copy_overlap<T: Clone>(
+    source: &ArrayD<T>,
+    source_origin: &[usize],
+    target: &mut ArrayD<T>,
+    target_ranges: &[(usize, usize)],
+) {
+    let overlap: Vec<(usize, usize)> = source_origin
+        .iter()
+        .zip(source.shape())
+        .zip(target_ranges)
+        .map(|((&origin, &len), &(start, end))| (origin.max(start), (origin + len).min(end)))
+        .collect();
+
+    if overlap.iter().any(|&(lo, hi)| lo >= hi) {
+        return;
+    }
+
+    let source = source.slice_each_axis(|ax| {
+        let (lo, hi) = overlap[ax.axis.index()];
+        let origin = source_origin[ax.axis.index()];
+        Slice::from(lo - origin..hi - origin)
+    });
+    let mut destination = target.slice_each_axis_mut(|ax| {
+        let (lo, hi) = overlap[ax.axis.index()];
+        let start = target_ranges[ax.axis.index()].0;
+        Slice::from(lo - start..hi - start)
+    });
+    destination.assign(&source);
+}
@@ -0,0 +1,733 @@
+//! Zarr chunk codecs
+//!
+//! This module decodes and encodes chunk bytes with the numcodecs filters and
+//! compressors commonly used by Zarr v2 stores. A [`CodecPipeline`] combines
+//! the `filters` declared for an array with its `compressor`, and maps to and
+//! from the `codecs` chain of Zarr v3 metadata.
+
+use super::blosc::{self, BloscShuffle};
+use super::dtype::{DataType, Endianness};
+use crate::errors::{Result, RuNeVisError};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use serde_json::Value as JsonValue;
+use std::io::{Read, Write};
+
+/// Compressor applied to the bytes of every chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compressor {
+    /// Blosc meta-compressor with an internal codec (`lz4`, `zstd`, `zlib`, ...)
+    Blosc {
+        cname: String,
+        clevel: u8,
+        shuffle: BloscShuffle,
+        blocksize: usize,
+    },
+    /// Zstandard
+    Zstd { level: i32 },
+    /// GZip (deflate with gzip framing)
+    Gzip { level: u32 },
+    /// Zlib (deflate with zlib framing)
+    Zlib { level: u32 },
+    /// LZ4 block prefixed with its uncompressed size
+    Lz4 { acceleration: i32 },
+}
+
+impl Compressor {
+    /// Blosc with byte shuffle, as used by default in zarr-python
+    pub fn blosc(cname: &str, clevel: u8) -> Self {
+        Self::Blosc {
+            cname: cname.to_string(),
+            clevel,
+            shuffle: BloscShuffle::Shuffle,
+            blocksize: 0,
+        }
+    }
+
+    /// Zstandard at the given level
+    pub fn zstd(level: i32) -> Self {
+        Self::Zstd { level }
+    }
+
+    /// GZip at the given level (0-9)
+    pub fn gzip(level: u32) -> Self {
+        Self::Gzip { level }
+    }
+
+    /// LZ4 with default acceleration
+    pub fn lz4() -> Self {
+        Self::Lz4 { acceleration: 1 }
+    }
+
+    /// Parse the `compressor` field of a `.zarray` document
+    ///
+    /// Returns `None` for uncompressed arrays (`"compressor": null`).
+    pub fn from_json(config: &JsonValue) -> Result<Option<Self>> {
+        if config.is_null() {
+            return Ok(None);
+        }
+
+        let id = config["id"].as_str().ok_or_else(|| {
+            RuNeVisError::ZarrError(format!("Compressor without an id: {}", config))
+        })?;
+        let int_field = |field: &str, default: i64| config[field].as_i64().unwrap_or(default);
+
+        let compressor = match id {
+            "blosc" => Self::Blosc {
+                cname: config["cname"].as_str().unwrap_or("lz4").to_string(),
+                clevel: int_field("clevel", 5).clamp(0, 9) as u8,
+                shuffle: BloscShuffle::from_code(int_field("shuffle", 1))?,
+                blocksize: int_field("blocksize", 0).max(0) as usize,
+            },
+            "zstd" => Self::Zstd {
+                level: int_field("level", 1) as i32,
+            },
+            "gzip" => Self::Gzip {
+                level: int_field("level", 1).clamp(0, 9) as u32,
+            },
+            "zlib" => Self::Zlib {
+                level: int_field("level", 1).clamp(0, 9) as u32,
+            },
+            "lz4" => Self::Lz4 {
+                acceleration: int_field("acceleration", 1) as i32,
+            },
+            other => {
+                return Err(RuNeVisError::ZarrError(format!(
+                    "Unsupported compressor '{}'",
+                    other
+                )))
+            }
+        };
+        Ok(Some(compressor))
+    }
+
+    /// Generate the numcodecs configuration for the `compressor` field
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            Self::Blosc {
+                cname,
+                clevel,
+                shuffle,
+                blocksize,
+            } => serde_json::json!({
+                "id": "blosc",
+                "cname": cname,
+                "clevel": clevel,
+                "shuffle": shuffle.code(),
+                "blocksize": blocksize
+            }),
+            Self::Zstd { level } => serde_json::json!({ "id": "zstd", "level": level }),
+            Self::Gzip { level } => serde_json::json!({ "id": "gzip", "level": level }),
+            Self::Zlib { level } => serde_json::json!({ "id": "zlib", "level": level }),
+            Self::Lz4 { acceleration } => {
+                serde_json::json!({ "id": "lz4", "acceleration": acceleration })
+            }
+        }
+    }
+
+    /// Generate the Zarr v3 codec entry for this compressor
+    ///
+    /// `typesize` is the width of the elements being compressed, recorded by
+    /// the v3 `blosc` codec.
+    pub fn to_v3_json(&self, typesize: usize) -> JsonValue {
+        match self {
+            Self::Blosc {
+                cname,
+                clevel,
+                shuffle,
+                blocksize,
+            } => serde_json::json!({
+                "name": "blosc",
+                "configuration": {
+                    "cname": cname,
+                    "clevel": clevel,
+                    "shuffle": shuffle.name(),
+                    "typesize": typesize,
+                    "blocksize": blocksize
+                }
+            }),
+            Self::Zstd { level } => serde_json::json!({
+                "name": "zstd",
+                "configuration": { "level": level, "checksum": false }
+            }),
+            Self::Gzip { level } => serde_json::json!({
+                "name": "gzip",
+                "configuration": { "level": level }
+            }),
+            // Not part of the v3 core; zarr-python exposes them under "numcodecs."
+            Self::Zlib { level } => serde_json::json!({
+                "name": "numcodecs.zlib",
+                "configuration": { "level": level }
+            }),
+            Self::Lz4 { acceleration } => serde_json::json!({
+                "name": "numcodecs.lz4",
+                "configuration": { "acceleration": acceleration }
+            }),
+        }
+    }
+
+    /// Decompress the stored bytes of a chunk
+    pub fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Blosc { .. } => blosc::decompress(bytes),
+            Self::Zstd { .. } => {
+                zstd::stream::decode_all(bytes).map_err(|e| codec_error("Zstd", &e))
+            }
+            Self::Gzip { .. } => {
+                let mut decoded = Vec::new();
+                GzDecoder::new(bytes)
+                    .read_to_end(&mut decoded)
+                    .map_err(|e| codec_error("GZip", &e))?;
+                Ok(decoded)
+            }
+            Self::Zlib { .. } => {
+                let mut decoded = Vec::new();
+                ZlibDecoder::new(bytes)
+                    .read_to_end(&mut decoded)
+                    .map_err(|e| codec_error("Zlib", &e))?;
+                Ok(decoded)
+            }
+            Self::Lz4 { .. } => lz4_flex::block::decompress_size_prepended(bytes)
+                .map_err(|e| codec_error("LZ4", &e)),
+        }
+    }
+
+    /// Compress the bytes of a chunk whose elements are `typesize` bytes wide
+    pub fn encode(&self, bytes: &[u8], typesize: usize) -> Result<Vec<u8>> {
+        match self {
+            Self::Blosc {
+                cname,
+                clevel,
+                shuffle,
+                blocksize,
+            } => blosc::compress(bytes, typesize, cname, *clevel, *shuffle, *blocksize),
+            Self::Zstd { level } => {
+                zstd::stream::encode_all(bytes, *level).map_err(RuNeVisError::IoError)
+            }
+            Self::Gzip { level } => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::new(*level));
+                encoder.write_all(bytes).map_err(RuNeVisError::IoError)?;
+                encoder.finish().map_err(RuNeVisError::IoError)
+            }
+            Self::Zlib { level } => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(*level));
+                encoder.write_all(bytes).map_err(RuNeVisError::IoError)?;
+                encoder.finish().map_err(RuNeVisError::IoError)
+            }
+            Self::Lz4 { .. } => Ok(lz4_flex::block::compress_prepend_size(bytes)),
+        }
+    }
+}
+
+/// Filter applied to the chunk bytes before compression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Store the differences between consecutive elements
+    Delta { dtype: DataType, astype: DataType },
+    /// Store `round((x - offset) * scale)`, typically in a narrower integer type
+    FixedScaleOffset {
+        scale: f64,
+        offset: f64,
+        dtype: DataType,
+        astype: DataType,
+    },
+    /// Round values to the given number of significant decimal digits
+    Quantize {
+        digits: i32,
+        dtype: DataType,
+        astype: DataType,
+    },
+    /// Group the n-th byte of every element together
+    Shuffle { elementsize: usize },
+}
+
+impl Filter {
+    /// Parse one entry of the `filters` list of a `.zarray` document
+    pub fn from_json(config: &JsonValue) -> Result<Self> {
+        let id = config["id"]
+            .as_str()
+            .ok_or_else(|| RuNeVisError::ZarrError(format!("Filter without an id: {}", config)))?;
+        let dtype_field = |field: &str| -> Result<DataType> {
+            let dtype = config[field].as_str().ok_or_else(|| {
+                RuNeVisError::ZarrError(format!("Filter '{}' is missing '{}'", id, field))
+            })?;
+            DataType::parse(dtype)
+        };
+        // `astype` defaults to `dtype` in numcodecs
+        let astype_field = || -> Result<DataType> {
+            if config["astype"].is_string() {
+                dtype_field("astype")
+            } else {
+                dtype_field("dtype")
+            }
+        };
+
+        match id {
+            "delta" => Ok(Self::Delta {
+                dtype: dtype_field("dtype")?,
+                astype: astype_field()?,
+            }),
+            "fixedscaleoffset" => Ok(Self::FixedScaleOffset {
+                scale: config["scale"].as_f64().unwrap_or(1.0),
+                offset: config["offset"].as_f64().unwrap_or(0.0),
+                dtype: dtype_field("dtype")?,
+                astype: astype_field()?,
+            }),
+            "quantize" => Ok(Self::Quantize {
+                digits: config["digits"].as_i64().ok_or_else(|| {
+                    RuNeVisError::ZarrError("Filter 'quantize' is missing 'digits'".to_string())
+                })? as i32,
+                dtype: dtype_field("dtype")?,
+                astype: astype_field()?,
+            }),
+            "shuffle" => Ok(Self::Shuffle {
+                elementsize: config["elementsize"].as_u64().unwrap_or(4).max(1) as usize,
+            }),
+            other => Err(RuNeVisError::ZarrError(format!(
+                "Unsupported filter '{}'",
+                other
+            ))),
+        }
+    }
+
+    /// Generate the numcodecs configuration for this filter
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            Self::Delta { dtype, astype } => serde_json::json!({
+                "id": "delta",
+                "dtype": dtype.to_type_string(),
+                "astype": astype.to_type_string()
+            }),
+            Self::FixedScaleOffset {
+                scale,
+                offset,
+                dtype,
+                astype,
+            } => serde_json::json!({
+                "id": "fixedscaleoffset",
+                "scale": scale,
+                "offset": offset,
+                "dtype": dtype.to_type_string(),
+                "astype": astype.to_type_string()
+            }),
+            Self::Quantize {
+                digits,
+                dtype,
+                astype,
+            } => serde_json::json!({
+                "id": "quantize",
+                "digits": digits,
+                "dtype": dtype.to_type_string(),
+                "astype": astype.to_type_string()
+            }),
+            Self::Shuffle { elementsize } => {
+                serde_json::json!({ "id": "shuffle", "elementsize": elementsize })
+            }
+        }
+    }
+
+    /// Whether the filter works on serialised bytes rather than on elements
+    ///
+    /// Zarr v3 places bytes-to-bytes codecs after the `bytes` codec of a
+    /// chain and array-to-array codecs before it.
+    pub fn is_bytes_to_bytes(&self) -> bool {
+        matches!(self, Self::Shuffle { .. })
+    }
+
+    /// Width in bytes of the elements this filter produces
+    pub fn encoded_itemsize(&self) -> usize {
+        match self {
+            Self::Delta { astype, .. }
+            | Self::FixedScaleOffset { astype, .. }
+            | Self::Quantize { astype, .. } => astype.size,
+            Self::Shuffle { elementsize } => *elementsize,
+        }
+    }
+
+    /// Apply the filter to raw chunk bytes
+    pub fn encode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Delta { dtype, astype } => {
+                let values = dtype.decode(bytes)?;
+                let mut previous = 0.0;
+                let deltas: Vec<f64> = values
+                    .iter()
+                    .map(|&value| {
+                        let delta = value - previous;
+                        previous = value;
+                        delta
+                    })
+                    .collect();
+                Ok(astype.encode(&deltas))
+            }
+            Self::FixedScaleOffset {
+                scale,
+                offset,
+                dtype,
+                astype,
+            } => {
+                let packed: Vec<f64> = dtype
+                    .decode(bytes)?
+                    .iter()
+                    .map(|&value| ((value - offset) * scale).round_ties_even())
+                    .collect();
+                Ok(astype.encode(&packed))
+            }
+            Self::Quantize {
+                digits,
+                dtype,
+                astype,
+            } => {
+                let scale = quantize_scale(*digits);
+                let quantized: Vec<f64> = dtype
+                    .decode(bytes)?
+                    .iter()
+                    .map(|&value| (scale * value).round_ties_even() / scale)
+                    .collect();
+                Ok(astype.encode(&quantized))
+            }
+            Self::Shuffle { elementsize } => Ok(blosc::byte_shuffle(bytes, *elementsize)),
+        }
+    }
+
+    /// Undo the filter, restoring raw chunk bytes
+    pub fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Delta { dtype, astype } => {
+                let mut total = 0.0;
+                let values: Vec<f64> = astype
+                    .decode(bytes)?
+                    .iter()
+                    .map(|&delta| {
+                        total += delta;
+                        total
+                    })
+                    .collect();
+                Ok(dtype.encode(&values))
+            }
+            Self::FixedScaleOffset {
+                scale,
+                offset,
+                dtype,
+                astype,
+            } => {
+                let values: Vec<f64> = astype
+                    .decode(bytes)?
+                    .iter()
+                    .map(|&packed| packed / scale + offset)
+                    .collect();
+                Ok(dtype.encode(&values))
+            }
+            Self::Quantize { dtype, astype, .. } => Ok(dtype.encode(&astype.decode(bytes)?)),
+            Self::Shuffle { elementsize } => Ok(blosc::byte_unshuffle(bytes, *elementsize)),
+        }
+    }
+}
+
+/// Power-of-two scale that keeps `digits` significant decimal digits (as in numcodecs)
+fn quantize_scale(digits: i32) -> f64 {
+    let exponent = -f64::from(digits);
+    let exponent = if exponent < 0.0 {
+        exponent.floor()
+    } else {
+        exponent.ceil()
+    };
+    let bits = 10f64.powf(-exponent).log2().ceil();
+    2f64.powf(bits)
+}
+
+/// Filters and compressor applied, in order, to every chunk of an array
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CodecPipeline {
+    pub filters: Vec<Filter>,
+    pub compressor: Option<Compressor>,
+    /// Append a CRC32C checksum to every chunk (Zarr v3 `crc32c` codec)
+    pub checksum: bool,
+}
+
+impl CodecPipeline {
+    /// Parse the `filters` and `compressor` fields of a `.zarray` document
+    pub fn from_zarray(doc: &JsonValue) -> Result<Self> {
+        let filters = match doc.get("filters") {
+            Some(JsonValue::Array(configs)) => configs
+                .iter()
+                .map(Filter::from_json)
+                .collect::<Result<Vec<_>>>()?,
+            _ => Vec::new(),
+        };
+        let compressor = Compressor::from_json(doc.get("compressor").unwrap_or(&JsonValue::Null))?;
+
+        Ok(Self {
+            filters,
+            compressor,
+            checksum: false,
+        })
+    }
+
+    /// Parse a Zarr v3 `codecs` chain
+    ///
+    /// Array-to-array codecs that change the memory order (`transpose`) must
+    /// already have been removed by the caller.
+    pub fn from_v3_codecs(codecs: &[JsonValue]) -> Result<Self> {
+        let mut pipeline = Self::default();
+        let mut seen_bytes = false;
+
+        for codec in codecs {
+            let name = codec["name"].as_str().ok_or_else(|| {
+                RuNeVisError::ZarrError(format!("Codec without a name: {}", codec))
+            })?;
+            if pipeline.checksum {
+                return Err(RuNeVisError::ZarrError(
+                    "Codec 'crc32c' must be the last codec of the chain".to_string(),
+                ));
+            }
+
+            match name {
+                // The byte order is part of the array data type (see `bytes_endianness`)
+                "bytes" => seen_bytes = true,
+                "crc32c" => pipeline.checksum = true,
+                _ if is_filter_id(name.strip_prefix("numcodecs.").unwrap_or(name)) => {
+                    let filter = Filter::from_json(&numcodecs_config(codec, name))?;
+                    if filter.is_bytes_to_bytes() && (!seen_bytes || pipeline.compressor.is_some())
+                    {
+                        return Err(RuNeVisError::ZarrError(format!(
+                            "Codec '{}' works on bytes and must come between 'bytes' and the compressor",
+                            name
+                        )));
+                    }
+                    if !filter.is_bytes_to_bytes() && seen_bytes {
+                        return Err(RuNeVisError::ZarrError(format!(
+                            "Codec '{}' works on elements and must come before 'bytes'",
+                            name
+                        )));
+                    }
+                    pipeline.filters.push(filter);
+                }
+                _ if !seen_bytes => {
+                    return Err(RuNeVisError::ZarrError(format!(
+                        "Unsupported array-to-array codec '{}'",
+                        name
+                    )));
+                }
+                _ => {
+                    if pipeline.compressor.is_some() {
+                        return Err(RuNeVisError::ZarrError(format!(
+                            "Chained compressors are not supported (found '{}')",
+                            name
+                        )));
+                    }
+                    pipeline.compressor = Compressor::from_json(&numcodecs_config(codec, name))?;
+                }
+            }
+        }
+
+        if !seen_bytes {
+            return Err(RuNeVisError::ZarrError(
+                "Codec chain has no 'bytes' codec".to_string(),
+            ));
+        }
+        Ok(pipeline)
+    }
+
+    /// Byte order declared by the `bytes` codec of a v3 codec chain (little-endian by default)
+    pub fn bytes_endianness(codecs: &[JsonValue]) -> Endianness {
+        let big = codecs.iter().any(|codec| {
+            codec["name"].as_str() == Some("bytes")
+                && codec["configuration"]["endian"].as_str() == Some("big")
+        });
+        if big {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+
+    /// Check that the filters can be split around the `bytes` codec of a v3 chain
+    ///
+    /// Zarr v3 applies every array-to-array codec before the elements are
+    /// serialised and every bytes-to-bytes codec after, so a bytes-to-bytes
+    /// filter such as `shuffle` cannot precede an array-to-array one.
+    pub fn check_v3_order(&self) -> Result<()> {
+        let first_bytes_filter = self.filters.iter().position(Filter::is_bytes_to_bytes);
+        let late_array_filter = first_bytes_filter.and_then(|first| {
+            self.filters[first..]
+                .iter()
+                .find(|filter| !filter.is_bytes_to_bytes())
+        });
+        match late_array_filter {
+            Some(filter) => Err(RuNeVisError::ZarrError(format!(
+                "Filter '{}' works on elements and cannot follow a bytes-to-bytes filter in Zarr v3",
+                filter.to_json()["id"].as_str().unwrap_or_default()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Generate the Zarr v3 `codecs` chain (without any `transpose` codec) for elements of `dtype`
+    ///
+    /// Array-to-array filters come before the `bytes` codec and bytes-to-bytes
+    /// filters after it (see [`CodecPipeline::check_v3_order`]).
+    pub fn to_v3_codecs(&self, dtype: &DataType) -> Vec<JsonValue> {
+        let (bytes_filters, array_filters): (Vec<&Filter>, Vec<&Filter>) = self
+            .filters
+            .iter()
+            .partition(|filter| filter.is_bytes_to_bytes());
+        let mut codecs: Vec<JsonValue> = array_filters.into_iter().map(filter_v3_json).collect();
+
+        let endian = match dtype.endianness {
+            Endianness::Little => "little",
+            Endianness::Big => "big",
+        };
+        codecs.push(serde_json::json!({ "name": "bytes", "configuration": { "endian": endian } }));
+        codecs.extend(bytes_filters.into_iter().map(filter_v3_json));
+
+        let typesize = self
+            .filters
+            .last()
+            .map_or(dtype.size, Filter::encoded_itemsize);
+        if let Some(compressor) = &self.compressor {
+            codecs.push(compressor.to_v3_json(typesize));
+        }
+        if self.checksum {
+            codecs.push(serde_json::json!({ "name": "crc32c" }));
+        }
+        codecs
+    }
+
+    /// Value of the `filters` field (`null` when there are no filters)
+    pub fn filters_json(&self) -> JsonValue {
+        if self.filters.is_empty() {
+            JsonValue::Null
+        } else {
+            JsonValue::Array(self.filters.iter().map(Filter::to_json).collect())
+        }
+    }
+
+    /// Value of the `compressor` field
+    pub fn compressor_json(&self) -> JsonValue {
+        self.compressor
+            .as_ref()
+            .map_or(JsonValue::Null, Compressor::to_json)
+    }
+
+    /// Decode stored chunk bytes: decompress, then undo the filters in reverse order
+    pub fn decode(&self, stored: &[u8]) -> Result<Vec<u8>> {
+        let stored = if self.checksum {
+            strip_crc32c(stored)?
+        } else {
+            stored
+        };
+        let mut bytes = match &self.compressor {
+            Some(compressor) => compressor.decode(stored)?,
+            None => stored.to_vec(),
+        };
+        for filter in self.filters.iter().rev() {
+            bytes = filter.decode(&bytes)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Encode raw chunk bytes of `itemsize`-byte elements: apply the filters, then compress
+    pub fn encode(&self, raw: &[u8], itemsize: usize) -> Result<Vec<u8>> {
+        let mut bytes = raw.to_vec();
+        let mut itemsize = itemsize;
+        for filter in &self.filters {
+            bytes = filter.encode(&bytes)?;
+            itemsize = filter.encoded_itemsize();
+        }
+        let mut encoded = match &self.compressor {
+            Some(compressor) => compressor.encode(&bytes, itemsize)?,
+            None => bytes,
+        };
+        if self.checksum {
+            let checksum = crc32c(&encoded);
+            encoded.extend_from_slice(&checksum.to_le_bytes());
+        }
+        Ok(encoded)
+    }
+}
+
+/// Whether a numcodecs id names a filter rather than a compressor
+fn is_filter_id(id: &str) -> bool {
+    matches!(id, "delta" | "fixedscaleoffset" | "quantize" | "shuffle")
+}
+
+/// Zarr v3 codec entry of a numcodecs filter, named `numcodecs.<id>` as in zarr-python
+fn filter_v3_json(filter: &Filter) -> JsonValue {
+    let mut configuration = filter.to_json();
+    let id = configuration["id"].as_str().unwrap_or_default().to_string();
+    if let Some(fields) = configuration.as_object_mut() {
+        fields.remove("id");
+    }
+    serde_json::json!({ "name": format!("numcodecs.{}", id), "configuration": configuration })
+}
+
+/// Convert a v3 codec entry (`{"name", "configuration"}`) into a numcodecs configuration
+fn numcodecs_config(codec: &JsonValue, name: &str) -> JsonValue {
+    let mut config = match codec.get("configuration") {
+        Some(JsonValue::Object(fields)) => fields.clone(),
+        _ => serde_json::Map::new(),
+    };
+    let id = name.strip_prefix("numcodecs.").unwrap_or(name);
+    config.insert("id".to_string(), JsonValue::String(id.to_string()));
+
+    // The v3 blosc codec names its shuffle modes instead of numbering them
+    if let Some(shuffle) = config.get("shuffle").and_then(JsonValue::as_str) {
+        if let Some(shuffle) = BloscShuffle::from_name(shuffle) {
+            config.insert("shuffle".to_string(), serde_json::json!(shuffle.code()));
+        }
+    }
+    JsonValue::Object(config)
+}
+
+/// Check and remove the little-endian CRC32C checksum at the end of a chunk
+fn strip_crc32c(stored: &[u8]) -> Result<&[u8]> {
+    if stored.len() < 4 {
+        return Err(RuNeVisError::ZarrError(
+            "Chunk is too short to hold a CRC32C checksum".to_string(),
+        ));
+    }
+    let (payload, checksum) = stored.split_at(stored.len() - 4);
+    let expected = u32::from_le_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+    if crc32c(payload) != expected {
+        return Err(RuNeVisError::ZarrError(
+            "CRC32C checksum mismatch".to_string(),
+        ));
+    }
+    Ok(payload)
+}
+
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+const fn crc32c_table() -> [u32; 256] {
+    // Reflected Castagnoli polynomial
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F6_3B78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC32C (Castagnoli) checksum, as used by the Zarr v3 `crc32c` codec
+pub(crate) fn crc32c(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        CRC32C_TABLE[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn codec_error(codec: &str, error: &dyn std::fmt::Display) -> RuNeVisError {
+    RuNeVisError::ZarrError(format!("{} decompression failed: {}", codec, error))
+}
@@ -0,0 +1,199 @@
+//! Consolidated metadata
+//!
+//! Consolidated metadata gathers the metadata documents of every group and
+//! array of a store into a single document, so that a store can be listed and
+//! opened with one read. Zarr v2 stores keep it in a root `.zmetadata`
+//! document, Zarr v3 stores inline it in the root `zarr.json`.
+
+use super::store::{join_key, ZarrStore};
+use super::{NodeKind, ZarrFormat};
+use crate::errors::{Result, RuNeVisError};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashMap};
+
+/// Name of the Zarr v2 consolidated metadata document
+pub(crate) const ZMETADATA: &str = ".zmetadata";
+
+/// Names of the per-node documents gathered by consolidation
+const NODE_DOCUMENTS: [&str; 4] = [".zgroup", ".zarray", ".zattrs", "zarr.json"];
+
+/// Metadata documents of a store, keyed by `<node path>/<document name>`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConsolidatedMetadata {
+    documents: HashMap<String, JsonValue>,
+}
+
+impl ConsolidatedMetadata {
+    /// Load the consolidated metadata of a store, if it has any
+    pub(crate) fn load(store: &dyn ZarrStore) -> Result<Option<Self>> {
+        if let Some(doc) = read_optional_json(store, ZMETADATA)? {
+            return Self::from_zmetadata(&doc).map(Some);
+        }
+
+        let Some(root_doc) = read_optional_json(store, "zarr.json")? else {
+            return Ok(None);
+        };
+        let Some(consolidated) = root_doc
+            .get("consolidated_metadata")
+            .filter(|c| !c.is_null())
+        else {
+            return Ok(None);
+        };
+        let nodes = consolidated["metadata"].as_object().ok_or_else(|| {
+            RuNeVisError::ZarrError("Consolidated metadata has no 'metadata' object".to_string())
+        })?;
+
+        let mut documents: HashMap<String, JsonValue> = nodes
+            .iter()
+            .map(|(node_path, doc)| (join_key(node_path, "zarr.json"), doc.clone()))
+            .collect();
+        documents.insert("zarr.json".to_string(), root_doc);
+        Ok(Some(Self { documents }))
+    }
+
+    /// Parse a Zarr v2 `.zmetadata` document
+    fn from_zmetadata(doc: &JsonValue) -> Result<Self> {
+        if doc["zarr_consolidated_format"].as_u64() != Some(1) {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Unsupported zarr_consolidated_format {}",
+                doc["zarr_consolidated_format"]
+            )));
+        }
+        let documents = doc["metadata"]
+            .as_object()
+            .ok_or_else(|| {
+                RuNeVisError::ZarrError(format!("{} has no 'metadata' object", ZMETADATA))
+            })?
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        Ok(Self { documents })
+    }
+
+    /// Gather the metadata documents of every node reachable from the root group
+    pub(crate) fn collect(store: &dyn ZarrStore) -> Result<Self> {
+        let mut metadata = Self::default();
+        metadata.collect_node(store, "")?;
+        Ok(metadata)
+    }
+
+    fn collect_node(&mut self, store: &dyn ZarrStore, node_path: &str) -> Result<()> {
+        for name in NODE_DOCUMENTS {
+            let key = join_key(node_path, name);
+            if let Some(doc) = read_optional_json(store, &key)? {
+                self.documents.insert(key, doc);
+            }
+        }
+
+        // Only groups have children; chunk directories of arrays are skipped
+        if !matches!(self.node_kind(node_path), Some(NodeKind::Group(_))) && !node_path.is_empty() {
+            return Ok(());
+        }
+        for name in store.list_dir(node_path)? {
+            let child = join_key(node_path, &name);
+            if super::node_kind(store, &child).is_some() {
+                self.collect_node(store, &child)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the documents as consolidated metadata in the given format
+    pub(crate) fn write(&self, store: &dyn ZarrStore, format: ZarrFormat) -> Result<()> {
+        match format {
+            ZarrFormat::V2 => {
+                // Sort the keys so that the document is stable across writes
+                let metadata: BTreeMap<&String, &JsonValue> = self
+                    .documents
+                    .iter()
+                    .filter(|(key, _)| !key.ends_with("zarr.json"))
+                    .collect();
+                let doc = serde_json::json!({
+                    "zarr_consolidated_format": 1,
+                    "metadata": metadata
+                });
+                store.set(
+                    ZMETADATA,
+                    serde_json::to_string_pretty(&doc).unwrap().as_bytes(),
+                )
+            }
+            ZarrFormat::V3 => {
+                let mut root_doc = self.documents.get("zarr.json").cloned().ok_or_else(|| {
+                    RuNeVisError::ZarrError("Zarr v3 store has no root group".to_string())
+                })?;
+                let metadata: BTreeMap<&str, JsonValue> = self
+                    .documents
+                    .iter()
+                    .filter_map(|(key, doc)| {
+                        let node_path = key.strip_suffix("/zarr.json")?;
+                        // Nested groups do not repeat the consolidated metadata
+                        let mut doc = doc.clone();
+                        if let Some(fields) = doc.as_object_mut() {
+                            fields.remove("consolidated_metadata");
+                        }
+                        Some((node_path, doc))
+                    })
+                    .collect();
+                root_doc["consolidated_metadata"] = serde_json::json!({
+                    "kind": "inline",
+                    "must_understand": false,
+                    "metadata": metadata
+                });
+                store.set(
+                    "zarr.json",
+                    serde_json::to_string_pretty(&root_doc).unwrap().as_bytes(),
+                )
+            }
+        }
+    }
+
+    /// Get a metadata document (e.g. `.zarray`) of the node at `node_path`
+    pub(crate) fn document(&self, node_path: &str, name: &str) -> Option<&JsonValue> {
+        self.documents.get(&join_key(node_path, name))
+    }
+
+    /// Determine whether the node at `node_path` is an array or a group
+    pub(crate) fn node_kind(&self, node_path: &str) -> Option<NodeKind> {
+        if self.document(node_path, ".zarray").is_some() {
+            return Some(NodeKind::Array(ZarrFormat::V2));
+        }
+        if self.document(node_path, ".zgroup").is_some() {
+            return Some(NodeKind::Group(ZarrFormat::V2));
+        }
+        match self.document(node_path, "zarr.json")?["node_type"].as_str() {
+            Some("array") => Some(NodeKind::Array(ZarrFormat::V3)),
+            Some("group") => Some(NodeKind::Group(ZarrFormat::V3)),
+            _ => None,
+        }
+    }
+
+    /// Paths and kinds of every node below the root
+    pub(crate) fn nodes(&self) -> Vec<(String, NodeKind)> {
+        let mut paths: Vec<&str> = self
+            .documents
+            .keys()
+            .filter_map(|key| {
+                NODE_DOCUMENTS
+                    .iter()
+                    .find_map(|name| key.strip_suffix(&format!("/{}", name)))
+            })
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+
+        paths
+            .into_iter()
+            .filter_map(|path| Some((path.to_string(), self.node_kind(path)?)))
+            .collect()
+    }
+}
+
+/// Read and parse a JSON document, returning `None` if it does not exist
+pub(crate) fn read_optional_json(store: &dyn ZarrStore, key: &str) -> Result<Option<JsonValue>> {
+    let Some(content) = store.get(key)? else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| RuNeVisError::ZarrError(format!("Failed to parse metadata {}: {}", key, e)))
+}
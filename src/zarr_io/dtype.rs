@@ -0,0 +1,326 @@
+//! Zarr data types
+//!
+//! Parsing of NumPy-style type strings (`<f4`, `>i2`, `|u1`, `|b1`, ...) used
+//! by Zarr v2, of the Zarr v3 data type names (`float32`, `int16`, `bool`, ...)
+//! and conversion between raw element bytes and values of an [`Element`] type.
+
+use crate::errors::{Result, RuNeVisError};
+
+/// Kind of numeric element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataKind {
+    /// Boolean stored as one byte (0 or 1)
+    Bool,
+    /// Signed integer
+    Int,
+    /// Unsigned integer
+    UInt,
+    /// IEEE 754 floating point
+    Float,
+}
+
+/// Byte order of multi-byte elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// Element type of a Zarr array or filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataType {
+    pub kind: DataKind,
+    /// Element size in bytes
+    pub size: usize,
+    pub endianness: Endianness,
+}
+
+impl DataType {
+    /// Little-endian 32-bit float (`<f4`)
+    pub const FLOAT32: Self = Self {
+        kind: DataKind::Float,
+        size: 4,
+        endianness: Endianness::Little,
+    };
+
+    /// Parse a NumPy-style type string such as `<f4` or `|u1`
+    pub fn parse(dtype: &str) -> Result<Self> {
+        let unsupported = || RuNeVisError::ZarrError(format!("Unsupported dtype '{}'", dtype));
+
+        let (endianness, code) = match dtype.chars().next() {
+            Some('<') | Some('|') => (Endianness::Little, &dtype[1..]),
+            Some('>') => (Endianness::Big, &dtype[1..]),
+            _ => (Endianness::Little, dtype),
+        };
+        if code.len() < 2 {
+            return Err(unsupported());
+        }
+
+        let kind = match &code[..1] {
+            "b" => DataKind::Bool,
+            "i" => DataKind::Int,
+            "u" => DataKind::UInt,
+            "f" => DataKind::Float,
+            _ => return Err(unsupported()),
+        };
+        let size: usize = code[1..].parse().map_err(|_| unsupported())?;
+
+        let valid = match kind {
+            DataKind::Bool => size == 1,
+            DataKind::Int | DataKind::UInt => matches!(size, 1 | 2 | 4 | 8),
+            DataKind::Float => matches!(size, 4 | 8),
+        };
+        if !valid {
+            return Err(unsupported());
+        }
+
+        Ok(Self {
+            kind,
+            size,
+            endianness,
+        })
+    }
+
+    /// Get the NumPy-style type string, e.g. `<f4`
+    pub fn to_type_string(&self) -> String {
+        let prefix = if self.size == 1 {
+            '|'
+        } else {
+            match self.endianness {
+                Endianness::Little => '<',
+                Endianness::Big => '>',
+            }
+        };
+        let code = match self.kind {
+            DataKind::Bool => 'b',
+            DataKind::Int => 'i',
+            DataKind::UInt => 'u',
+            DataKind::Float => 'f',
+        };
+        format!("{}{}{}", prefix, code, self.size)
+    }
+
+    /// Parse a Zarr v3 `data_type` name, with the byte order given by the `bytes` codec
+    pub fn parse_v3(name: &str, endianness: Endianness) -> Result<Self> {
+        let (kind, size) = match name {
+            "bool" => (DataKind::Bool, 1),
+            "int8" => (DataKind::Int, 1),
+            "int16" => (DataKind::Int, 2),
+            "int32" => (DataKind::Int, 4),
+            "int64" => (DataKind::Int, 8),
+            "uint8" => (DataKind::UInt, 1),
+            "uint16" => (DataKind::UInt, 2),
+            "uint32" => (DataKind::UInt, 4),
+            "uint64" => (DataKind::UInt, 8),
+            "float32" => (DataKind::Float, 4),
+            "float64" => (DataKind::Float, 8),
+            other => {
+                return Err(RuNeVisError::ZarrError(format!(
+                    "Unsupported data_type '{}'",
+                    other
+                )))
+            }
+        };
+        Ok(Self {
+            kind,
+            size,
+            endianness,
+        })
+    }
+
+    /// Get the Zarr v3 `data_type` name, e.g. `float32`
+    pub fn to_v3_name(&self) -> String {
+        match self.kind {
+            DataKind::Bool => "bool".to_string(),
+            DataKind::Int => format!("int{}", self.size * 8),
+            DataKind::UInt => format!("uint{}", self.size * 8),
+            DataKind::Float => format!("float{}", self.size * 8),
+        }
+    }
+
+    /// Decode raw element bytes into values
+    pub fn decode(&self, bytes: &[u8]) -> Result<Vec<f64>> {
+        self.decode_as(bytes)
+    }
+
+    /// Decode raw element bytes into values of type `T`
+    ///
+    /// Floats decoded as `f64` and integers decoded as `i64` (signed) or
+    /// `u64` (unsigned) keep their exact value.
+    pub fn decode_as<T: Element>(&self, bytes: &[u8]) -> Result<Vec<T>> {
+        if bytes.len() % self.size != 0 {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Buffer of {} bytes is not a whole number of '{}' elements",
+                bytes.len(),
+                self.to_type_string()
+            )));
+        }
+
+        Ok(bytes
+            .chunks_exact(self.size)
+            .map(|element| {
+                // Normalise to little-endian before interpreting the element
+                let mut le = [0u8; 8];
+                le[..self.size].copy_from_slice(element);
+                if self.endianness == Endianness::Big {
+                    le[..self.size].reverse();
+                }
+                T::from_element(self.kind, self.size, le)
+            })
+            .collect())
+    }
+
+    /// Encode values as raw element bytes
+    ///
+    /// Conversion to integer types truncates towards zero and wraps around
+    /// the type's range, matching NumPy's `astype`; any non-zero value is `true`.
+    pub fn encode(&self, values: &[f64]) -> Vec<u8> {
+        self.encode_from(values)
+    }
+
+    /// Encode values of type `T` as raw element bytes
+    ///
+    /// Conversions follow [`encode`](Self::encode); `i64` and `u64` values
+    /// of an integer type wide enough are stored exactly.
+    pub fn encode_from<T: Element>(&self, values: &[T]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(values.len() * self.size);
+        for &value in values {
+            let le = value.to_element(self.kind, self.size);
+            let element = &le[..self.size];
+            match self.endianness {
+                Endianness::Little => bytes.extend_from_slice(element),
+                Endianness::Big => bytes.extend(element.iter().rev()),
+            }
+        }
+        bytes
+    }
+}
+
+/// Value type that array elements are decoded into and encoded from
+///
+/// Implemented for `f32`, in which arrays are analysed, and for `f64`, `i64`
+/// and `u64`, which hold every float, signed and unsigned element exactly and
+/// are used to copy arrays without changing their values.
+pub trait Element: Copy + Send + Sync + 'static {
+    /// Value of an element of `kind` and `size` bytes, given in little-endian
+    /// order and padded with zeros
+    fn from_element(kind: DataKind, size: usize, le: [u8; 8]) -> Self;
+
+    /// Little-endian bytes of the value as an element of `kind` and `size`
+    /// bytes; bytes past `size` are ignored
+    fn to_element(self, kind: DataKind, size: usize) -> [u8; 8];
+
+    /// Value of a fill value, which arrays record as `f64`
+    fn from_f64(value: f64) -> Self;
+}
+
+impl Element for f64 {
+    fn from_element(kind: DataKind, size: usize, le: [u8; 8]) -> Self {
+        match kind {
+            DataKind::Float => float_value(size, le),
+            DataKind::Bool => f64::from(u8::from(le[0] != 0)),
+            DataKind::UInt => u64::from_le_bytes(le) as f64,
+            DataKind::Int => signed_value(size, le) as f64,
+        }
+    }
+
+    fn to_element(self, kind: DataKind, size: usize) -> [u8; 8] {
+        match kind {
+            DataKind::Float => float_element(self, size),
+            DataKind::Bool => [u8::from(self != 0.0), 0, 0, 0, 0, 0, 0, 0],
+            // Two's complement truncation to the element width wraps the value
+            DataKind::Int | DataKind::UInt => (self.trunc() as i128 as u64).to_le_bytes(),
+        }
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+impl Element for f32 {
+    fn from_element(kind: DataKind, size: usize, le: [u8; 8]) -> Self {
+        f64::from_element(kind, size, le) as f32
+    }
+
+    fn to_element(self, kind: DataKind, size: usize) -> [u8; 8] {
+        f64::from(self).to_element(kind, size)
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl Element for i64 {
+    fn from_element(kind: DataKind, size: usize, le: [u8; 8]) -> Self {
+        match kind {
+            DataKind::Float => float_value(size, le) as i64,
+            DataKind::Bool => i64::from(le[0] != 0),
+            DataKind::UInt => u64::from_le_bytes(le) as i64,
+            DataKind::Int => signed_value(size, le),
+        }
+    }
+
+    fn to_element(self, kind: DataKind, size: usize) -> [u8; 8] {
+        match kind {
+            DataKind::Float => float_element(self as f64, size),
+            DataKind::Bool => [u8::from(self != 0), 0, 0, 0, 0, 0, 0, 0],
+            DataKind::Int | DataKind::UInt => self.to_le_bytes(),
+        }
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value as i64
+    }
+}
+
+impl Element for u64 {
+    fn from_element(kind: DataKind, size: usize, le: [u8; 8]) -> Self {
+        match kind {
+            DataKind::Float => float_value(size, le) as u64,
+            DataKind::Bool => u64::from(le[0] != 0),
+            DataKind::UInt => u64::from_le_bytes(le),
+            DataKind::Int => signed_value(size, le) as u64,
+        }
+    }
+
+    fn to_element(self, kind: DataKind, size: usize) -> [u8; 8] {
+        match kind {
+            DataKind::Float => float_element(self as f64, size),
+            DataKind::Bool => [u8::from(self != 0), 0, 0, 0, 0, 0, 0, 0],
+            DataKind::Int | DataKind::UInt => self.to_le_bytes(),
+        }
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value as u64
+    }
+}
+
+/// Value of a little-endian float element of `size` bytes
+fn float_value(size: usize, le: [u8; 8]) -> f64 {
+    if size == 4 {
+        f64::from(f32::from_le_bytes([le[0], le[1], le[2], le[3]]))
+    } else {
+        f64::from_le_bytes(le)
+    }
+}
+
+/// Little-endian float element of `size` bytes
+fn float_element(value: f64, size: usize) -> [u8; 8] {
+    if size == 4 {
+        let mut le = [0u8; 8];
+        le[..4].copy_from_slice(&(value as f32).to_le_bytes());
+        le
+    } else {
+        value.to_le_bytes()
+    }
+}
+
+/// Value of a little-endian signed integer element of `size` bytes
+fn signed_value(size: usize, le: [u8; 8]) -> i64 {
+    // Sign-extend from the element width
+    let shift = 64 - size * 8;
+    (i64::from_le_bytes(le) << shift) >> shift
+}
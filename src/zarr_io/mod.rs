@@ -0,0 +1,1725 @@
+//! Zarr I/O operations
+//!
+//! This module provides Zarr v2 and v3 array support: reading and writing
+//! chunked arrays stored on the local filesystem or in an S3-compatible object
+//! store, following the Zarr storage specifications.
+//!
+//! # Organization
+//!
+//! - [`chunks`]: Chunk grid, chunk keys and chunk memory order
+//! - `consolidated`: Consolidated metadata (`.zmetadata`, inline v3 metadata)
+//! - [`codecs`]: Filters and compressors (Blosc, Zstd, GZip, Zlib, LZ4) forming a codec pipeline
+//! - [`dtype`]: NumPy-style element types
+//! - `sharding`: Zarr v3 `sharding_indexed` shard layout and index
+//! - `spec`: Parsing of `.zarray` (v2) and `zarr.json` (v3) metadata documents
+//! - [`store`]: Storage backends (filesystem, memory, S3, HTTP, zip) behind the [`ZarrStore`] trait
+
+mod blosc;
+mod cache;
+pub mod chunks;
+pub mod codecs;
+mod consolidated;
+pub mod dtype;
+mod sharding;
+mod spec;
+pub mod store;
+
+pub use blosc::BloscShuffle;
+pub use cache::{CacheStats, DEFAULT_CACHE_BUDGET};
+pub use chunks::{ChunkGrid, ChunkKeyEncoding, ChunkOrder};
+pub use codecs::{CodecPipeline, Compressor, Filter};
+pub use dtype::{DataType, Element};
+pub use store::{
+    FilesystemStore, HttpConfig, HttpStore, MemoryStore, S3Config, S3Credentials, S3Store,
+    SigV4Request, ZarrStore, ZipStore,
+};
+
+use crate::errors::{Result, RuNeVisError};
+use crate::statistics::StatResult;
+use crate::data_source::{DataReader, LazyDataReader, StreamingDataReader, DataWriter, DataArrayMetadata, AdvancedDataSource, FullDataSource, DataChunk, DataChunkStream};
+use cache::ChunkCache;
+use chunks::copy_overlap;
+use consolidated::{read_optional_json, ConsolidatedMetadata};
+use ndarray::{ArrayD, IxDyn};
+use rayon::prelude::*;
+use serde_json::Value as JsonValue;
+use sharding::ShardingSpec;
+use spec::ArraySpec;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use store::join_key;
+use async_trait::async_trait;
+use async_stream;
+
+/// Version of the Zarr storage specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZarrFormat {
+    /// Zarr v2: `.zarray` metadata and `0.0` chunk keys
+    #[default]
+    V2,
+    /// Zarr v3: `zarr.json` metadata, `c/0/0` chunk keys and a codec chain
+    V3,
+}
+
+impl ZarrFormat {
+    /// Name of the metadata document of an array
+    #[must_use]
+    pub const fn array_metadata_key(self) -> &'static str {
+        match self {
+            Self::V2 => ".zarray",
+            Self::V3 => "zarr.json",
+        }
+    }
+
+    /// The other format, tried when an array is not found in this one
+    const fn other(self) -> Self {
+        match self {
+            Self::V2 => Self::V3,
+            Self::V3 => Self::V2,
+        }
+    }
+}
+
+/// Zarr data source
+#[derive(Debug, Clone)]
+pub struct ZarrSource {
+    /// Local filesystem path, or the URL of a remote store
+    pub path: PathBuf,
+    /// Format used for new arrays and preferred when reading
+    pub format: ZarrFormat,
+    /// Backend holding the documents and chunks of the store
+    store: Arc<dyn ZarrStore>,
+}
+
+impl ZarrSource {
+    /// Create a new ZarrSource from a path string
+    ///
+    /// `s3://bucket/prefix` and `gs://bucket/prefix` URLs open an [`S3Store`]
+    /// configured from the environment (see [`S3Config::from_env`]),
+    /// `http(s)://` URLs a read-only [`HttpStore`] and `.zip` paths a
+    /// [`ZipStore`]; anything else is a local directory. Stores with a root
+    /// `zarr.json` document are treated as Zarr v3.
+    pub fn from_path_str(s: &str) -> Result<Self> {
+        let store: Arc<dyn ZarrStore> = if s.starts_with("s3://") || s.starts_with("gs://") {
+            Arc::new(S3Store::from_url(s, S3Config::from_env())?)
+        } else if s.starts_with("https://") || s.starts_with("http://") {
+            Arc::new(HttpStore::new(s)?)
+        } else if s.to_ascii_lowercase().ends_with(".zip") {
+            Arc::new(ZipStore::new(s))
+        } else {
+            Arc::new(FilesystemStore::new(s))
+        };
+        Ok(Self::from_store(s, store))
+    }
+
+    /// Create a ZarrSource reading and writing through the given store
+    ///
+    /// `location` is only used to describe the store in messages.
+    pub fn from_store(location: &str, store: Arc<dyn ZarrStore>) -> Self {
+        let format = if matches!(store.exists("zarr.json"), Ok(true)) {
+            ZarrFormat::V3
+        } else {
+            ZarrFormat::V2
+        };
+        ZarrSource {
+            path: PathBuf::from(location),
+            format,
+            store,
+        }
+    }
+
+    /// The storage backend of this source
+    pub fn store(&self) -> &Arc<dyn ZarrStore> {
+        &self.store
+    }
+
+    /// Select the Zarr format of this source
+    #[must_use]
+    pub fn with_format(mut self, format: ZarrFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+/// Zarr reader for accessing Zarr arrays
+pub struct ZarrReader {
+    source: ZarrSource,
+    /// Consolidated metadata of the store, used instead of per-node documents when present
+    consolidated: Option<ConsolidatedMetadata>,
+}
+
+impl ZarrReader {
+    /// Create a new ZarrReader from a source
+    pub async fn new(source: ZarrSource) -> Result<Self> {
+        source.store.validate()?;
+
+        let consolidated = ConsolidatedMetadata::load(source.store.as_ref())?;
+        Ok(ZarrReader {
+            source,
+            consolidated,
+        })
+    }
+
+    /// List all arrays in the Zarr store
+    ///
+    /// Groups are traversed recursively; arrays inside groups are listed by
+    /// their path, e.g. `atmos/tas`.
+    pub async fn list_arrays(&self) -> Result<Vec<String>> {
+        let (mut arrays, _) = self.list_nodes()?;
+        arrays.sort();
+        Ok(arrays)
+    }
+
+    /// List the paths of all groups below the root of the store
+    pub async fn list_groups(&self) -> Result<Vec<String>> {
+        let (_, mut groups) = self.list_nodes()?;
+        groups.sort();
+        Ok(groups)
+    }
+
+    /// Check whether the store was opened with consolidated metadata
+    pub fn is_consolidated(&self) -> bool {
+        self.consolidated.is_some()
+    }
+
+    /// Paths of all arrays and groups, from the consolidated metadata when available
+    fn list_nodes(&self) -> Result<(Vec<String>, Vec<String>)> {
+        let mut arrays = Vec::new();
+        let mut groups = Vec::new();
+        match &self.consolidated {
+            Some(consolidated) => {
+                for (path, kind) in consolidated.nodes() {
+                    match kind {
+                        NodeKind::Array(_) => arrays.push(path),
+                        NodeKind::Group(_) => groups.push(path),
+                    }
+                }
+            }
+            None => self.walk_hierarchy("", &mut arrays, &mut groups)?,
+        }
+        Ok((arrays, groups))
+    }
+
+    /// Read a metadata document of a node, from the consolidated metadata when available
+    fn read_document(&self, node_path: &str, name: &str) -> Result<Option<JsonValue>> {
+        match &self.consolidated {
+            Some(consolidated) => Ok(consolidated.document(node_path, name).cloned()),
+            None => read_optional_json(self.source.store.as_ref(), &join_key(node_path, name)),
+        }
+    }
+
+    /// Determine whether the node at `node_path` is an array or a group
+    fn node_kind(&self, node_path: &str) -> Option<NodeKind> {
+        match &self.consolidated {
+            Some(consolidated) => consolidated.node_kind(node_path),
+            None => node_kind(self.source.store.as_ref(), node_path),
+        }
+    }
+
+    /// Get the attributes of a group; `""` addresses the root group
+    pub async fn get_group_attributes(&self, group_path: &str) -> Result<HashMap<String, JsonValue>> {
+        let group_path = normalize_node_path(group_path)?;
+
+        match self.node_kind(&group_path) {
+            Some(NodeKind::Group(ZarrFormat::V2)) => {
+                parse_attributes(self.read_document(&group_path, ".zattrs")?, &group_path)
+            }
+            Some(NodeKind::Group(ZarrFormat::V3)) => {
+                let doc = self.read_document(&group_path, "zarr.json")?;
+                Ok(match doc.as_ref().and_then(|doc| doc.get("attributes")) {
+                    Some(JsonValue::Object(fields)) => fields.clone().into_iter().collect(),
+                    _ => HashMap::new(),
+                })
+            }
+            // The root of a store without a group document has no attributes
+            None if group_path.is_empty() => Ok(HashMap::new()),
+            _ => Err(RuNeVisError::ZarrError(format!(
+                "Group not found: '{}'",
+                group_path
+            ))),
+        }
+    }
+
+    /// Collect the arrays and groups below `group_path`
+    fn walk_hierarchy(
+        &self,
+        group_path: &str,
+        arrays: &mut Vec<String>,
+        groups: &mut Vec<String>,
+    ) -> Result<()> {
+        let store = self.source.store.as_ref();
+        for name in store.list_dir(group_path)? {
+            let child = join_key(group_path, &name);
+            match node_kind(store, &child) {
+                Some(NodeKind::Array(_)) => arrays.push(child),
+                Some(NodeKind::Group(_)) => {
+                    self.walk_hierarchy(&child, arrays, groups)?;
+                    groups.push(child);
+                }
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get array metadata
+    pub async fn get_array_metadata(&self, array_name: &str) -> Result<ArrayMetadata> {
+        Ok(self.load_array_spec(array_name)?.metadata)
+    }
+
+    /// Get the fill value of an array, used for chunks that were never written
+    pub async fn get_fill_value(&self, array_name: &str) -> Result<f64> {
+        Ok(self.load_array_spec(array_name)?.fill_value)
+    }
+
+    /// Get the fill value recorded in the metadata of an array
+    ///
+    /// Unlike [`get_fill_value`](Self::get_fill_value), returns `None` for a
+    /// Zarr v2 `fill_value` of `null`, so an explicit fill value of 0 can be
+    /// told apart from none.
+    pub async fn get_declared_fill_value(&self, array_name: &str) -> Result<Option<f64>> {
+        let spec = self.load_array_spec(array_name)?;
+        Ok(spec.has_fill_value.then_some(spec.fill_value))
+    }
+
+    /// Get the element type of an array as stored on disk
+    pub async fn get_data_type(&self, array_name: &str) -> Result<DataType> {
+        Ok(self.load_array_spec(array_name)?.dtype)
+    }
+
+    /// Read and parse the metadata document (`.zarray` or `zarr.json`) of an array
+    fn load_array_spec(&self, array_name: &str) -> Result<ArraySpec> {
+        let node_path = normalize_node_path(array_name)?;
+
+        // Prefer the source's format, falling back to the other one
+        let preferred = self.source.format;
+        for format in [preferred, preferred.other()] {
+            let Some(metadata) = self.read_document(&node_path, format.array_metadata_key())? else {
+                continue;
+            };
+
+            let mut spec = ArraySpec::parse(format, array_name, &metadata)?;
+            if format == ZarrFormat::V2 {
+                let mut attributes =
+                    parse_attributes(self.read_document(&node_path, ".zattrs")?, &node_path)?;
+                // xarray records dimension names in a reserved attribute
+                if let Some(names) = attributes.remove(ARRAY_DIMENSIONS) {
+                    spec.set_dimension_names(Some(parse_array_dimensions(
+                        &names,
+                        spec.metadata.shape.len(),
+                        array_name,
+                    )?));
+                }
+                spec.metadata.attributes = attributes;
+            }
+            return Ok(spec);
+        }
+
+        // Check if array exists: a node is listed by its parent (stores that cannot list have no such nodes)
+        let (parent, name) = node_path.rsplit_once('/').unwrap_or(("", node_path.as_str()));
+        let listed = self
+            .source
+            .store
+            .list_dir(parent)
+            .is_ok_and(|children| children.iter().any(|child| child == name));
+        if self.consolidated.is_some() || !listed {
+            return Err(RuNeVisError::ArrayNotFound {
+                array: array_name.to_string(),
+            });
+        }
+        Err(RuNeVisError::ZarrError(format!(
+            "Array metadata file not found: {}",
+            self.source
+                .path
+                .join(&node_path)
+                .join(preferred.array_metadata_key())
+                .display()
+        )))
+    }
+
+    /// Read and decode a single chunk, returning `None` if it has not been written
+    fn read_chunk<T: Element>(&self, array_name: &str, spec: &ArraySpec, index: &[usize]) -> Result<Option<ArrayD<T>>> {
+        let chunk_key = spec.grid.chunk_key(index);
+        let key = join_key(&normalize_node_path(array_name)?, &chunk_key);
+
+        let Some(stored) = self.source.store.get(&key)? else {
+            return Ok(None);
+        };
+        let bytes = spec.codecs.decode(&stored)?;
+        let values = decode_elements(&bytes, &spec.dtype, &chunk_key, array_name)?;
+
+        spec.grid.chunk_from_values(values).map(Some)
+    }
+
+    /// Read and decode the inner chunks of a shard that intersect `ranges`
+    ///
+    /// Only the shard index and the byte ranges of the required inner chunks
+    /// are read. Returns each decoded inner chunk with its array origin.
+    fn read_shard<T: Element>(
+        &self,
+        array_name: &str,
+        spec: &ArraySpec,
+        sharding: &ShardingSpec,
+        shard_index: &[usize],
+        ranges: &[(usize, usize)],
+    ) -> Result<Vec<(Vec<usize>, ArrayD<T>)>> {
+        let shard_key = spec.grid.chunk_key(shard_index);
+        let key = join_key(&normalize_node_path(array_name)?, &shard_key);
+        let store = self.source.store.as_ref();
+
+        let Some(shard_len) = store.size(&key)? else {
+            return Ok(Vec::new());
+        };
+        let index_offset = sharding.index_offset(shard_len)?;
+        let index = sharding.decode_index(&read_range(store, &key, index_offset, sharding.index_len() as u64)?)?;
+
+        // Translate the requested region into coordinates local to the shard,
+        // empty along any dimension where it misses the shard
+        let shard_origin = spec.grid.chunk_origin(shard_index);
+        let local_ranges: Vec<(usize, usize)> = ranges
+            .iter()
+            .zip(&shard_origin)
+            .zip(spec.grid.chunks())
+            .map(|((&(start, end), &origin), &len)| {
+                let local = |x: usize| x.clamp(origin, origin + len) - origin;
+                (local(start), local(end))
+            })
+            .collect();
+
+        let mut chunks = Vec::new();
+        for local_index in sharding.inner.chunks_intersecting(&local_ranges) {
+            let Some((offset, length)) = index[sharding.index_position(&local_index)] else {
+                continue;
+            };
+            let stored = read_range(store, &key, offset, length)?;
+            let bytes = sharding.codecs.decode(&stored)?;
+            let values = decode_elements(&bytes, &spec.dtype, &shard_key, array_name)?;
+
+            let origin: Vec<usize> = sharding
+                .inner
+                .chunk_origin(&local_index)
+                .iter()
+                .zip(&shard_origin)
+                .map(|(&local, &shard)| local + shard)
+                .collect();
+            chunks.push((origin, sharding.inner.chunk_from_values(values)?));
+        }
+        Ok(chunks)
+    }
+
+    /// Assemble the region `ranges` of an array from the chunks that intersect it
+    fn read_region<T: Element>(
+        &self,
+        array_name: &str,
+        spec: &ArraySpec,
+        ranges: &[(usize, usize)],
+    ) -> Result<ArrayD<T>> {
+        let region_shape: Vec<usize> = ranges.iter().map(|&(start, end)| end - start).collect();
+        let mut data = ArrayD::from_elem(IxDyn(&region_shape), T::from_f64(spec.fill_value));
+
+        // Decode the intersecting chunks (or shards) in parallel with Rayon
+        let intersecting = spec.grid.chunks_intersecting(ranges).into_par_iter();
+        let chunks: Vec<(Vec<usize>, ArrayD<T>)> = match &spec.sharding {
+            Some(sharding) => intersecting
+                .map(|index| self.read_shard(array_name, spec, sharding, &index, ranges))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect(),
+            None => intersecting
+                .map(|index| {
+                    let chunk = self.read_chunk(array_name, spec, &index)?;
+                    Ok(chunk.map(|chunk| (spec.grid.chunk_origin(&index), chunk)))
+                })
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect(),
+        };
+
+        // Chunks that were never written keep the fill value
+        for (origin, chunk) in chunks {
+            copy_overlap(&chunk, &origin, &mut data, ranges);
+        }
+
+        Ok(data)
+    }
+
+    /// Read an entire array as ndarray
+    pub async fn read_array(&self, array_name: &str) -> Result<ArrayD<f32>> {
+        let spec = self.load_array_spec(array_name)?;
+        let full_ranges: Vec<(usize, usize)> =
+            spec.metadata.shape.iter().map(|&n| (0, n)).collect();
+
+        log::debug!("Loading array '{}' with parallel processing", array_name);
+
+        self.read_region(array_name, &spec, &full_ranges)
+    }
+
+/// Lazy load an array as needed (returns a lazy wrapper)
+    pub async fn lazy_load_array(&self, array_name: &str) -> Result<LazyArray> {
+        let spec = self.load_array_spec(array_name)?;
+        let metadata = spec.metadata.clone();
+        // Cache whole chunks, or the inner chunks of shards
+        let cache_chunks = match &spec.sharding {
+            Some(sharding) => sharding.inner.chunks().to_vec(),
+            None => spec.grid.chunks().to_vec(),
+        };
+        let cache_grid = ChunkGrid::new(spec.metadata.shape.clone(), cache_chunks, ".", ChunkOrder::C)?;
+        Ok(LazyArray {
+            source: self.source.clone(),
+            array_name: array_name.to_string(),
+            metadata,
+            spec,
+            cache_grid,
+            cache: ChunkCache::new(DEFAULT_CACHE_BUDGET),
+            loaded: None,
+        })
+    }
+
+    /// Stream the chunks of an array in storage order
+    ///
+    /// Each [`DataChunk`] holds the N-D data of one chunk (one shard for
+    /// sharded arrays), clipped to the array bounds, with its chunk grid index
+    /// and offset. Only one chunk is held in memory at a time.
+    pub fn stream_chunks(&self, array_name: &str) -> DataChunkStream {
+        let array_name = array_name.to_string();
+        let source = self.source.clone();
+
+        Box::pin(async_stream::stream! {
+            // Create a temporary reader for metadata
+            let reader = match ZarrReader::new(source).await {
+                Ok(r) => r,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            let spec = match reader.load_array_spec(&array_name) {
+                Ok(spec) => spec,
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            for index in spec.grid.chunk_indices() {
+                let region = spec.grid.chunk_region(&index);
+                match reader.read_region::<f32>(&array_name, &spec, &region) {
+                    Ok(data) => yield Ok(DataChunk {
+                        offset: spec.grid.chunk_origin(&index),
+                        grid_index: index,
+                        data,
+                    }),
+                    Err(e) => yield Err(e),
+                }
+            }
+        })
+    }
+
+    /// Read a slice of an array
+    ///
+    /// `slice_ranges` holds one half-open `(start, end)` range per dimension.
+    /// Only the chunks (or inner chunks of shards) intersecting the slice are
+    /// fetched, in parallel, and their overlapping regions copied into the result.
+    pub async fn read_slice(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+    ) -> Result<ArrayD<f32>> {
+        let spec = self.load_array_spec(array_name)?;
+        check_slice_ranges(array_name, &spec.metadata.shape, slice_ranges)?;
+
+        log::debug!(
+            "Reading slice {:?} of array '{}' with parallel processing",
+            slice_ranges,
+            array_name
+        );
+
+        self.read_region(array_name, &spec, slice_ranges)
+    }
+
+    /// Read a slice of an array as values of type `T`
+    ///
+    /// Like [`read_slice`](Self::read_slice), but elements are converted from
+    /// their stored type to `T` rather than `f32`: reading float arrays as
+    /// `f64` and integer arrays as `i64` or `u64` keeps every value exact.
+    pub async fn read_slice_as<T: Element>(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+    ) -> Result<ArrayD<T>> {
+        let spec = self.load_array_spec(array_name)?;
+        check_slice_ranges(array_name, &spec.metadata.shape, slice_ranges)?;
+        self.read_region(array_name, &spec, slice_ranges)
+    }
+}
+
+/// Convert ArrayMetadata to DataArrayMetadata for trait compatibility
+impl From<ArrayMetadata> for DataArrayMetadata {
+    fn from(meta: ArrayMetadata) -> Self {
+        DataArrayMetadata {
+            name: meta.name,
+            shape: meta.shape,
+            dtype: meta.dtype,
+            dimensions: meta.dimensions,
+            attributes: meta.attributes,
+        }
+    }
+}
+
+/// Implement DataReader trait for ZarrReader
+#[async_trait]
+impl DataReader for ZarrReader {
+    type ArrayType = ArrayD<f32>;
+    
+    async fn list_arrays(&self) -> Result<Vec<String>> {
+        self.list_arrays().await
+    }
+    
+    async fn get_metadata(&self, array_name: &str) -> Result<DataArrayMetadata> {
+        let meta = self.get_array_metadata(array_name).await?;
+        Ok(meta.into())
+    }
+    
+    async fn read_array(&self, array_name: &str) -> Result<ArrayD<f32>> {
+        self.read_array(array_name).await
+    }
+    
+    async fn read_slice(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+    ) -> Result<ArrayD<f32>> {
+        self.read_slice(array_name, slice_ranges).await
+    }
+    
+    async fn chunk_shape(&self, array_name: &str) -> Result<Option<Vec<usize>>> {
+        Ok(Some(self.get_array_metadata(array_name).await?.chunks))
+    }
+}
+
+/// Implement LazyDataReader trait for ZarrReader
+#[async_trait]
+impl LazyDataReader for ZarrReader {
+    type LazyArray = LazyArray;
+    
+    async fn lazy_load(&self, array_name: &str) -> Result<Self::LazyArray> {
+        self.lazy_load_array(array_name).await
+    }
+}
+
+/// Implement StreamingDataReader trait for ZarrReader
+#[async_trait]
+impl StreamingDataReader for ZarrReader {
+    type ChunkStream = DataChunkStream;
+    
+    fn stream_chunks(&self, array_name: &str) -> Self::ChunkStream {
+        self.stream_chunks(array_name)
+    }
+}
+
+/// Implement DataWriter trait for ZarrWriter
+#[async_trait]
+impl DataWriter for ZarrWriter {
+    async fn write_array(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        chunk_shape: Option<Vec<usize>>,
+        attributes: Option<HashMap<String, JsonValue>>,
+    ) -> Result<()> {
+        self.write_array(array_name, data, chunk_shape, attributes).await
+    }
+    
+    async fn write_statistical_result(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        dim_names: &[String],
+        operation: &str,
+        original_array_name: &str,
+        source_metadata: Option<&DataArrayMetadata>,
+    ) -> Result<()> {
+        // Convert back to ArrayMetadata if needed
+        let array_meta = source_metadata.map(stat_source_metadata);
+        
+        self.write_statistical_result(
+            array_name,
+            data,
+            dim_names,
+            operation,
+            original_array_name,
+            array_meta.as_ref(),
+        ).await
+    }
+    
+    async fn write_stat_result(
+        &self,
+        result: &StatResult<f32>,
+        source_metadata: Option<&DataArrayMetadata>,
+    ) -> Result<()> {
+        let array_meta = source_metadata.map(stat_source_metadata);
+        
+        self.write_statistical_result_with_ddof(
+            &result.output_name(),
+            &result.data,
+            &result.remaining_dimensions,
+            result.operation.as_str(),
+            result.operation.ddof(),
+            &result.variable_name,
+            array_meta.as_ref(),
+        ).await
+    }
+}
+
+/// Metadata of the source array of a statistical result, without chunks
+fn stat_source_metadata(meta: &DataArrayMetadata) -> ArrayMetadata {
+    ArrayMetadata {
+        name: meta.name.clone(),
+        shape: meta.shape.clone(),
+        dtype: meta.dtype.clone(),
+        chunks: vec![], // Default empty chunks
+        dimensions: meta.dimensions.clone(),
+        attributes: meta.attributes.clone(),
+    }
+}
+
+/// Combined Zarr data source that implements all data source traits
+pub struct ZarrDataSource {
+    pub reader: ZarrReader,
+    pub writer: ZarrWriter,
+}
+
+impl ZarrDataSource {
+    /// Create a new combined Zarr data source
+    pub async fn new(source: ZarrSource) -> Result<Self> {
+        let reader = ZarrReader::new(source.clone()).await?;
+        let writer = ZarrWriter::new(source).await?;
+        Ok(Self { reader, writer })
+    }
+}
+
+/// Implement all data source traits for ZarrDataSource
+#[async_trait]
+impl DataReader for ZarrDataSource {
+    type ArrayType = ArrayD<f32>;
+    
+    async fn list_arrays(&self) -> Result<Vec<String>> {
+        self.reader.list_arrays().await
+    }
+    
+    async fn get_metadata(&self, array_name: &str) -> Result<DataArrayMetadata> {
+        self.reader.get_metadata(array_name).await
+    }
+    
+    async fn read_array(&self, array_name: &str) -> Result<ArrayD<f32>> {
+        self.reader.read_array(array_name).await
+    }
+    
+    async fn read_slice(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+    ) -> Result<ArrayD<f32>> {
+        self.reader.read_slice(array_name, slice_ranges).await
+    }
+    
+    async fn chunk_shape(&self, array_name: &str) -> Result<Option<Vec<usize>>> {
+        self.reader.chunk_shape(array_name).await
+    }
+}
+
+#[async_trait]
+impl LazyDataReader for ZarrDataSource {
+    type LazyArray = LazyArray;
+    
+    async fn lazy_load(&self, array_name: &str) -> Result<Self::LazyArray> {
+        self.reader.lazy_load(array_name).await
+    }
+}
+
+#[async_trait]
+impl StreamingDataReader for ZarrDataSource {
+    type ChunkStream = DataChunkStream;
+    
+    fn stream_chunks(&self, array_name: &str) -> Self::ChunkStream {
+        self.reader.stream_chunks(array_name)
+    }
+}
+
+#[async_trait]
+impl DataWriter for ZarrDataSource {
+    async fn write_array(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        chunk_shape: Option<Vec<usize>>,
+        attributes: Option<HashMap<String, JsonValue>>,
+    ) -> Result<()> {
+        self.writer.write_array(array_name, data, chunk_shape, attributes).await
+    }
+    
+    async fn write_statistical_result(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        dim_names: &[String],
+        operation: &str,
+        original_array_name: &str,
+        source_metadata: Option<&DataArrayMetadata>,
+    ) -> Result<()> {
+        let array_meta = source_metadata.map(stat_source_metadata);
+        
+        self.writer.write_statistical_result(
+            array_name,
+            data,
+            dim_names,
+            operation,
+            original_array_name,
+            array_meta.as_ref(),
+        ).await
+    }
+    
+    async fn write_stat_result(
+        &self,
+        result: &StatResult<f32>,
+        source_metadata: Option<&DataArrayMetadata>,
+    ) -> Result<()> {
+        DataWriter::write_stat_result(&self.writer, result, source_metadata).await
+    }
+}
+
+/// Combined Zarr data source that implements all data source traits
+impl FullDataSource for ZarrDataSource {}
+impl AdvancedDataSource for ZarrDataSource {}
+
+/// Lazy-loading wrapper for Zarr arrays
+///
+/// [`LazyArray::get`] reads windows of the array, fetching only the chunks
+/// they intersect. Decoded chunks are kept in a least-recently-used cache
+/// bounded by a memory budget ([`DEFAULT_CACHE_BUDGET`] unless set with
+/// [`LazyArray::with_cache_budget`]), so exploring a huge array never holds
+/// more than the budget plus the requested window in memory.
+#[derive(Debug)]
+pub struct LazyArray {
+    source: ZarrSource,
+    array_name: String,
+    metadata: ArrayMetadata,
+    spec: ArraySpec,
+    /// Grid of the cached units: chunks, or inner chunks of sharded arrays
+    cache_grid: ChunkGrid,
+    cache: ChunkCache,
+    loaded: Option<ArrayD<f32>>,
+}
+
+impl LazyArray {
+    /// Bound the chunk cache to `bytes` of decoded data
+    #[must_use]
+    pub fn with_cache_budget(mut self, bytes: usize) -> Self {
+        self.cache.set_budget(bytes);
+        self
+    }
+
+    /// Get array metadata without loading data
+    pub fn metadata(&self) -> &ArrayMetadata {
+        &self.metadata
+    }
+
+    /// Read a window of the array, loading only the chunks it intersects
+    ///
+    /// `slice_ranges` holds one half-open `(start, end)` range per dimension.
+    /// Cached chunks are reused and missing ones fetched in parallel, then
+    /// added to the cache.
+    pub async fn get(&mut self, slice_ranges: &[(usize, usize)]) -> Result<ArrayD<f32>> {
+        check_slice_ranges(&self.array_name, &self.metadata.shape, slice_ranges)?;
+        if let Some(loaded) = &self.loaded {
+            return Ok(loaded
+                .slice_each_axis(|ax| {
+                    let (start, end) = slice_ranges[ax.axis.index()];
+                    ndarray::Slice::from(start..end)
+                })
+                .to_owned());
+        }
+
+        let region_shape: Vec<usize> = slice_ranges.iter().map(|&(start, end)| end - start).collect();
+        let mut data = ArrayD::from_elem(IxDyn(&region_shape), self.spec.fill_value as f32);
+
+        let mut missing = Vec::new();
+        for index in self.cache_grid.chunks_intersecting(slice_ranges) {
+            match self.cache.get(&index) {
+                Some(chunk) => {
+                    copy_overlap(chunk, &self.cache_grid.chunk_origin(&index), &mut data, slice_ranges)
+                }
+                None => missing.push(index),
+            }
+        }
+
+        // The spec is already known, so the reader needs no consolidated metadata
+        let reader = ZarrReader {
+            source: self.source.clone(),
+            consolidated: None,
+        };
+        let fetched = missing
+            .into_par_iter()
+            .map(|index| {
+                let region = self.cache_grid.chunk_region(&index);
+                let chunk = reader.read_region::<f32>(&self.array_name, &self.spec, &region)?;
+                Ok((index, chunk))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for (index, chunk) in fetched {
+            copy_overlap(&chunk, &self.cache_grid.chunk_origin(&index), &mut data, slice_ranges);
+            self.cache.insert(index, chunk);
+        }
+        Ok(data)
+    }
+
+    /// Hit, miss and memory counters of the chunk cache
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Check whether the chunk of the cache grid at `index` is cached
+    ///
+    /// The cache grid is the chunk grid, or the inner chunk grid of sharded arrays.
+    pub fn is_chunk_cached(&self, index: &[usize]) -> bool {
+        self.cache.contains(index)
+    }
+
+    /// Drop every cached chunk
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+    
+    /// Load the array data if not already loaded
+    pub async fn load(&mut self) -> Result<&ArrayD<f32>> {
+        if self.loaded.is_none() {
+            let reader = ZarrReader::new(self.source.clone()).await?;
+            let data = reader.read_array(&self.array_name).await?;
+            self.loaded = Some(data);
+        }
+        Ok(self.loaded.as_ref().unwrap())
+    }
+    
+    /// Check if data is loaded
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.is_some()
+    }
+    
+    /// Get shape without loading data
+    pub fn shape(&self) -> &[usize] {
+        &self.metadata.shape
+    }
+    
+    /// Get chunks without loading data
+    pub fn chunks(&self) -> &[usize] {
+        &self.metadata.chunks
+    }
+}
+
+/// Zarr writer for creating new Zarr arrays
+pub struct ZarrWriter {
+    source: ZarrSource,
+}
+
+impl ZarrWriter {
+    /// Create a new ZarrWriter from a source
+    pub async fn new(source: ZarrSource) -> Result<Self> {
+        Ok(ZarrWriter { source })
+    }
+
+/// Write an ndarray to a Zarr array
+    pub async fn write_array(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        chunk_shape: Option<Vec<usize>>,
+        attributes: Option<HashMap<String, JsonValue>>,
+    ) -> Result<()> {
+        let options = WriteOptions {
+            chunk_shape,
+            attributes,
+            ..WriteOptions::default()
+        };
+        self.write_array_with_options(array_name, data, &options)
+            .await
+    }
+
+    /// Write an ndarray to a Zarr array with explicit layout options
+    ///
+    /// The array is split into hyper-rectangular chunks stored under their
+    /// `i.j.k` (or `i/j/k`) chunk keys, with edge chunks padded with the fill value.
+    pub async fn write_array_with_options(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        options: &WriteOptions,
+    ) -> Result<()> {
+        let data_shape = data.shape().to_vec();
+        // Zero-length dimensions still need a non-empty chunk extent
+        let chunks = options
+            .chunk_shape
+            .clone()
+            .unwrap_or_else(|| data_shape.iter().map(|&n| n.max(1)).collect());
+
+        log::info!(
+            "Writing array '{}' of shape {:?} in chunks of shape {:?}",
+            array_name, data_shape, chunks
+        );
+
+        let spec = self.define_array(array_name, data_shape, chunks, options)?;
+        let node_path = normalize_node_path(array_name)?;
+        let store = self.source.store.as_ref();
+
+        let chunk_indices = spec.grid.chunk_indices();
+        let num_chunks = chunk_indices.len();
+
+        log::debug!(
+            "Processing {} chunks in parallel across {} threads",
+            num_chunks,
+            rayon::current_num_threads()
+        );
+
+        // Write chunks (or shards) in parallel
+        chunk_indices.par_iter().try_for_each(|index| {
+            let chunk = spec.grid.extract_chunk(data, index, spec.fill_value as f32);
+            let bytes = encode_chunk(&spec, &chunk)?;
+            store.set(&join_key(&node_path, &spec.grid.chunk_key(index)), &bytes)
+        })?;
+
+        self.update_consolidated_metadata(options.consolidate)?;
+        store.flush()?;
+
+        log::info!("Wrote array '{}' with {} chunks", array_name, num_chunks);
+        Ok(())
+    }
+
+    /// Create an array of `shape` without writing any chunk
+    ///
+    /// Every element reads as the fill value of `options` until written with
+    /// [`write_slice`](Self::write_slice) or [`write_slice_as`](Self::write_slice_as),
+    /// so arrays larger than memory can be written region by region. Without
+    /// a chunk shape the array is a single chunk.
+    pub async fn create_array(
+        &self,
+        array_name: &str,
+        shape: &[usize],
+        options: &WriteOptions,
+    ) -> Result<()> {
+        let chunks = options
+            .chunk_shape
+            .clone()
+            .unwrap_or_else(|| shape.iter().map(|&n| n.max(1)).collect());
+        self.define_array(array_name, shape.to_vec(), chunks, options)?;
+        self.update_consolidated_metadata(options.consolidate)?;
+        self.source.store.flush()
+    }
+
+    /// Write the metadata of a new array, creating its parent groups
+    fn define_array(
+        &self,
+        array_name: &str,
+        shape: Vec<usize>,
+        chunks: Vec<usize>,
+        options: &WriteOptions,
+    ) -> Result<ArraySpec> {
+        let format = self.source.format;
+        let spec = ArraySpec::for_write(array_name, shape, chunks, format, options)?;
+
+        // Create the array inside its (possibly nested) parent groups
+        let node_path = normalize_node_path(array_name)?;
+        self.ensure_parent_groups(&node_path)?;
+        let store = self.source.store.as_ref();
+
+        // Write .zarray (v2) or zarr.json (v3) metadata
+        store.set(
+            &join_key(&node_path, format.array_metadata_key()),
+            serde_json::to_string_pretty(&spec.to_document()).unwrap().as_bytes(),
+        )?;
+
+        // Zarr v2 keeps user attributes and dimension names in .zattrs (v3 stores them in zarr.json)
+        if format == ZarrFormat::V2 {
+            let mut attributes = spec.metadata.attributes.clone();
+            if options.dimension_names.is_some() {
+                attributes.insert(
+                    ARRAY_DIMENSIONS.to_string(),
+                    serde_json::json!(spec.metadata.dimensions),
+                );
+            }
+            write_zattrs(store, &node_path, &attributes)?;
+        }
+        Ok(spec)
+    }
+
+    /// Write `data` into the region `slice_ranges` of an existing array
+    ///
+    /// `slice_ranges` holds one half-open `(start, end)` range per dimension and
+    /// `data` must have the shape of that region. Only the chunks intersecting
+    /// the region are rewritten; partly covered ones keep their other values.
+    pub async fn write_slice(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+        data: &ArrayD<f32>,
+    ) -> Result<()> {
+        self.write_slice_as(array_name, slice_ranges, data).await
+    }
+
+    /// Write values of type `T` into the region `slice_ranges` of an existing array
+    ///
+    /// Like [`write_slice`](Self::write_slice), but `i64`, `u64` and `f64`
+    /// values are stored exactly in integer and float arrays wide enough.
+    pub async fn write_slice_as<T: Element>(
+        &self,
+        array_name: &str,
+        slice_ranges: &[(usize, usize)],
+        data: &ArrayD<T>,
+    ) -> Result<()> {
+        let spec = self.reader().load_array_spec(array_name)?;
+        check_slice_ranges(array_name, &spec.metadata.shape, slice_ranges)?;
+        let region_shape: Vec<usize> = slice_ranges.iter().map(|&(start, end)| end - start).collect();
+        if data.shape() != region_shape.as_slice() {
+            return Err(RuNeVisError::InvalidSlice {
+                message: format!(
+                    "Data of shape {:?} does not fit slice {:?} of array '{}'",
+                    data.shape(),
+                    slice_ranges,
+                    array_name
+                ),
+            });
+        }
+
+        self.write_region(array_name, &spec, slice_ranges, data)?;
+        self.source.store.flush()
+    }
+
+    /// Append `data` to an existing array along `axis`
+    ///
+    /// Every other dimension of `data` must match the array. The new chunks are
+    /// written before the metadata, so readers never see the grown shape
+    /// before its data.
+    pub async fn append(&self, array_name: &str, data: &ArrayD<f32>, axis: usize) -> Result<()> {
+        let spec = self.reader().load_array_spec(array_name)?;
+        let shape = &spec.metadata.shape;
+        let compatible = data.ndim() == shape.len()
+            && axis < shape.len()
+            && (0..shape.len()).all(|dim| dim == axis || data.shape()[dim] == shape[dim]);
+        if !compatible {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Cannot append data of shape {:?} to array '{}' of shape {:?} along axis {}",
+                data.shape(),
+                array_name,
+                shape,
+                axis
+            )));
+        }
+
+        let mut new_shape = shape.clone();
+        new_shape[axis] += data.shape()[axis];
+        let ranges: Vec<(usize, usize)> = shape
+            .iter()
+            .zip(&new_shape)
+            .enumerate()
+            .map(|(dim, (&old, &new))| if dim == axis { (old, new) } else { (0, new) })
+            .collect();
+        let resized = spec.resized(new_shape)?;
+
+        self.write_region(array_name, &resized, &ranges, data)?;
+        self.write_array_shape(&normalize_node_path(array_name)?, &resized)?;
+        self.update_consolidated_metadata(false)?;
+        self.source.store.flush()
+    }
+
+    /// Change the shape of an existing array
+    ///
+    /// Growing an array exposes fill values. Shrinking it deletes the chunks
+    /// that fall outside the new shape and resets the cut-off part of edge
+    /// chunks, so that growing it again does not bring old values back.
+    pub async fn resize_array(&self, array_name: &str, new_shape: &[usize]) -> Result<()> {
+        let node_path = normalize_node_path(array_name)?;
+        let reader = self.reader();
+        let spec = reader.load_array_spec(array_name)?;
+        let resized = spec.resized(new_shape.to_vec())?;
+        let store = self.source.store.as_ref();
+
+        // Readers see the new shape before any chunk disappears
+        self.write_array_shape(&node_path, &resized)?;
+
+        let shrunk: Vec<bool> = spec
+            .metadata
+            .shape
+            .iter()
+            .zip(new_shape)
+            .map(|(&old, &new)| new < old)
+            .collect();
+        if shrunk.contains(&true) {
+            let chunks = spec.grid.chunks();
+            spec.grid.chunk_indices().par_iter().try_for_each(|index| {
+                let key = join_key(&node_path, &spec.grid.chunk_key(index));
+                let origin = spec.grid.chunk_origin(index);
+                if origin.iter().zip(new_shape).any(|(&start, &len)| start >= len) {
+                    return store.delete(&key);
+                }
+                // Edge chunks cut by the new shape keep only their in-bounds values
+                let cut = (0..index.len()).any(|dim| shrunk[dim] && origin[dim] + chunks[dim] > new_shape[dim]);
+                if cut && store.exists(&key)? {
+                    let region = resized.grid.chunk_region(index);
+                    let values = reader.read_region::<f64>(array_name, &resized, &region)?;
+                    self.write_region(array_name, &resized, &region, &values)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        self.update_consolidated_metadata(false)?;
+        store.flush()
+    }
+
+    /// Reader of the arrays being updated, bypassing consolidated metadata that may be stale
+    fn reader(&self) -> ZarrReader {
+        ZarrReader {
+            source: self.source.clone(),
+            consolidated: None,
+        }
+    }
+
+    /// Write `data`, covering `ranges` of the array, into every chunk it intersects
+    ///
+    /// Chunks only partly covered by `ranges` are read and merged with `data`.
+    fn write_region<T: Element>(
+        &self,
+        array_name: &str,
+        spec: &ArraySpec,
+        ranges: &[(usize, usize)],
+        data: &ArrayD<T>,
+    ) -> Result<()> {
+        let node_path = normalize_node_path(array_name)?;
+        let reader = self.reader();
+        let store = self.source.store.as_ref();
+        let data_origin: Vec<usize> = ranges.iter().map(|&(start, _)| start).collect();
+
+        spec.grid.chunks_intersecting(ranges).par_iter().try_for_each(|index| {
+            let region = spec.grid.chunk_region(index);
+            let origin = spec.grid.chunk_origin(index);
+            let padded: Vec<(usize, usize)> = origin
+                .iter()
+                .zip(spec.grid.chunks())
+                .map(|(&start, &len)| (start, start + len))
+                .collect();
+
+            let mut chunk = ArrayD::from_elem(IxDyn(spec.grid.chunks()), T::from_f64(spec.fill_value));
+            let covered = region
+                .iter()
+                .zip(ranges)
+                .all(|(&(lo, hi), &(start, end))| start <= lo && hi <= end);
+            if !covered {
+                let existing = reader.read_region(array_name, spec, &region)?;
+                copy_overlap(&existing, &origin, &mut chunk, &padded);
+            }
+            copy_overlap(data, &data_origin, &mut chunk, &padded);
+
+            store.set(&join_key(&node_path, &spec.grid.chunk_key(index)), &encode_chunk(spec, &chunk)?)
+        })
+    }
+
+    /// Record the shape of a resized array in its metadata document
+    ///
+    /// Only `shape` changes, and the document is replaced in a single store
+    /// write, so readers see either the old or the new shape.
+    fn write_array_shape(&self, node_path: &str, spec: &ArraySpec) -> Result<()> {
+        let store = self.source.store.as_ref();
+        let key = join_key(node_path, spec.format.array_metadata_key());
+        let mut doc = read_optional_json(store, &key)?.ok_or_else(|| RuNeVisError::ArrayNotFound {
+            array: node_path.to_string(),
+        })?;
+        doc["shape"] = serde_json::json!(spec.metadata.shape);
+        store.set(&key, serde_json::to_string_pretty(&doc).unwrap().as_bytes())
+    }
+
+    /// Consolidate the metadata of every group and array into a single document
+    ///
+    /// Zarr v2 stores get a root `.zmetadata` document and Zarr v3 stores an
+    /// inline `consolidated_metadata` entry in the root `zarr.json`, matching
+    /// `zarr.consolidate_metadata`.
+    pub async fn consolidate_metadata(&self) -> Result<()> {
+        self.update_consolidated_metadata(true)?;
+        self.source.store.flush()
+    }
+
+    /// Rebuild the consolidated metadata if requested, or if the store already has some
+    fn update_consolidated_metadata(&self, force: bool) -> Result<()> {
+        let store = self.source.store.as_ref();
+        if !force && ConsolidatedMetadata::load(store)?.is_none() {
+            return Ok(());
+        }
+        if self.source.format == ZarrFormat::V3 {
+            // Inline consolidated metadata lives in the root group document
+            self.ensure_parent_groups("")?;
+        }
+        ConsolidatedMetadata::collect(store)?.write(store, self.source.format)
+    }
+
+    /// Create a group, and any missing parent groups, with optional attributes
+    ///
+    /// Existing groups keep their attributes unless new ones are given.
+    pub async fn create_group(
+        &self,
+        group_path: &str,
+        attributes: Option<HashMap<String, JsonValue>>,
+    ) -> Result<()> {
+        let group_path = normalize_node_path(group_path)?;
+        self.ensure_parent_groups(&group_path)?;
+
+        let store = self.source.store.as_ref();
+        if matches!(node_kind(store, &group_path), Some(NodeKind::Array(_))) {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Cannot create group '{}': an array exists at this path",
+                group_path
+            )));
+        }
+        if attributes.is_some() || node_kind(store, &group_path).is_none() {
+            self.write_group(&group_path, &attributes.unwrap_or_default())?;
+        }
+        self.update_consolidated_metadata(false)?;
+        store.flush()
+    }
+
+    /// Make sure the root and every ancestor group of `node_path` exist
+    fn ensure_parent_groups(&self, node_path: &str) -> Result<()> {
+        let mut ancestors = vec![String::new()];
+        let parts: Vec<&str> = node_path.split('/').collect();
+        for depth in 1..parts.len() {
+            ancestors.push(parts[..depth].join("/"));
+        }
+
+        for ancestor in ancestors {
+            match node_kind(self.source.store.as_ref(), &ancestor) {
+                Some(NodeKind::Group(_)) => {}
+                Some(NodeKind::Array(_)) => {
+                    return Err(RuNeVisError::ZarrError(format!(
+                        "Cannot create '{}' inside array '{}'",
+                        node_path, ancestor
+                    )))
+                }
+                None => self.write_group(&ancestor, &HashMap::new())?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the group document (`.zgroup` and `.zattrs`, or `zarr.json`) of a group
+    fn write_group(&self, group_path: &str, attributes: &HashMap<String, JsonValue>) -> Result<()> {
+        let store = self.source.store.as_ref();
+        match self.source.format {
+            ZarrFormat::V2 => {
+                let zgroup = serde_json::json!({ "zarr_format": 2 });
+                store.set(
+                    &join_key(group_path, ".zgroup"),
+                    serde_json::to_string_pretty(&zgroup).unwrap().as_bytes(),
+                )?;
+                write_zattrs(store, group_path, attributes)
+            }
+            ZarrFormat::V3 => {
+                let mut group = serde_json::json!({
+                    "zarr_format": 3,
+                    "node_type": "group",
+                    "attributes": attributes
+                });
+                // Keep inline consolidated metadata when rewriting the root group
+                let key = join_key(group_path, "zarr.json");
+                if let Some(existing) = read_optional_json(store, &key)? {
+                    if let Some(consolidated) = existing.get("consolidated_metadata") {
+                        group["consolidated_metadata"] = consolidated.clone();
+                    }
+                }
+                store.set(&key, serde_json::to_string_pretty(&group).unwrap().as_bytes())
+            }
+        }
+    }
+
+    /// Write statistical result to Zarr array with metadata
+    pub async fn write_statistical_result(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        dim_names: &[String],
+        operation: &str,
+        original_array_name: &str,
+        source_metadata: Option<&ArrayMetadata>,
+    ) -> Result<()> {
+        self.write_statistical_result_with_ddof(
+            array_name,
+            data,
+            dim_names,
+            operation,
+            None,
+            original_array_name,
+            source_metadata,
+        )
+        .await
+    }
+
+    /// Write statistical result to Zarr array with metadata, recording the
+    /// delta degrees of freedom of a standard deviation or variance in a
+    /// `ddof` attribute
+    #[allow(clippy::too_many_arguments)]
+    pub async fn write_statistical_result_with_ddof(
+        &self,
+        array_name: &str,
+        data: &ArrayD<f32>,
+        dim_names: &[String],
+        operation: &str,
+        ddof: Option<u32>,
+        original_array_name: &str,
+        source_metadata: Option<&ArrayMetadata>,
+    ) -> Result<()> {
+        log::info!(
+            "Writing statistical result '{}' ({}) with parallel processing",
+            array_name, operation
+        );
+
+        // Create enhanced attributes for statistical results
+        let mut attributes = HashMap::new();
+        attributes.insert(
+            "operation".to_string(),
+            serde_json::Value::String(operation.to_string()),
+        );
+        if let Some(ddof) = ddof {
+            attributes.insert("ddof".to_string(), serde_json::Value::from(ddof));
+        }
+        attributes.insert(
+            "source_array".to_string(),
+            serde_json::Value::String(original_array_name.to_string()),
+        );
+        attributes.insert(
+            "dimensions".to_string(),
+            serde_json::Value::Array(
+                dim_names
+                    .iter()
+                    .map(|s| serde_json::Value::String(s.clone()))
+                    .collect(),
+            ),
+        );
+
+        if let Some(metadata) = source_metadata {
+            attributes.insert(
+                "source_shape".to_string(),
+                serde_json::Value::Array(
+                    metadata
+                        .shape
+                        .iter()
+                        .map(|&s| serde_json::Value::Number(serde_json::Number::from(s)))
+                        .collect(),
+                ),
+            );
+            attributes.insert(
+                "source_dtype".to_string(),
+                serde_json::Value::String(metadata.dtype.clone()),
+            );
+        }
+
+        // Record the kept dimensions as the dimension names of the result
+        let options = WriteOptions {
+            attributes: Some(attributes),
+            dimension_names: (dim_names.len() == data.ndim()).then(|| dim_names.to_vec()),
+            ..WriteOptions::default()
+        };
+        self.write_array_with_options(array_name, data, &options)
+            .await
+    }
+}
+
+/// Layout options for writing a Zarr array
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    /// Chunk shape; defaults to a single chunk covering the whole array
+    pub chunk_shape: Option<Vec<usize>>,
+    /// Element type stored on disk; values are converted from `f32`, or from
+    /// the element type given to [`ZarrWriter::write_slice_as`], when written
+    ///
+    /// Conversion of floats to integer types truncates towards zero.
+    pub dtype: DataType,
+    /// Separator between chunk indices in chunk keys, `.` or `/`
+    ///
+    /// Zarr v3 arrays use it as the separator of their `default` chunk key
+    /// encoding. Defaults to the separator of the store format: `.` for
+    /// Zarr v2 and `/` for Zarr v3.
+    pub dimension_separator: Option<String>,
+    /// Memory order of the elements inside each chunk
+    pub order: ChunkOrder,
+    /// Value recorded as `fill_value` and used to pad edge chunks
+    ///
+    /// `None` records no fill value: `fill_value` is `null` in Zarr v2 and,
+    /// as Zarr v3 requires one, 0 in Zarr v3. Unwritten elements read as 0.
+    pub fill_value: Option<f64>,
+    /// Filters applied to every chunk before compression
+    pub filters: Vec<Filter>,
+    /// Compressor applied to every chunk; `None` stores chunks uncompressed
+    pub compressor: Option<Compressor>,
+    /// User attributes for the array
+    pub attributes: Option<HashMap<String, JsonValue>>,
+    /// Names of the array dimensions, recorded as `_ARRAY_DIMENSIONS` (v2)
+    /// or `dimension_names` (v3)
+    pub dimension_names: Option<Vec<String>>,
+    /// Consolidate the store metadata after writing (see [`ZarrWriter::consolidate_metadata`])
+    ///
+    /// Stores that already have consolidated metadata are always kept up to date.
+    pub consolidate: bool,
+    /// Inner chunk shape of Zarr v3 sharded arrays
+    ///
+    /// When set, `chunk_shape` is the shard shape (a multiple of the inner
+    /// chunk shape) and every shard is stored with the `sharding_indexed` codec.
+    pub inner_chunk_shape: Option<Vec<usize>>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            chunk_shape: None,
+            dtype: DataType::FLOAT32,
+            dimension_separator: None,
+            order: ChunkOrder::C,
+            fill_value: Some(0.0),
+            filters: Vec::new(),
+            compressor: None,
+            attributes: None,
+            dimension_names: None,
+            consolidate: false,
+            inner_chunk_shape: None,
+        }
+    }
+}
+
+/// Attribute holding the dimension names of Zarr v2 arrays written by xarray
+const ARRAY_DIMENSIONS: &str = "_ARRAY_DIMENSIONS";
+
+/// Parse an `_ARRAY_DIMENSIONS` attribute: one name per dimension
+fn parse_array_dimensions(
+    value: &JsonValue,
+    ndim: usize,
+    array_name: &str,
+) -> Result<Vec<Option<String>>> {
+    let names: Option<Vec<Option<String>>> = value
+        .as_array()
+        .and_then(|names| names.iter().map(|name| name.as_str().map(|n| Some(n.to_string()))).collect());
+
+    match names {
+        Some(names) if names.len() == ndim => Ok(names),
+        _ => Err(RuNeVisError::ZarrError(format!(
+            "Invalid {} attribute {} for array '{}' with {} dimensions",
+            ARRAY_DIMENSIONS, value, array_name, ndim
+        ))),
+    }
+}
+
+/// Interpret a `.zattrs` document (`None` if the node has none) as attributes
+fn parse_attributes(
+    zattrs: Option<JsonValue>,
+    node_path: &str,
+) -> Result<HashMap<String, JsonValue>> {
+    match zattrs {
+        None => Ok(HashMap::new()),
+        Some(JsonValue::Object(fields)) => Ok(fields.into_iter().collect()),
+        Some(_) => Err(RuNeVisError::ZarrError(format!(
+            "Attributes of '{}' are not a JSON object",
+            node_path
+        ))),
+    }
+}
+
+/// Write the `.zattrs` document of a Zarr v2 node, removing a stale one when there are no attributes
+fn write_zattrs(store: &dyn ZarrStore, node_path: &str, attributes: &HashMap<String, JsonValue>) -> Result<()> {
+    let key = join_key(node_path, ".zattrs");
+    if attributes.is_empty() {
+        return store.delete(&key);
+    }
+
+    // Sort the keys so that the document is stable across writes
+    let sorted: std::collections::BTreeMap<&String, &JsonValue> = attributes.iter().collect();
+    store.set(&key, serde_json::to_string_pretty(&sorted).unwrap().as_bytes())
+}
+
+/// Interpret decoded chunk bytes as `dtype` elements, converted to `T` (`f32` for analysis)
+fn decode_elements<T: Element>(bytes: &[u8], dtype: &DataType, chunk_key: &str, array_name: &str) -> Result<Vec<T>> {
+    if bytes.len() % dtype.size != 0 {
+        return Err(RuNeVisError::ZarrError(format!(
+            "Chunk {} of array '{}' has {} bytes, which is not a whole number of '{}' elements",
+            chunk_key,
+            array_name,
+            bytes.len(),
+            dtype.to_type_string()
+        )));
+    }
+    dtype.decode_as(bytes)
+}
+
+/// Store chunk elements (already in storage order) as `dtype` and encode them
+fn encode_elements<T: Element>(values: &[T], dtype: &DataType, codecs: &CodecPipeline) -> Result<Vec<u8>> {
+    codecs.encode(&dtype.encode_from(values), dtype.size)
+}
+
+/// Encode a full, padded chunk (or shard) of an array
+fn encode_chunk<T: Element>(spec: &ArraySpec, chunk: &ArrayD<T>) -> Result<Vec<u8>> {
+    match &spec.sharding {
+        Some(sharding) => {
+            let inner_chunks = sharding
+                .inner
+                .chunk_indices()
+                .iter()
+                .map(|local_index| {
+                    let inner = sharding.inner.extract_chunk(chunk, local_index, T::from_f64(spec.fill_value));
+                    encode_elements(&sharding.inner.chunk_to_values(&inner), &spec.dtype, &sharding.codecs).map(Some)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(sharding.encode_shard(&inner_chunks))
+        }
+        None => encode_elements(&spec.grid.chunk_to_values(chunk), &spec.dtype, &spec.codecs),
+    }
+}
+
+/// Read `length` bytes starting at `offset` from an object that must exist
+fn read_range(store: &dyn ZarrStore, key: &str, offset: u64, length: u64) -> Result<Vec<u8>> {
+    let bytes = store.get_range(key, offset, length)?.ok_or_else(|| {
+        RuNeVisError::ZarrError(format!("Object '{}' disappeared while reading it", key))
+    })?;
+    if bytes.len() as u64 != length {
+        return Err(RuNeVisError::ZarrError(format!(
+            "Read {} bytes at offset {} of '{}', expected {}",
+            bytes.len(),
+            offset,
+            key,
+            length
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Kind of node stored in a directory of the hierarchy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Array(ZarrFormat),
+    Group(ZarrFormat),
+}
+
+/// Determine whether the node at `node_path` is a Zarr v2 or v3 array or group
+fn node_kind(store: &dyn ZarrStore, node_path: &str) -> Option<NodeKind> {
+    if store.exists(&join_key(node_path, ".zarray")).ok()? {
+        return Some(NodeKind::Array(ZarrFormat::V2));
+    }
+    if store.exists(&join_key(node_path, ".zgroup")).ok()? {
+        return Some(NodeKind::Group(ZarrFormat::V2));
+    }
+    // Zarr v3 arrays and groups share the zarr.json document name
+    match read_optional_json(store, &join_key(node_path, "zarr.json")).ok()??["node_type"].as_str() {
+        Some("array") => Some(NodeKind::Array(ZarrFormat::V3)),
+        Some("group") => Some(NodeKind::Group(ZarrFormat::V3)),
+        _ => None,
+    }
+}
+
+/// Normalise a node path such as `/atmos/tas/` to `atmos/tas`
+///
+/// Paths may not contain `.` or `..` components that would leave the store.
+fn normalize_node_path(path: &str) -> Result<String> {
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    if parts.iter().any(|&part| part == "." || part == ".." || part.contains('\\')) {
+        return Err(RuNeVisError::ZarrError(format!(
+            "Invalid node path '{}'",
+            path
+        )));
+    }
+    Ok(parts.join("/"))
+}
+
+/// Check that `slice_ranges` holds one in-bounds `(start, end)` range per dimension of `shape`
+pub(crate) fn check_slice_ranges(array_name: &str, shape: &[usize], slice_ranges: &[(usize, usize)]) -> Result<()> {
+    if slice_ranges.len() != shape.len() {
+        return Err(RuNeVisError::InvalidSlice {
+            message: format!(
+                "Expected {} slice ranges for array '{}', got {}",
+                shape.len(),
+                array_name,
+                slice_ranges.len()
+            ),
+        });
+    }
+    for (dim, (&(start, end), &len)) in slice_ranges.iter().zip(shape).enumerate() {
+        if start > end || end > len {
+            return Err(RuNeVisError::InvalidSlice {
+                message: format!(
+                    "Invalid slice range {}:{} for dimension {} of array '{}' (dimension size: {})",
+                    start, end, dim, array_name, len
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Metadata for a Zarr array
+#[derive(Debug, Clone)]
+pub struct ArrayMetadata {
+    pub name: String,
+    pub shape: Vec<usize>,
+    pub dtype: String,
+    pub chunks: Vec<usize>,
+    /// Dimension names from `_ARRAY_DIMENSIONS` (v2) or `dimension_names` (v3),
+    /// `dim_<i>` for unnamed dimensions
+    pub dimensions: Vec<String>,
+    pub attributes: HashMap<String, JsonValue>,
+}
+
+impl ArrayMetadata {
+    /// Print array metadata in a formatted way
+    pub fn print(&self) {
+        println!("Array: {}", self.name);
+        println!("  Shape: {:?}", self.shape);
+        println!("  Dimensions: {:?}", self.dimensions);
+        println!("  Data type: {}", self.dtype);
+        println!("  Chunks: {:?}", self.chunks);
+        println!("  Attributes:");
+        for (key, value) in &self.attributes {
+            println!("    {}: {}", key, value);
+        }
+    }
+}
+
+/// Convenience function to read a Zarr array from a path
+pub async fn read_zarr_array(path: &str, array_name: &str) -> Result<ArrayD<f32>> {
+    let source = ZarrSource::from_path_str(path)?;
+    let reader = ZarrReader::new(source).await?;
+    reader.read_array(array_name).await
+}
+
+/// Convenience function to write a Zarr array to a path with parallel processing
+pub async fn write_zarr_array(
+    path: &str,
+    array_name: &str,
+    data: &ArrayD<f32>,
+    chunk_shape: Option<Vec<usize>>,
+) -> Result<()> {
+    let source = ZarrSource::from_path_str(path)?;
+    let writer = ZarrWriter::new(source).await?;
+    writer
+        .write_array(array_name, data, chunk_shape, None)
+        .await
+}
+
+/// List all arrays in a Zarr store
+pub async fn list_zarr_arrays(path: &str) -> Result<Vec<String>> {
+    let source = ZarrSource::from_path_str(path)?;
+    let reader = ZarrReader::new(source).await?;
+    reader.list_arrays().await
+}
+
+/// Get metadata for a Zarr array
+pub async fn get_zarr_metadata(path: &str, array_name: &str) -> Result<ArrayMetadata> {
+    let source = ZarrSource::from_path_str(path)?;
+    let reader = ZarrReader::new(source).await?;
+    reader.get_array_metadata(array_name).await
+}
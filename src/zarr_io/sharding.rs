@@ -0,0 +1,242 @@
+//! Zarr v3 sharding
+//!
+//! The `sharding_indexed` codec stores a whole grid of inner chunks in one
+//! shard object. An index at the end (or start) of the shard records the byte
+//! range of every inner chunk, so single inner chunks can be read with range
+//! requests instead of fetching the whole shard.
+
+use super::chunks::{ChunkGrid, ChunkOrder};
+use super::codecs::{crc32c, CodecPipeline};
+use super::dtype::DataType;
+use super::spec::{split_transpose, transpose_codec};
+use crate::errors::{Result, RuNeVisError};
+use serde_json::Value as JsonValue;
+
+/// Index entry value marking an inner chunk that was never written
+const EMPTY: u64 = u64::MAX;
+
+/// Position of the shard index inside a shard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IndexLocation {
+    Start,
+    End,
+}
+
+/// Layout of the shards of a sharded array
+#[derive(Debug, Clone)]
+pub(crate) struct ShardingSpec {
+    /// Grid of inner chunks covering one shard
+    pub(crate) inner: ChunkGrid,
+    /// Codecs applied to every inner chunk
+    pub(crate) codecs: CodecPipeline,
+    /// Whether the index is followed by a CRC32C checksum
+    pub(crate) index_checksum: bool,
+    pub(crate) index_location: IndexLocation,
+}
+
+impl ShardingSpec {
+    /// Create the layout of shards of `shard_shape` split into `inner_chunks`
+    pub(crate) fn new(
+        shard_shape: Vec<usize>,
+        inner_chunks: Vec<usize>,
+        order: ChunkOrder,
+        codecs: CodecPipeline,
+    ) -> Result<Self> {
+        let inner = ChunkGrid::new(shard_shape.clone(), inner_chunks.clone(), ".", order)?;
+        if shard_shape
+            .iter()
+            .zip(&inner_chunks)
+            .any(|(&shard, &chunk)| shard % chunk != 0)
+        {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Shard shape {:?} is not a multiple of the inner chunk shape {:?}",
+                shard_shape, inner_chunks
+            )));
+        }
+
+        Ok(Self {
+            inner,
+            codecs,
+            index_checksum: true,
+            index_location: IndexLocation::End,
+        })
+    }
+
+    /// Parse the `configuration` of a `sharding_indexed` codec
+    pub(crate) fn from_json(configuration: &JsonValue, shard_shape: &[usize]) -> Result<Self> {
+        let inner_chunks: Vec<usize> = configuration["chunk_shape"]
+            .as_array()
+            .and_then(|dims| {
+                dims.iter()
+                    .map(|d| d.as_u64().map(|d| d as usize))
+                    .collect()
+            })
+            .ok_or_else(|| {
+                RuNeVisError::ZarrError("Sharding codec is missing 'chunk_shape'".to_string())
+            })?;
+
+        let codec_docs = configuration["codecs"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let (order, chain) = split_transpose(codec_docs, shard_shape.len())?;
+        let codecs = CodecPipeline::from_v3_codecs(&chain)?;
+
+        // The index is an array of uint64 pairs; only its byte order and checksum may vary
+        let mut index_checksum = false;
+        for codec in configuration["index_codecs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            match codec["name"].as_str() {
+                Some("bytes") if codec["configuration"]["endian"].as_str() != Some("big") => {}
+                Some("crc32c") => index_checksum = true,
+                _ => {
+                    return Err(RuNeVisError::ZarrError(format!(
+                        "Unsupported shard index codec {}",
+                        codec
+                    )))
+                }
+            }
+        }
+        let index_location = match configuration["index_location"].as_str() {
+            Some("start") => IndexLocation::Start,
+            Some("end") | None => IndexLocation::End,
+            Some(other) => {
+                return Err(RuNeVisError::ZarrError(format!(
+                    "Unsupported shard index location '{}'",
+                    other
+                )))
+            }
+        };
+
+        let mut spec = Self::new(shard_shape.to_vec(), inner_chunks, order, codecs)?;
+        spec.index_checksum = index_checksum;
+        spec.index_location = index_location;
+        Ok(spec)
+    }
+
+    /// Generate the `sharding_indexed` codec entry for an array of `dtype` elements
+    pub(crate) fn to_json(&self, dtype: &DataType) -> JsonValue {
+        let mut codecs: Vec<JsonValue> =
+            transpose_codec(self.inner.order(), self.inner.shape().len())
+                .into_iter()
+                .collect();
+        codecs.extend(self.codecs.to_v3_codecs(dtype));
+
+        let mut index_codecs =
+            vec![serde_json::json!({ "name": "bytes", "configuration": { "endian": "little" } })];
+        if self.index_checksum {
+            index_codecs.push(serde_json::json!({ "name": "crc32c" }));
+        }
+
+        serde_json::json!({
+            "name": "sharding_indexed",
+            "configuration": {
+                "chunk_shape": self.inner.chunks(),
+                "codecs": codecs,
+                "index_codecs": index_codecs,
+                "index_location": match self.index_location {
+                    IndexLocation::Start => "start",
+                    IndexLocation::End => "end",
+                }
+            }
+        })
+    }
+
+    /// Number of inner chunks in a shard
+    fn inner_count(&self) -> usize {
+        self.inner.grid_shape().iter().product()
+    }
+
+    /// Size in bytes of the encoded shard index
+    pub(crate) fn index_len(&self) -> usize {
+        self.inner_count() * 16 + if self.index_checksum { 4 } else { 0 }
+    }
+
+    /// Byte offset of the index in a shard of `shard_len` bytes
+    pub(crate) fn index_offset(&self, shard_len: u64) -> Result<u64> {
+        let index_len = self.index_len() as u64;
+        if shard_len < index_len {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Shard of {} bytes is too short to hold its {}-byte index",
+                shard_len, index_len
+            )));
+        }
+        Ok(match self.index_location {
+            IndexLocation::Start => 0,
+            IndexLocation::End => shard_len - index_len,
+        })
+    }
+
+    /// Position of an inner chunk in the shard index (C order over the inner grid)
+    pub(crate) fn index_position(&self, local_index: &[usize]) -> usize {
+        local_index
+            .iter()
+            .zip(self.inner.grid_shape())
+            .fold(0, |position, (&i, n)| position * n + i)
+    }
+
+    /// Decode a shard index into the `(offset, length)` of every inner chunk
+    pub(crate) fn decode_index(&self, bytes: &[u8]) -> Result<Vec<Option<(u64, u64)>>> {
+        if bytes.len() != self.index_len() {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Shard index has {} bytes, expected {}",
+                bytes.len(),
+                self.index_len()
+            )));
+        }
+
+        let entries = &bytes[..self.inner_count() * 16];
+        if self.index_checksum {
+            let stored = &bytes[entries.len()..];
+            if crc32c(entries).to_le_bytes() != stored {
+                return Err(RuNeVisError::ZarrError(
+                    "Shard index CRC32C checksum mismatch".to_string(),
+                ));
+            }
+        }
+
+        Ok(entries
+            .chunks_exact(16)
+            .map(|entry| {
+                let offset = u64::from_le_bytes(entry[..8].try_into().unwrap());
+                let length = u64::from_le_bytes(entry[8..].try_into().unwrap());
+                (offset != EMPTY || length != EMPTY).then_some((offset, length))
+            })
+            .collect())
+    }
+
+    /// Assemble a shard from its encoded inner chunks, given in index order
+    pub(crate) fn encode_shard(&self, chunks: &[Option<Vec<u8>>]) -> Vec<u8> {
+        let data_start = match self.index_location {
+            IndexLocation::Start => self.index_len() as u64,
+            IndexLocation::End => 0,
+        };
+
+        let mut entries = Vec::with_capacity(self.inner_count() * 16);
+        let mut body = Vec::new();
+        for chunk in chunks {
+            let (offset, length) = match chunk {
+                Some(bytes) => {
+                    let entry = (data_start + body.len() as u64, bytes.len() as u64);
+                    body.extend_from_slice(bytes);
+                    entry
+                }
+                None => (EMPTY, EMPTY),
+            };
+            entries.extend_from_slice(&offset.to_le_bytes());
+            entries.extend_from_slice(&length.to_le_bytes());
+        }
+        if self.index_checksum {
+            let checksum = crc32c(&entries);
+            entries.extend_from_slice(&checksum.to_le_bytes());
+        }
+
+        match self.index_location {
+            IndexLocation::Start => [entries, body].concat(),
+            IndexLocation::End => [body, entries].concat(),
+        }
+    }
+}
@@ -0,0 +1,498 @@
+//! Zarr array metadata documents
+//!
+//! Parsing and generation of the documents that describe the shape, chunking
+//! and storage layout of an array: `.zarray` for Zarr v2 and `zarr.json` for
+//! Zarr v3.
+
+use super::chunks::{ChunkGrid, ChunkKeyEncoding, ChunkOrder};
+use super::codecs::CodecPipeline;
+use super::dtype::{DataKind, DataType};
+use super::sharding::ShardingSpec;
+use super::{ArrayMetadata, WriteOptions, ZarrFormat};
+use crate::errors::{Result, RuNeVisError};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// Everything needed to locate and decode the chunks of an array
+#[derive(Debug, Clone)]
+pub(crate) struct ArraySpec {
+    pub(crate) metadata: ArrayMetadata,
+    /// Element type of the stored chunks
+    pub(crate) dtype: DataType,
+    pub(crate) grid: ChunkGrid,
+    pub(crate) fill_value: f64,
+    /// Whether the metadata records a `fill_value`; Zarr v2 allows `null`,
+    /// in which case `fill_value` is 0
+    pub(crate) has_fill_value: bool,
+    pub(crate) codecs: CodecPipeline,
+    /// Inner chunk layout of Zarr v3 arrays using the `sharding_indexed` codec
+    pub(crate) sharding: Option<ShardingSpec>,
+    pub(crate) format: ZarrFormat,
+    /// Zarr v3 `dimension_names`, with `None` for unnamed dimensions
+    pub(crate) dimension_names: Option<Vec<Option<String>>>,
+}
+
+impl ArraySpec {
+    /// Build the spec of a new array written with the given options
+    pub(crate) fn for_write(
+        array_name: &str,
+        shape: Vec<usize>,
+        chunks: Vec<usize>,
+        format: ZarrFormat,
+        options: &WriteOptions,
+    ) -> Result<Self> {
+        if let Some(names) = &options.dimension_names {
+            if names.len() != shape.len() {
+                return Err(RuNeVisError::ZarrError(format!(
+                    "Got {} dimension names for array '{}' with {} dimensions",
+                    names.len(),
+                    array_name,
+                    shape.len()
+                )));
+            }
+        }
+
+        let ndim = shape.len();
+        let dtype = options.dtype;
+        let (dtype_name, key_encoding) = match format {
+            ZarrFormat::V2 => (dtype.to_type_string(), ChunkKeyEncoding::V2),
+            ZarrFormat::V3 => (dtype.to_v3_name(), ChunkKeyEncoding::Default),
+        };
+        let codecs = CodecPipeline {
+            filters: options.filters.clone(),
+            compressor: options.compressor.clone(),
+            checksum: false,
+        };
+        if format == ZarrFormat::V3 {
+            codecs.check_v3_order()?;
+        }
+
+        // Sharded arrays apply the memory order and codecs to their inner chunks
+        let (order, codecs, sharding) = match &options.inner_chunk_shape {
+            None => (options.order, codecs, None),
+            Some(_) if format != ZarrFormat::V3 => {
+                return Err(RuNeVisError::ZarrError(format!(
+                    "Array '{}' cannot be sharded: sharding requires Zarr v3",
+                    array_name
+                )))
+            }
+            Some(inner_chunks) => {
+                let sharding =
+                    ShardingSpec::new(chunks.clone(), inner_chunks.clone(), options.order, codecs)?;
+                (ChunkOrder::C, CodecPipeline::default(), Some(sharding))
+            }
+        };
+
+        let separator = options
+            .dimension_separator
+            .as_deref()
+            .unwrap_or(match format {
+                ZarrFormat::V2 => ".",
+                ZarrFormat::V3 => "/",
+            });
+        let grid = ChunkGrid::new(shape.clone(), chunks.clone(), separator, order)?
+            .with_key_encoding(key_encoding);
+
+        Ok(Self {
+            metadata: ArrayMetadata {
+                name: array_name.to_string(),
+                shape,
+                dtype: dtype_name,
+                chunks,
+                dimensions: match &options.dimension_names {
+                    Some(names) => names.clone(),
+                    None => default_dimension_names(ndim),
+                },
+                attributes: options.attributes.clone().unwrap_or_default(),
+            },
+            dtype,
+            grid,
+            fill_value: options.fill_value.unwrap_or(0.0),
+            has_fill_value: options.fill_value.is_some(),
+            codecs,
+            sharding,
+            format,
+            dimension_names: options
+                .dimension_names
+                .as_ref()
+                .map(|names| names.iter().cloned().map(Some).collect()),
+        })
+    }
+
+    /// Parse the metadata document of an array stored in the given format
+    pub(crate) fn parse(format: ZarrFormat, array_name: &str, doc: &JsonValue) -> Result<Self> {
+        match format {
+            ZarrFormat::V2 => Self::from_zarray(array_name, doc),
+            ZarrFormat::V3 => Self::from_zarr_json(array_name, doc),
+        }
+    }
+
+    /// The spec of the same array resized to `shape`
+    pub(crate) fn resized(&self, shape: Vec<usize>) -> Result<Self> {
+        let mut spec = self.clone();
+        spec.grid = self.grid.with_shape(shape.clone())?;
+        spec.metadata.shape = shape;
+        Ok(spec)
+    }
+
+    /// Generate the metadata document of this array
+    pub(crate) fn to_document(&self) -> JsonValue {
+        match self.format {
+            ZarrFormat::V2 => self.to_zarray(),
+            ZarrFormat::V3 => self.to_zarr_json(),
+        }
+    }
+
+    /// Parse a `.zarray` document
+    pub(crate) fn from_zarray(array_name: &str, doc: &JsonValue) -> Result<Self> {
+        if let Some(format) = doc.get("zarr_format").and_then(JsonValue::as_u64) {
+            if format != 2 {
+                return Err(RuNeVisError::ZarrError(format!(
+                    "Unsupported zarr_format {} for array '{}'",
+                    format, array_name
+                )));
+            }
+        }
+
+        let shape = parse_usize_list(doc, "shape")?;
+        let ndim = shape.len();
+        let chunks = parse_usize_list(doc, "chunks")?;
+        let dtype = doc["dtype"].as_str().unwrap_or("unknown").to_string();
+
+        let order = match doc.get("order").and_then(JsonValue::as_str) {
+            Some(order) => ChunkOrder::parse(order)?,
+            None => ChunkOrder::C,
+        };
+        let separator = doc
+            .get("dimension_separator")
+            .and_then(JsonValue::as_str)
+            .unwrap_or(".");
+
+        let codecs = CodecPipeline::from_zarray(doc)?;
+        let data_type = DataType::parse(&dtype)?;
+
+        let fill_json = doc.get("fill_value").unwrap_or(&JsonValue::Null);
+        let fill_value = parse_fill_value(fill_json)?;
+        let grid = ChunkGrid::new(shape.clone(), chunks.clone(), separator, order)?;
+
+        Ok(Self {
+            metadata: ArrayMetadata {
+                name: array_name.to_string(),
+                shape,
+                dimensions: default_dimension_names(ndim),
+                dtype,
+                chunks,
+                attributes: HashMap::new(), // Read from .zattrs by the reader
+            },
+            dtype: data_type,
+            grid,
+            fill_value,
+            has_fill_value: !fill_json.is_null(),
+            codecs,
+            sharding: None,
+            format: ZarrFormat::V2,
+            dimension_names: None,
+        })
+    }
+
+    /// Parse a Zarr v3 `zarr.json` array document
+    pub(crate) fn from_zarr_json(array_name: &str, doc: &JsonValue) -> Result<Self> {
+        if doc.get("zarr_format").and_then(JsonValue::as_u64) != Some(3) {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Metadata of array '{}' is not a Zarr v3 document",
+                array_name
+            )));
+        }
+        if doc.get("node_type").and_then(JsonValue::as_str) != Some("array") {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Node '{}' is not an array",
+                array_name
+            )));
+        }
+
+        let shape = parse_usize_list(doc, "shape")?;
+        let ndim = shape.len();
+        let dtype = doc["data_type"].as_str().unwrap_or("unknown").to_string();
+
+        let grid_doc = &doc["chunk_grid"];
+        if grid_doc["name"].as_str() != Some("regular") {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Unsupported chunk grid {} for array '{}'",
+                grid_doc["name"], array_name
+            )));
+        }
+        let chunks = parse_usize_list(&grid_doc["configuration"], "chunk_shape")?;
+
+        let key_doc = &doc["chunk_key_encoding"];
+        let key_encoding = ChunkKeyEncoding::parse(key_doc["name"].as_str().unwrap_or("default"))?;
+        let default_separator = match key_encoding {
+            ChunkKeyEncoding::V2 => ".",
+            ChunkKeyEncoding::Default => "/",
+        };
+        let separator = key_doc["configuration"]["separator"]
+            .as_str()
+            .unwrap_or(default_separator);
+
+        let codec_docs = doc["codecs"].as_array().cloned().unwrap_or_default();
+        let (order, chain) = split_transpose(codec_docs, shape.len())?;
+        let (codecs, sharding) = match chain.as_slice() {
+            [codec] if codec["name"].as_str() == Some("sharding_indexed") => {
+                if order != ChunkOrder::C {
+                    return Err(RuNeVisError::ZarrError(format!(
+                        "Transposing shards of array '{}' is not supported",
+                        array_name
+                    )));
+                }
+                let sharding = ShardingSpec::from_json(&codec["configuration"], &chunks)?;
+                (CodecPipeline::default(), Some(sharding))
+            }
+            _ => (CodecPipeline::from_v3_codecs(&chain)?, None),
+        };
+
+        // The byte order is a property of the `bytes` codec, inside the shard if sharded
+        let endianness = match chain.as_slice() {
+            [codec] if sharding.is_some() => CodecPipeline::bytes_endianness(
+                codec["configuration"]["codecs"]
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            ),
+            _ => CodecPipeline::bytes_endianness(&chain),
+        };
+        let data_type = DataType::parse_v3(&dtype, endianness)?;
+
+        let fill_json = doc.get("fill_value").unwrap_or(&JsonValue::Null);
+        let fill_value = parse_fill_value(fill_json)?;
+        let grid = ChunkGrid::new(shape.clone(), chunks.clone(), separator, order)?
+            .with_key_encoding(key_encoding);
+
+        let attributes = match doc.get("attributes") {
+            Some(JsonValue::Object(fields)) => fields.clone().into_iter().collect(),
+            _ => HashMap::new(),
+        };
+        let dimension_names: Option<Vec<Option<String>>> = doc
+            .get("dimension_names")
+            .and_then(JsonValue::as_array)
+            .map(|names| {
+                names
+                    .iter()
+                    .map(|name| name.as_str().map(str::to_string))
+                    .collect()
+            });
+        if dimension_names
+            .as_ref()
+            .is_some_and(|names| names.len() != ndim)
+        {
+            return Err(RuNeVisError::ZarrError(format!(
+                "dimension_names of array '{}' do not match its {} dimensions",
+                array_name, ndim
+            )));
+        }
+
+        Ok(Self {
+            metadata: ArrayMetadata {
+                name: array_name.to_string(),
+                shape,
+                dimensions: resolve_dimension_names(dimension_names.as_deref(), ndim),
+                dtype,
+                chunks,
+                attributes,
+            },
+            dtype: data_type,
+            grid,
+            fill_value,
+            has_fill_value: !fill_json.is_null(),
+            codecs,
+            sharding,
+            format: ZarrFormat::V3,
+            dimension_names,
+        })
+    }
+
+    /// Record the dimension names of the array, falling back to `dim_<i>` for unnamed dimensions
+    pub(crate) fn set_dimension_names(&mut self, names: Option<Vec<Option<String>>>) {
+        self.metadata.dimensions =
+            resolve_dimension_names(names.as_deref(), self.metadata.shape.len());
+        self.dimension_names = names;
+    }
+
+    /// Generate the Zarr v3 `zarr.json` document describing this array
+    pub(crate) fn to_zarr_json(&self) -> JsonValue {
+        let codecs: Vec<JsonValue> = match &self.sharding {
+            Some(sharding) => vec![sharding.to_json(&self.dtype)],
+            None => transpose_codec(self.grid.order(), self.grid.shape().len())
+                .into_iter()
+                .chain(self.codecs.to_v3_codecs(&self.dtype))
+                .collect(),
+        };
+
+        let mut doc = serde_json::json!({
+            "zarr_format": 3,
+            "node_type": "array",
+            "shape": self.grid.shape(),
+            "data_type": self.metadata.dtype,
+            "chunk_grid": {
+                "name": "regular",
+                "configuration": { "chunk_shape": self.grid.chunks() }
+            },
+            "chunk_key_encoding": {
+                "name": self.grid.key_encoding().as_str(),
+                "configuration": { "separator": self.grid.separator() }
+            },
+            "fill_value": fill_value_to_json(self.fill_value, &self.dtype),
+            "codecs": codecs,
+            "attributes": self.metadata.attributes
+        });
+        if let Some(names) = &self.dimension_names {
+            doc["dimension_names"] = serde_json::json!(names);
+        }
+        doc
+    }
+
+    /// Generate the `.zarray` document describing this array
+    pub(crate) fn to_zarray(&self) -> JsonValue {
+        serde_json::json!({
+            "chunks": self.grid.chunks(),
+            "compressor": self.codecs.compressor_json(),
+            "dimension_separator": self.grid.separator(),
+            "dtype": self.metadata.dtype,
+            "fill_value": if self.has_fill_value {
+                fill_value_to_json(self.fill_value, &self.dtype)
+            } else {
+                JsonValue::Null
+            },
+            "filters": self.codecs.filters_json(),
+            "order": self.grid.order().as_str(),
+            "shape": self.grid.shape(),
+            "zarr_format": 2
+        })
+    }
+}
+
+/// Names `dim_0`, `dim_1`, ... used for arrays without recorded dimension names
+pub(crate) fn default_dimension_names(ndim: usize) -> Vec<String> {
+    (0..ndim).map(|i| format!("dim_{}", i)).collect()
+}
+
+/// Fill in `dim_<i>` for dimensions without a recorded name
+fn resolve_dimension_names(names: Option<&[Option<String>]>, ndim: usize) -> Vec<String> {
+    match names {
+        Some(names) if names.len() == ndim => names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| name.clone().unwrap_or_else(|| format!("dim_{}", i)))
+            .collect(),
+        _ => default_dimension_names(ndim),
+    }
+}
+
+/// Remove the `transpose` codec from a v3 codec chain, returning the chunk memory order it implies
+pub(crate) fn split_transpose(
+    codecs: Vec<JsonValue>,
+    ndim: usize,
+) -> Result<(ChunkOrder, Vec<JsonValue>)> {
+    // A transpose codec reversing the axes stores chunks in F order
+    let mut order = ChunkOrder::C;
+    let mut chain = Vec::with_capacity(codecs.len());
+    for codec in codecs {
+        if codec["name"].as_str() == Some("transpose") {
+            order = transpose_order(&codec["configuration"]["order"], ndim)?;
+        } else {
+            chain.push(codec);
+        }
+    }
+    Ok((order, chain))
+}
+
+/// The `transpose` codec storing chunks of `ndim` dimensions in the given order, if needed
+pub(crate) fn transpose_codec(order: ChunkOrder, ndim: usize) -> Option<JsonValue> {
+    (order == ChunkOrder::F && ndim > 1).then(|| {
+        let axes: Vec<usize> = (0..ndim).rev().collect();
+        serde_json::json!({ "name": "transpose", "configuration": { "order": axes } })
+    })
+}
+
+/// Map the axis permutation of a v3 `transpose` codec to a chunk memory order
+fn transpose_order(order: &JsonValue, ndim: usize) -> Result<ChunkOrder> {
+    let permutation: Vec<u64> = order
+        .as_array()
+        .map(|axes| axes.iter().filter_map(JsonValue::as_u64).collect())
+        .unwrap_or_default();
+    let identity: Vec<u64> = (0..ndim as u64).collect();
+    let reversed: Vec<u64> = identity.iter().rev().copied().collect();
+
+    if permutation == identity {
+        Ok(ChunkOrder::C)
+    } else if permutation == reversed {
+        Ok(ChunkOrder::F)
+    } else {
+        Err(RuNeVisError::ZarrError(format!(
+            "Unsupported transpose order {} (only C and F orders are supported)",
+            order
+        )))
+    }
+}
+
+/// Parse a list of non-negative integers such as `shape` or `chunks`
+fn parse_usize_list(doc: &JsonValue, field: &str) -> Result<Vec<usize>> {
+    doc[field]
+        .as_array()
+        .ok_or_else(|| RuNeVisError::ZarrError(format!("Missing {} in metadata", field)))?
+        .iter()
+        .map(|v| {
+            v.as_u64().map(|n| n as usize).ok_or_else(|| {
+                RuNeVisError::ZarrError(format!("Invalid entry {} in {} metadata", v, field))
+            })
+        })
+        .collect()
+}
+
+/// Parse a `fill_value`, including the special string encodings of non-finite floats
+pub(crate) fn parse_fill_value(value: &JsonValue) -> Result<f64> {
+    match value {
+        JsonValue::Null => Ok(0.0),
+        JsonValue::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+        JsonValue::Number(n) => n
+            .as_f64()
+            .ok_or_else(|| RuNeVisError::ZarrError(format!("Invalid fill_value {}", n))),
+        JsonValue::String(s) => match s.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            // Zarr v3 may encode float fill values by their bit pattern
+            hex if hex.starts_with("0x") && hex.len() == 18 => u64::from_str_radix(&hex[2..], 16)
+                .map(f64::from_bits)
+                .map_err(|_| RuNeVisError::ZarrError(format!("Invalid fill_value '{}'", hex))),
+            hex if hex.starts_with("0x") => u32::from_str_radix(&hex[2..], 16)
+                .map(|bits| f64::from(f32::from_bits(bits)))
+                .map_err(|_| RuNeVisError::ZarrError(format!("Invalid fill_value '{}'", hex))),
+            other => Err(RuNeVisError::ZarrError(format!(
+                "Unsupported fill_value '{}'",
+                other
+            ))),
+        },
+        other => Err(RuNeVisError::ZarrError(format!(
+            "Unsupported fill_value {}",
+            other
+        ))),
+    }
+}
+
+/// Encode a fill value for elements of `dtype`, using the special strings for non-finite floats
+pub(crate) fn fill_value_to_json(value: f64, dtype: &DataType) -> JsonValue {
+    match dtype.kind {
+        DataKind::Bool => return JsonValue::Bool(value != 0.0),
+        DataKind::Int if value.is_finite() => return serde_json::json!(value as i64),
+        DataKind::UInt if value.is_finite() => return serde_json::json!(value as u64),
+        _ => {}
+    }
+    if value.is_nan() {
+        JsonValue::String("NaN".to_string())
+    } else if value == f64::INFINITY {
+        JsonValue::String("Infinity".to_string())
+    } else if value == f64::NEG_INFINITY {
+        JsonValue::String("-Infinity".to_string())
+    } else {
+        serde_json::json!(value)
+    }
+}
@@ -0,0 +1,134 @@
+//! Local filesystem store
+
+use super::ZarrStore;
+use crate::errors::{Result, RuNeVisError};
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes the temporary files of concurrent writes
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Zarr store in a directory of the local filesystem
+///
+/// Keys map to files below the root directory; nested chunk keys such as
+/// `c/0/1` live in subdirectories, which are created on write. Objects are
+/// replaced atomically by renaming a temporary file over them.
+#[derive(Debug, Clone)]
+pub struct FilesystemStore {
+    root: PathBuf,
+}
+
+impl FilesystemStore {
+    /// Open the store rooted at `root`, which need not exist until written to
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Root directory of the store
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+impl ZarrStore for FilesystemStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match std::fs::read(self.path(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if is_missing(&e) => Ok(None),
+            Err(e) => Err(RuNeVisError::IoError(e)),
+        }
+    }
+
+    fn get_range(&self, key: &str, offset: u64, length: u64) -> Result<Option<Vec<u8>>> {
+        let mut file = match std::fs::File::open(self.path(key)) {
+            Ok(file) => file,
+            Err(e) if is_missing(&e) => return Ok(None),
+            Err(e) => return Err(RuNeVisError::IoError(e)),
+        };
+        file.seek(SeekFrom::Start(offset))
+            .map_err(RuNeVisError::IoError)?;
+        let mut buffer = Vec::new();
+        file.take(length)
+            .read_to_end(&mut buffer)
+            .map_err(RuNeVisError::IoError)?;
+        Ok(Some(buffer))
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        match std::fs::metadata(self.path(key)) {
+            Ok(metadata) if metadata.is_file() => Ok(Some(metadata.len())),
+            Ok(_) => Ok(None),
+            Err(e) if is_missing(&e) => Ok(None),
+            Err(e) => Err(RuNeVisError::IoError(e)),
+        }
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(RuNeVisError::IoError)?;
+        }
+        // Write next to the target and rename it into place, so readers never
+        // see a partially written document or chunk
+        let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or(key);
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}-{}.tmp",
+            file_name,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&temp_path, value).map_err(RuNeVisError::IoError)?;
+        std::fs::rename(&temp_path, &path).map_err(|e| {
+            let _ = std::fs::remove_file(&temp_path);
+            RuNeVisError::IoError(e)
+        })
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match std::fs::remove_file(self.path(key)) {
+            Err(e) if !is_missing(&e) => Err(RuNeVisError::IoError(e)),
+            _ => Ok(()),
+        }
+    }
+
+    fn list_dir(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(self.path(prefix)).map_err(RuNeVisError::IoError)? {
+            let entry = entry.map_err(RuNeVisError::IoError)?;
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+        Ok(names)
+    }
+
+    fn validate(&self) -> Result<()> {
+        // Verify the path exists and is a directory
+        if !self.root.exists() {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Zarr store path does not exist: {:?}",
+                self.root
+            )));
+        }
+        if !self.root.is_dir() {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Zarr store path is not a directory: {:?}",
+                self.root
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn is_missing(error: &std::io::Error) -> bool {
+    error.kind() == ErrorKind::NotFound
+}
@@ -0,0 +1,294 @@
+//! Read-only HTTP(S) store and the HTTP client shared by the remote stores
+//!
+//! Static file hosting serves every key of a store as `<base URL>/<key>`, but
+//! offers no way to list directories, so hierarchies on plain HTTP can only be
+//! browsed through their consolidated metadata.
+
+use super::ZarrStore;
+use crate::errors::{Result, RuNeVisError};
+use std::io::Read;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// Statuses of transient server failures that are worth retrying
+const RETRY_STATUSES: [u16; 5] = [429, 500, 502, 503, 504];
+
+/// Connection settings of the remote stores
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// Retries of requests failing with a transport error or a transient status
+    pub max_retries: u32,
+    /// Maximum number of requests in flight at once
+    pub max_concurrent_requests: usize,
+    /// Timeout of establishing a connection and of every read
+    pub timeout: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            max_concurrent_requests: 16,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// A response whose body has been read
+pub(super) struct HttpResponse {
+    pub(super) status: u16,
+    pub(super) content_length: Option<u64>,
+    pub(super) body: Vec<u8>,
+}
+
+/// Blocking HTTP client with connection reuse, retries and a concurrency limit
+#[derive(Debug)]
+pub(super) struct HttpClient {
+    agent: ureq::Agent,
+    max_retries: u32,
+    limit: ConcurrencyLimit,
+}
+
+impl HttpClient {
+    pub(super) fn new(config: &HttpConfig) -> Self {
+        let max_concurrent = config.max_concurrent_requests.max(1);
+        // Keep one idle connection per concurrent request so connections are reused
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(config.timeout)
+            .timeout_read(config.timeout)
+            .max_idle_connections_per_host(max_concurrent)
+            .build();
+        Self {
+            agent,
+            max_retries: config.max_retries,
+            limit: ConcurrencyLimit::new(max_concurrent),
+        }
+    }
+
+    pub(super) fn request(&self, method: &str, url: &str) -> ureq::Request {
+        self.agent.request(method, url)
+    }
+
+    /// Send a request and read its response, retrying transient failures
+    ///
+    /// Returns `None` when the server answers 404. `description` names the
+    /// request in error messages, e.g. `HTTP request for 'tas/.zarray'`.
+    pub(super) fn send(
+        &self,
+        request: ureq::Request,
+        body: Option<&[u8]>,
+        description: &str,
+    ) -> Result<Option<HttpResponse>> {
+        let mut attempt = 0;
+        loop {
+            let permit = self.limit.acquire();
+            let result = match body {
+                Some(body) => request.clone().send_bytes(body),
+                None => request.clone().call(),
+            };
+            let transient = match &result {
+                Ok(_) => false,
+                Err(ureq::Error::Status(status, _)) => RETRY_STATUSES.contains(status),
+                Err(ureq::Error::Transport(_)) => true,
+            };
+            if transient && attempt < self.max_retries {
+                drop(permit);
+                std::thread::sleep(Duration::from_millis(100 * 2u64.pow(attempt)));
+                attempt += 1;
+                continue;
+            }
+
+            return match result {
+                Ok(response) => {
+                    let status = response.status();
+                    let content_length = response
+                        .header("Content-Length")
+                        .and_then(|len| len.parse().ok());
+                    let mut body = Vec::new();
+                    response
+                        .into_reader()
+                        .read_to_end(&mut body)
+                        .map_err(RuNeVisError::IoError)?;
+                    Ok(Some(HttpResponse {
+                        status,
+                        content_length,
+                        body,
+                    }))
+                }
+                Err(ureq::Error::Status(404, _)) => Ok(None),
+                Err(ureq::Error::Status(status, response)) => {
+                    Err(RuNeVisError::ZarrError(format!(
+                        "{} failed with status {}: {}",
+                        description,
+                        status,
+                        response.into_string().unwrap_or_default()
+                    )))
+                }
+                Err(e) => Err(RuNeVisError::ZarrError(format!(
+                    "{} failed: {}",
+                    description, e
+                ))),
+            };
+        }
+    }
+}
+
+/// Counting semaphore bounding the number of requests in flight
+#[derive(Debug)]
+struct ConcurrencyLimit {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl ConcurrencyLimit {
+    fn new(permits: usize) -> Self {
+        Self {
+            available: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) -> Permit<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.released.wait(available).unwrap();
+        }
+        *available -= 1;
+        Permit { limit: self }
+    }
+}
+
+struct Permit<'a> {
+    limit: &'a ConcurrencyLimit,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.limit.available.lock().unwrap() += 1;
+        self.limit.released.notify_one();
+    }
+}
+
+/// Read-only Zarr store served over HTTP(S)
+#[derive(Debug)]
+pub struct HttpStore {
+    /// URL of the store root, without trailing slash
+    base_url: String,
+    client: HttpClient,
+}
+
+impl HttpStore {
+    /// Open the store at `url` with the default connection settings
+    pub fn new(url: &str) -> Result<Self> {
+        Self::with_config(url, HttpConfig::default())
+    }
+
+    /// Open the store at `url`
+    pub fn with_config(url: &str, config: HttpConfig) -> Result<Self> {
+        let host = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .map(|rest| rest.split('/').next().unwrap_or_default());
+        if host.map_or(true, str::is_empty) {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Invalid HTTP store URL '{}'",
+                url
+            )));
+        }
+        Ok(Self {
+            base_url: url.trim_end_matches('/').to_string(),
+            client: HttpClient::new(&config),
+        })
+    }
+
+    fn get_object(
+        &self,
+        method: &str,
+        key: &str,
+        range: Option<String>,
+    ) -> Result<Option<HttpResponse>> {
+        let url = format!("{}/{}", self.base_url, uri_encode(key, false));
+        let mut request = self.client.request(method, &url);
+        if let Some(range) = range {
+            request = request.set("Range", &range);
+        }
+        self.client
+            .send(request, None, &format!("HTTP request for '{}'", url))
+    }
+
+    fn read_only(&self, key: &str) -> RuNeVisError {
+        RuNeVisError::ZarrError(format!(
+            "Cannot write '{}': HTTP store {} is read-only",
+            key, self.base_url
+        ))
+    }
+}
+
+impl ZarrStore for HttpStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .get_object("GET", key, None)?
+            .map(|response| response.body))
+    }
+
+    fn get_range(&self, key: &str, offset: u64, length: u64) -> Result<Option<Vec<u8>>> {
+        if length == 0 {
+            return Ok(self.exists(key)?.then(Vec::new));
+        }
+        let range = format!("bytes={}-{}", offset, offset + length - 1);
+        Ok(self
+            .get_object("GET", key, Some(range))?
+            .map(|response| ranged_body(response, offset, length)))
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        let Some(response) = self.get_object("HEAD", key, None)? else {
+            return Ok(None);
+        };
+        match response.content_length {
+            Some(len) => Ok(Some(len)),
+            // Servers may omit the length of dynamically generated responses
+            None => Ok(self.get(key)?.map(|bytes| bytes.len() as u64)),
+        }
+    }
+
+    fn set(&self, key: &str, _value: &[u8]) -> Result<()> {
+        Err(self.read_only(key))
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        Err(self.read_only(key))
+    }
+
+    fn list_dir(&self, prefix: &str) -> Result<Vec<String>> {
+        Err(RuNeVisError::ZarrError(format!(
+            "Cannot list '{}' of HTTP store {}: HTTP stores need consolidated metadata to be browsed",
+            prefix, self.base_url
+        )))
+    }
+}
+
+/// The requested bytes of a range response; servers ignoring the range send the whole object
+pub(super) fn ranged_body(response: HttpResponse, offset: u64, length: u64) -> Vec<u8> {
+    if response.status == 206 {
+        return response.body;
+    }
+    let start = (offset as usize).min(response.body.len());
+    let end = start.saturating_add(length as usize).min(response.body.len());
+    response.body[start..end].to_vec()
+}
+
+/// Percent-encode everything but unreserved characters (and `/` unless `encode_slash`)
+pub(super) fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,110 @@
+//! In-memory store
+
+use super::ZarrStore;
+use crate::errors::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::RwLock;
+
+/// Zarr store keeping every object in memory
+///
+/// Intermediate results of multi-step pipelines can stay in RAM and be
+/// written to a persistent store at the end with [`MemoryStore::copy_to`].
+/// Share the store between a [`ZarrWriter`](crate::zarr_io::ZarrWriter) and a
+/// [`ZarrReader`](crate::zarr_io::ZarrReader) through
+/// [`ZarrSource::from_store`](crate::zarr_io::ZarrSource::from_store):
+///
+/// ```no_run
+/// # async fn example() -> ru_ne_vis::errors::Result<()> {
+/// use ru_ne_vis::zarr_io::{FilesystemStore, MemoryStore, ZarrReader, ZarrSource};
+/// use std::sync::Arc;
+///
+/// let store = Arc::new(MemoryStore::new());
+/// let source = ZarrSource::from_store("memory", store.clone());
+/// // ... write intermediate arrays with a ZarrWriter on `source.clone()` ...
+/// let _reader = ZarrReader::new(source).await?;
+/// store.copy_to(&FilesystemStore::new("result.zarr"))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    objects: RwLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys of every stored object, in sorted order
+    pub fn keys(&self) -> Vec<String> {
+        self.objects.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Total size of the stored objects in bytes
+    pub fn nbytes(&self) -> usize {
+        self.objects.read().unwrap().values().map(Vec::len).sum()
+    }
+
+    /// Write every object to `target`, e.g. a [`FilesystemStore`](super::FilesystemStore)
+    pub fn copy_to(&self, target: &dyn ZarrStore) -> Result<()> {
+        for (key, value) in self.objects.read().unwrap().iter() {
+            target.set(key, value)?;
+        }
+        target.flush()
+    }
+}
+
+impl ZarrStore for MemoryStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.objects.read().unwrap().get(key).cloned())
+    }
+
+    fn get_range(&self, key: &str, offset: u64, length: u64) -> Result<Option<Vec<u8>>> {
+        Ok(self.objects.read().unwrap().get(key).map(|bytes| {
+            let start = (offset as usize).min(bytes.len());
+            let end = start.saturating_add(length as usize).min(bytes.len());
+            bytes[start..end].to_vec()
+        }))
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        Ok(self
+            .objects
+            .read()
+            .unwrap()
+            .get(key)
+            .map(|bytes| bytes.len() as u64))
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.objects
+            .write()
+            .unwrap()
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.objects.write().unwrap().remove(key);
+        Ok(())
+    }
+
+    fn list_dir(&self, prefix: &str) -> Result<Vec<String>> {
+        let dir = if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", prefix)
+        };
+        let names: BTreeSet<String> = self
+            .objects
+            .read()
+            .unwrap()
+            .keys()
+            .filter_map(|key| key.strip_prefix(dir.as_str()))
+            .filter_map(|rest| rest.split_once('/').map(|(name, _)| name.to_string()))
+            .collect();
+        Ok(names.into_iter().collect())
+    }
+}
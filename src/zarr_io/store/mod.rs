@@ -0,0 +1,90 @@
+//! Storage backends for Zarr stores
+//!
+//! A store maps `/`-separated keys such as `atmos/tas/.zarray` or
+//! `atmos/tas/0.0` to byte objects. [`ZarrReader`](super::ZarrReader) and
+//! [`ZarrWriter`](super::ZarrWriter) only talk to a store through the
+//! [`ZarrStore`] trait, so the same listing, metadata and chunk logic works for
+//! every backend:
+//!
+//! - [`FilesystemStore`]: a directory on the local filesystem
+//! - [`MemoryStore`]: objects kept in memory, for tests and pipeline intermediates
+//! - [`S3Store`]: an S3-compatible object store (AWS S3, MinIO, Google Cloud Storage)
+//! - [`HttpStore`]: a read-only store served by plain HTTP(S) file hosting
+//! - [`ZipStore`]: a store packaged in a zip archive such as `store.zarr.zip`
+
+mod filesystem;
+mod http;
+mod memory;
+mod s3;
+mod zip;
+
+pub use filesystem::FilesystemStore;
+pub use http::{HttpConfig, HttpStore};
+pub use memory::MemoryStore;
+pub use s3::{S3Config, S3Credentials, S3Store, SigV4Request};
+pub use zip::ZipStore;
+
+use crate::errors::Result;
+
+/// Key-value storage holding the documents and chunks of a Zarr store
+///
+/// Keys are relative to the root of the store, use `/` as separator and never
+/// start with `/`. Methods are blocking; they are called from Rayon worker
+/// threads when chunks are read or written in parallel.
+pub trait ZarrStore: Send + Sync + std::fmt::Debug {
+    /// Read a whole object, returning `None` if it does not exist
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Read `length` bytes of an object starting at `offset`
+    ///
+    /// The result is cut at the end of the object, so it may be shorter.
+    fn get_range(&self, key: &str, offset: u64, length: u64) -> Result<Option<Vec<u8>>> {
+        Ok(self.get(key)?.map(|bytes| {
+            let start = (offset as usize).min(bytes.len());
+            let end = start.saturating_add(length as usize).min(bytes.len());
+            bytes[start..end].to_vec()
+        }))
+    }
+
+    /// Size of an object in bytes, `None` if it does not exist
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        Ok(self.get(key)?.map(|bytes| bytes.len() as u64))
+    }
+
+    /// Check whether an object exists
+    fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.size(key)?.is_some())
+    }
+
+    /// Write an object, replacing any previous content
+    fn set(&self, key: &str, value: &[u8]) -> Result<()>;
+
+    /// Remove an object; removing a missing object is not an error
+    fn delete(&self, key: &str) -> Result<()>;
+
+    /// Names of the child "directories" below `prefix` (`""` for the root)
+    ///
+    /// These are the candidate groups and arrays of a hierarchy walk.
+    fn list_dir(&self, prefix: &str) -> Result<Vec<String>>;
+
+    /// Check that the store can be opened for reading
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Persist writes that the backend buffers; called after every writer operation
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Join a node path and a name into a store key: `atmos` + `.zattrs` gives `atmos/.zattrs`
+pub(crate) fn join_key(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else if name.is_empty() {
+        prefix.to_string()
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
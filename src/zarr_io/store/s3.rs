@@ -0,0 +1,494 @@
+//! S3-compatible object store
+//!
+//! Objects are read with plain (optionally range) `GET` requests and listed
+//! with `ListObjectsV2`. Requests are signed with AWS Signature Version 4 when
+//! credentials are configured and sent anonymously otherwise, which is enough
+//! for public buckets.
+
+use super::http::{ranged_body, uri_encode, HttpClient, HttpConfig, HttpResponse};
+use super::{join_key, ZarrStore};
+use crate::errors::{Result, RuNeVisError};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+/// Endpoint of the S3-compatible XML API of Google Cloud Storage, used for `gs://` URLs
+const GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+
+/// Credentials used to sign requests
+#[derive(Clone)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Session token of temporary credentials
+    pub session_token: Option<String>,
+}
+
+impl S3Credentials {
+    /// Read `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            access_key_id: env_var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: env_var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: env_var("AWS_SESSION_TOKEN"),
+        })
+    }
+}
+
+// Keep the secret out of debug output
+impl std::fmt::Debug for S3Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A request to sign with AWS Signature Version 4
+#[derive(Debug, Clone, Copy)]
+pub struct SigV4Request<'a> {
+    pub method: &'a str,
+    /// URI-encoded path
+    pub path: &'a str,
+    /// Canonical query string: URI-encoded `name=value` pairs sorted by name
+    pub query: &'a str,
+    /// Headers to sign, including `host` and `x-amz-date`
+    pub headers: &'a [(&'a str, &'a str)],
+    /// Hex-encoded SHA-256 of the payload
+    pub payload_hash: &'a str,
+}
+
+impl S3Credentials {
+    /// `Authorization` header of a request signed with AWS Signature Version 4
+    ///
+    /// `amz_date` is the request time in the format of the `x-amz-date`
+    /// header (`20130524T000000Z`). Header names are lowercased and values
+    /// trimmed, with runs of spaces collapsed, before signing.
+    pub fn authorization(
+        &self,
+        request: &SigV4Request<'_>,
+        region: &str,
+        service: &str,
+        amz_date: &str,
+    ) -> String {
+        let mut headers: Vec<(String, String)> = request
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+                (name.to_ascii_lowercase(), value)
+            })
+            .collect();
+        headers.sort();
+
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            request.method,
+            request.path,
+            request.query,
+            canonical_headers,
+            signed_headers,
+            request.payload_hash
+        );
+
+        let date = &amz_date[..8];
+        let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let secret = format!("AWS4{}", self.secret_access_key);
+        let mut key = hmac_sha256(secret.as_bytes(), date.as_bytes());
+        for part in [region, service, "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        )
+    }
+}
+
+/// Connection settings of an S3-compatible service
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    /// Endpoint URL such as `http://localhost:9000`; `None` uses AWS S3 in `region`
+    pub endpoint: Option<String>,
+    pub region: String,
+    /// Credentials; `None` sends anonymous requests
+    pub credentials: Option<S3Credentials>,
+    /// Address buckets as `<endpoint>/<bucket>` instead of `<bucket>.<endpoint host>`
+    pub path_style: bool,
+    /// Retries, concurrency limit and timeouts of the requests
+    pub http: HttpConfig,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            region: "us-east-1".to_string(),
+            credentials: None,
+            path_style: false,
+            http: HttpConfig::default(),
+        }
+    }
+}
+
+impl S3Config {
+    /// Read the configuration from the standard AWS environment variables
+    ///
+    /// `AWS_ENDPOINT_URL_S3` or `AWS_ENDPOINT_URL` select an S3-compatible
+    /// service (addressed path-style), `AWS_REGION` or `AWS_DEFAULT_REGION` the
+    /// region, and the credentials are read by [`S3Credentials::from_env`].
+    pub fn from_env() -> Self {
+        let endpoint = env_var("AWS_ENDPOINT_URL_S3").or_else(|| env_var("AWS_ENDPOINT_URL"));
+        Self {
+            path_style: endpoint.is_some(),
+            endpoint,
+            region: env_var("AWS_REGION")
+                .or_else(|| env_var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|| "us-east-1".to_string()),
+            credentials: S3Credentials::from_env(),
+            http: HttpConfig::default(),
+        }
+    }
+}
+
+/// Zarr store under a prefix of an S3 bucket
+#[derive(Debug)]
+pub struct S3Store {
+    /// Key prefix of the store root inside the bucket, without slashes at either end
+    prefix: String,
+    /// `scheme://host[:port]` of the bucket
+    origin: String,
+    /// `host[:port]` of the bucket, part of every signature
+    host: String,
+    /// URL path of the bucket: `/<bucket>` when addressed path-style, empty otherwise
+    bucket_path: String,
+    config: S3Config,
+    client: HttpClient,
+}
+
+impl S3Store {
+    /// Open the store at `prefix` inside `bucket`
+    pub fn new(bucket: &str, prefix: &str, config: S3Config) -> Result<Self> {
+        if bucket.is_empty() {
+            return Err(RuNeVisError::ZarrError(
+                "S3 bucket name is empty".to_string(),
+            ));
+        }
+        let endpoint = config
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", config.region));
+        let Some((scheme, endpoint_host)) = endpoint.trim_end_matches('/').split_once("://") else {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Invalid S3 endpoint URL '{}'",
+                endpoint
+            )));
+        };
+        if endpoint_host.is_empty() || endpoint_host.contains('/') {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Invalid S3 endpoint URL '{}' (expected scheme://host[:port])",
+                endpoint
+            )));
+        }
+
+        let (host, bucket_path) = if config.path_style {
+            (
+                endpoint_host.to_string(),
+                format!("/{}", uri_encode(bucket, false)),
+            )
+        } else {
+            (format!("{}.{}", bucket, endpoint_host), String::new())
+        };
+        Ok(Self {
+            prefix: prefix.trim_matches('/').to_string(),
+            origin: format!("{}://{}", scheme, host),
+            host,
+            bucket_path,
+            client: HttpClient::new(&config.http),
+            config,
+        })
+    }
+
+    /// Open a store from an `s3://bucket/prefix` or `gs://bucket/prefix` URL
+    ///
+    /// `gs://` URLs use the S3-compatible API of Google Cloud Storage (with
+    /// HMAC keys as credentials) unless `config` names another endpoint.
+    pub fn from_url(url: &str, mut config: S3Config) -> Result<Self> {
+        let (scheme, location) = url.split_once("://").unwrap_or(("", url));
+        match scheme {
+            "s3" => {}
+            "gs" if config.endpoint.is_none() => {
+                config.endpoint = Some(GCS_ENDPOINT.to_string());
+                config.path_style = true;
+            }
+            "gs" => {}
+            _ => {
+                return Err(RuNeVisError::ZarrError(format!(
+                    "Unsupported object store URL '{}' (expected s3:// or gs://)",
+                    url
+                )))
+            }
+        }
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        Self::new(bucket, prefix, config)
+    }
+
+    /// URL path of the object holding `key`
+    fn object_path(&self, key: &str) -> String {
+        format!(
+            "{}/{}",
+            self.bucket_path,
+            uri_encode(&join_key(&self.prefix, key), false)
+        )
+    }
+
+    /// Build a request, signing it when credentials are configured
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        query: &str,
+        headers: &[(&str, String)],
+        payload: &[u8],
+    ) -> ureq::Request {
+        let url = if query.is_empty() {
+            format!("{}{}", self.origin, path)
+        } else {
+            format!("{}{}?{}", self.origin, path, query)
+        };
+        let mut request = self.client.request(method, &url);
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+        let Some(credentials) = &self.config.credentials else {
+            return request;
+        };
+
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex(&Sha256::digest(payload));
+        let mut signed: Vec<(&str, &str)> = headers
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        signed.push(("host", self.host.as_str()));
+        signed.push(("x-amz-content-sha256", payload_hash.as_str()));
+        signed.push(("x-amz-date", amz_date.as_str()));
+        if let Some(token) = &credentials.session_token {
+            signed.push(("x-amz-security-token", token.as_str()));
+            request = request.set("x-amz-security-token", token);
+        }
+
+        let authorization = credentials.authorization(
+            &SigV4Request {
+                method,
+                path,
+                query,
+                headers: &signed,
+                payload_hash: &payload_hash,
+            },
+            &self.config.region,
+            "s3",
+            &amz_date,
+        );
+        request
+            .set("x-amz-content-sha256", &payload_hash)
+            .set("x-amz-date", &amz_date)
+            .set("Authorization", &authorization)
+    }
+
+    /// Send a request; `None` means the object does not exist
+    fn send(
+        &self,
+        key: &str,
+        request: ureq::Request,
+        body: Option<&[u8]>,
+    ) -> Result<Option<HttpResponse>> {
+        self.client
+            .send(request, body, &format!("S3 request for '{}'", key))
+    }
+}
+
+impl ZarrStore for S3Store {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let request = self.request("GET", &self.object_path(key), "", &[], &[]);
+        Ok(self.send(key, request, None)?.map(|response| response.body))
+    }
+
+    fn get_range(&self, key: &str, offset: u64, length: u64) -> Result<Option<Vec<u8>>> {
+        if length == 0 {
+            return Ok(self.exists(key)?.then(Vec::new));
+        }
+        let range = format!("bytes={}-{}", offset, offset + length - 1);
+        let request = self.request("GET", &self.object_path(key), "", &[("Range", range)], &[]);
+        Ok(self
+            .send(key, request, None)?
+            .map(|response| ranged_body(response, offset, length)))
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        let request = self.request("HEAD", &self.object_path(key), "", &[], &[]);
+        let Some(response) = self.send(key, request, None)? else {
+            return Ok(None);
+        };
+        response.content_length.map(Some).ok_or_else(|| {
+            RuNeVisError::ZarrError(format!("S3 response for '{}' has no Content-Length", key))
+        })
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        let request = self.request("PUT", &self.object_path(key), "", &[], value);
+        self.send(key, request, Some(value))?.ok_or_else(|| {
+            RuNeVisError::ZarrError(format!("S3 bucket of '{}' does not exist", key))
+        })?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let request = self.request("DELETE", &self.object_path(key), "", &[], &[]);
+        self.send(key, request, None).map(|_| ())
+    }
+
+    fn list_dir(&self, prefix: &str) -> Result<Vec<String>> {
+        let full_prefix = join_key(&self.prefix, prefix);
+        let list_prefix = if full_prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", full_prefix)
+        };
+        let bucket_path = if self.bucket_path.is_empty() {
+            "/"
+        } else {
+            self.bucket_path.as_str()
+        };
+
+        let mut names = Vec::new();
+        let mut continuation: Option<String> = None;
+        loop {
+            // Query parameters must be sorted by name for the signature
+            let mut query = String::new();
+            if let Some(token) = &continuation {
+                query.push_str(&format!("continuation-token={}&", uri_encode(token, true)));
+            }
+            query.push_str(&format!(
+                "delimiter=%2F&list-type=2&prefix={}",
+                uri_encode(&list_prefix, true)
+            ));
+
+            let request = self.request("GET", bucket_path, &query, &[], &[]);
+            let Some(response) = self.send(prefix, request, None)? else {
+                return Err(RuNeVisError::ZarrError(format!(
+                    "S3 bucket of store prefix '{}' does not exist",
+                    full_prefix
+                )));
+            };
+            let body = String::from_utf8_lossy(&response.body).into_owned();
+
+            for common_prefix in xml_elements(&body, "CommonPrefixes") {
+                for child in xml_elements(common_prefix, "Prefix") {
+                    let child = xml_unescape(child);
+                    if let Some(name) = child.strip_prefix(&list_prefix) {
+                        names.push(name.trim_end_matches('/').to_string());
+                    }
+                }
+            }
+
+            let truncated = xml_elements(&body, "IsTruncated").first() == Some(&"true");
+            continuation = xml_elements(&body, "NextContinuationToken")
+                .first()
+                .map(|token| xml_unescape(token));
+            if !truncated || continuation.is_none() {
+                break;
+            }
+        }
+        Ok(names)
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Contents of every `<tag>...</tag>` element in an XML document
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        elements.push(&rest[..end]);
+        rest = &rest[end + close.len()..];
+    }
+    elements
+}
+
+/// Resolve the predefined entities and the character references of XML text
+fn xml_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest
+            .find(';')
+            .and_then(|end| Some((xml_reference(&rest[1..end])?, end)));
+        match reference {
+            Some((character, end)) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Character named by an entity (`amp`) or character reference (`#38`, `#x26`)
+fn xml_reference(name: &str) -> Option<char> {
+    let code = match name {
+        "lt" => return Some('<'),
+        "gt" => return Some('>'),
+        "amp" => return Some('&'),
+        "quot" => return Some('"'),
+        "apos" => return Some('\''),
+        _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => name.strip_prefix('#')?.parse().ok()?,
+        },
+    };
+    char::from_u32(code)
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
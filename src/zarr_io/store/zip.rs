@@ -0,0 +1,328 @@
+//! Zip-archive store
+//!
+//! Archived deliveries often come as `store.zarr.zip`. Entries are read in
+//! place without extracting the archive. Writes are staged in memory and reach
+//! the archive on [`ZarrStore::flush`], or as soon as the staged values exceed
+//! the flush threshold (see [`ZipStore::with_flush_threshold`]), so writing a
+//! large store never holds more than about one threshold of chunks in memory.
+//! New keys are appended, while replacing or deleting existing entries
+//! rewrites the archive, because zip files cannot hold two entries with the
+//! same name.
+
+use super::ZarrStore;
+use crate::errors::{Result, RuNeVisError};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Staged bytes above which writes are flushed to the archive (256 MiB)
+const DEFAULT_FLUSH_THRESHOLD: usize = 256 * 1024 * 1024;
+
+/// Root documents identifying the top level of a Zarr hierarchy
+const ROOT_DOCUMENTS: [&str; 4] = [".zgroup", ".zarray", ".zmetadata", "zarr.json"];
+
+/// Zarr store packaged in a zip archive
+///
+/// Keys are entry names, relative to the top-level directory of the archive
+/// when the whole store was zipped as a folder (`zip -r store.zarr.zip store.zarr`).
+/// Entries are stored uncompressed, like `zarr.storage.ZipStore`, since chunks
+/// carry their own compression.
+#[derive(Debug)]
+pub struct ZipStore {
+    path: PathBuf,
+    flush_threshold: usize,
+    state: Mutex<ZipState>,
+}
+
+#[derive(Debug, Default)]
+struct ZipState {
+    /// Opened archive, `None` until first accessed or when the file does not exist yet
+    archive: Option<ZipArchive<File>>,
+    opened: bool,
+    /// Entry name prefix of the store root: empty, or a top-level directory with trailing `/`
+    root: String,
+    /// Staged writes (`Some`) and deletions (`None`) not yet flushed
+    pending: BTreeMap<String, Option<Vec<u8>>>,
+    /// Total size of the staged values
+    pending_bytes: usize,
+}
+
+impl ZipStore {
+    /// Open the archive at `path`, which need not exist until written to
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            flush_threshold: DEFAULT_FLUSH_THRESHOLD,
+            state: Mutex::new(ZipState::default()),
+        }
+    }
+
+    /// Flush staged writes once they exceed `bytes` (256 MiB by default)
+    ///
+    /// Lower thresholds bound memory use more tightly, at the cost of more
+    /// frequent archive updates; each flush that replaces or deletes existing
+    /// entries rewrites the whole archive.
+    #[must_use]
+    pub fn with_flush_threshold(mut self, bytes: usize) -> Self {
+        self.flush_threshold = bytes;
+        self
+    }
+
+    /// Path of the zip archive
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Lock the state, opening the archive on first use
+    fn state(&self) -> Result<std::sync::MutexGuard<'_, ZipState>> {
+        let mut state = self.state.lock().unwrap();
+        if !state.opened {
+            if self.path.exists() {
+                let file = File::open(&self.path).map_err(RuNeVisError::IoError)?;
+                let archive = ZipArchive::new(file).map_err(|e| self.zip_error(e))?;
+                state.root = store_root(&archive);
+                state.archive = Some(archive);
+            }
+            state.opened = true;
+        }
+        Ok(state)
+    }
+
+    /// Write the staged changes of a locked state to the archive
+    fn flush_state(&self, state: &mut ZipState) -> Result<()> {
+        if state.pending.is_empty() {
+            return Ok(());
+        }
+        let pending = std::mem::take(&mut state.pending);
+        state.pending_bytes = 0;
+        let root = state.root.clone();
+        let archived: BTreeSet<String> = state.archived_keys().into_iter().collect();
+
+        let options = |len: usize| {
+            SimpleFileOptions::default()
+                .compression_method(CompressionMethod::Stored)
+                .large_file(len as u64 >= u32::MAX as u64)
+        };
+
+        if pending.keys().all(|key| !archived.contains(key)) {
+            // Only new keys: append them after the existing entries
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&self.path)
+                .map_err(RuNeVisError::IoError)?;
+            let mut writer = if state.archive.is_some() {
+                ZipWriter::new_append(file).map_err(|e| self.zip_error(e))?
+            } else {
+                ZipWriter::new(file)
+            };
+            for (key, value) in pending
+                .iter()
+                .filter_map(|(key, value)| Some((key, value.as_ref()?)))
+            {
+                writer
+                    .start_file(format!("{}{}", root, key), options(value.len()))
+                    .map_err(|e| self.zip_error(e))?;
+                writer.write_all(value).map_err(RuNeVisError::IoError)?;
+            }
+            writer.finish().map_err(|e| self.zip_error(e))?;
+        } else {
+            // Replaced or deleted entries: copy the untouched ones into a new archive
+            let temp_path = self.path.with_extension("zip.tmp");
+            let file = File::create(&temp_path).map_err(RuNeVisError::IoError)?;
+            let mut writer = ZipWriter::new(file);
+            if let Some(archive) = state.archive.as_mut() {
+                for index in 0..archive.len() {
+                    let entry = archive.by_index_raw(index).map_err(|e| self.zip_error(e))?;
+                    let replaced = entry
+                        .name()
+                        .strip_prefix(root.as_str())
+                        .is_some_and(|key| pending.contains_key(key));
+                    if !replaced {
+                        writer.raw_copy_file(entry).map_err(|e| self.zip_error(e))?;
+                    }
+                }
+            }
+            for (key, value) in pending
+                .iter()
+                .filter_map(|(key, value)| Some((key, value.as_ref()?)))
+            {
+                writer
+                    .start_file(format!("{}{}", root, key), options(value.len()))
+                    .map_err(|e| self.zip_error(e))?;
+                writer.write_all(value).map_err(RuNeVisError::IoError)?;
+            }
+            writer.finish().map_err(|e| self.zip_error(e))?;
+            // Release the old archive before replacing it
+            state.archive = None;
+            std::fs::rename(&temp_path, &self.path).map_err(RuNeVisError::IoError)?;
+        }
+
+        let file = File::open(&self.path).map_err(RuNeVisError::IoError)?;
+        state.archive = Some(ZipArchive::new(file).map_err(|e| self.zip_error(e))?);
+        Ok(())
+    }
+
+    fn zip_error(&self, error: zip::result::ZipError) -> RuNeVisError {
+        RuNeVisError::ZarrError(format!("Zip archive {:?}: {}", self.path, error))
+    }
+}
+
+impl ZipState {
+    /// Read an entry of the archive, ignoring staged changes
+    fn read_entry(
+        &mut self,
+        key: &str,
+    ) -> std::result::Result<Option<Vec<u8>>, zip::result::ZipError> {
+        let name = format!("{}{}", self.root, key);
+        let Some(archive) = self.archive.as_mut() else {
+            return Ok(None);
+        };
+        let mut entry = match archive.by_name(&name) {
+            Ok(entry) => entry,
+            Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if entry.is_dir() {
+            return Ok(None);
+        }
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    /// Stage a write (`Some`) or deletion (`None`) of `key`
+    fn stage(&mut self, key: &str, value: Option<Vec<u8>>) {
+        let added = value.as_ref().map_or(0, Vec::len);
+        let replaced = self
+            .pending
+            .insert(key.to_string(), value)
+            .flatten()
+            .map_or(0, |old| old.len());
+        self.pending_bytes = self.pending_bytes + added - replaced;
+    }
+
+    /// Keys of every file in the archive, relative to the store root
+    fn archived_keys(&self) -> Vec<String> {
+        let Some(archive) = &self.archive else {
+            return Vec::new();
+        };
+        archive
+            .file_names()
+            .filter_map(|name| name.strip_prefix(self.root.as_str()))
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl ZarrStore for ZipStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let mut state = self.state()?;
+        if let Some(staged) = state.pending.get(key) {
+            return Ok(staged.clone());
+        }
+        state.read_entry(key).map_err(|e| self.zip_error(e))
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>> {
+        let mut state = self.state()?;
+        if let Some(staged) = state.pending.get(key) {
+            return Ok(staged.as_ref().map(|bytes| bytes.len() as u64));
+        }
+        let name = format!("{}{}", state.root, key);
+        let Some(archive) = state.archive.as_mut() else {
+            return Ok(None);
+        };
+        let size = match archive.by_name(&name) {
+            Ok(entry) if entry.is_dir() => Ok(None),
+            Ok(entry) => Ok(Some(entry.size())),
+            Err(zip::result::ZipError::FileNotFound) => Ok(None),
+            Err(e) => Err(self.zip_error(e)),
+        };
+        size
+    }
+
+    fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        let mut state = self.state()?;
+        state.stage(key, Some(value.to_vec()));
+        if state.pending_bytes > self.flush_threshold {
+            self.flush_state(&mut state)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.state()?.stage(key, None);
+        Ok(())
+    }
+
+    fn list_dir(&self, prefix: &str) -> Result<Vec<String>> {
+        let state = self.state()?;
+        let dir = if prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", prefix)
+        };
+
+        let mut keys: BTreeSet<String> = state.archived_keys().into_iter().collect();
+        for (key, staged) in &state.pending {
+            match staged {
+                Some(_) => keys.insert(key.clone()),
+                None => keys.remove(key),
+            };
+        }
+        // Directories are implied by the keys below them (or by explicit `name/` entries)
+        let names: BTreeSet<String> = keys
+            .iter()
+            .filter_map(|key| key.strip_prefix(dir.as_str()))
+            .filter_map(|rest| rest.split_once('/').map(|(name, _)| name.to_string()))
+            .collect();
+        Ok(names.into_iter().collect())
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.path.is_file() {
+            return Err(RuNeVisError::ZarrError(format!(
+                "Zarr zip archive does not exist: {:?}",
+                self.path
+            )));
+        }
+        self.state().map(|_| ())
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut state = self.state()?;
+        self.flush_state(&mut state)
+    }
+}
+
+/// Entry name prefix of the store root
+///
+/// Archives of a zipped store folder keep every entry below a single
+/// top-level directory holding the root documents.
+fn store_root(archive: &ZipArchive<File>) -> String {
+    let names: Vec<&str> = archive.file_names().collect();
+    if names.iter().any(|name| ROOT_DOCUMENTS.contains(name)) {
+        return String::new();
+    }
+    let Some((top, _)) = names.first().and_then(|name| name.split_once('/')) else {
+        return String::new();
+    };
+    let root = format!("{}/", top);
+    let nested = names.iter().all(|name| name.starts_with(root.as_str()))
+        && ROOT_DOCUMENTS
+            .iter()
+            .any(|document| names.contains(&format!("{}{}", root, document).as_str()));
+    if nested {
+        root
+    } else {
+        String::new()
+    }
+}
@@ -3,18 +3,34 @@
 //! These tests provide extensive coverage of the core functionality
 //! to ensure reliability and prevent regressions.
 
-use ndarray::{Array3, ArrayD};
+use futures::StreamExt;
+use ndarray::{Array3, ArrayD, Axis};
 use netcdf::{create, open};
 use ru_ne_vis::{
+    convert::{netcdf_to_zarr, zarr_to_netcdf, ConversionOptions},
+    data_source::{
+        DataChunkStream, DataReader, DataSourceConverter, DataWriter, LazyDataReader,
+        StreamingDataReader,
+    },
     errors::{Result, RuNeVisError},
     metadata::{
         compute_variable_summary, describe_variable, list_variables_and_dimensions, print_metadata,
     },
-    netcdf_io::NetCDFWriter,
+    netcdf_io::{write_moment_to_netcdf, NetCDFDataSource, NetCDFWriter},
     parallel::{get_parallel_info, ParallelConfig},
-    statistics::{mean_over_dimension, median_over_dimension, StatOperation},
-    zarr_io::{ArrayMetadata, ZarrReader, ZarrSource},
+    statistics::{
+        mean_over_dimension, median_over_dimension, parallel_std_axis, parallel_var_axis,
+        reduce_over_dimension, reduce_over_dimension_with_budget, stat_over_dimension_with_budget,
+        std_over_dimension, streaming_reduce_over_dimension,
+        streaming_reduce_over_dimension_with_budget, var_over_dimension,
+        zarr::{zarr_max_over_dimension, zarr_median_over_dimension, zarr_std_over_dimension},
+        ChunkAccumulator, Moments, QuantileSketch, StatOperation, StatisticalReduction,
+    },
+    zarr_io::{
+        ArrayMetadata, Compressor, WriteOptions, ZarrDataSource, ZarrReader, ZarrSource, ZarrWriter,
+    },
 };
+use std::collections::HashMap;
 use tempfile::tempdir;
 
 #[test]
@@ -355,20 +371,11 @@ async fn test_zarr_source_and_reader() -> Result<()> {
     let source = ZarrSource::from_path_str("./test_path")?;
     assert_eq!(source.path.to_str().unwrap(), "./test_path");
 
-    // Test cloud storage rejection
-    let s3_result = ZarrSource::from_path_str("s3://bucket/path");
-    assert!(s3_result.is_err());
-    match s3_result {
-        Err(RuNeVisError::Generic(msg)) => {
-            assert!(msg.contains("Cloud storage not yet implemented"));
-        }
-        _ => panic!("Expected Generic error for cloud storage"),
-    }
-
-    let gs_result = ZarrSource::from_path_str("gs://bucket/path");
-    assert!(gs_result.is_err());
+    // Object store URLs select the S3 backend, which needs a bucket name
+    assert!(ZarrSource::from_path_str("s3://").is_err());
+    assert!(ZarrSource::from_path_str("gs:///path").is_err());
 
-    let https_result = ZarrSource::from_path_str("https://example.com/path");
+    let https_result = ZarrSource::from_path_str("https://");
     assert!(https_result.is_err());
 
     // Test ZarrReader with non-existent path
@@ -405,6 +412,7 @@ fn test_array_metadata() {
         shape: vec![10, 20, 30],
         dtype: "float32".to_string(),
         chunks: vec![5, 10, 15],
+        dimensions: vec!["time".to_string(), "lat".to_string(), "lon".to_string()],
         attributes: std::collections::HashMap::new(),
     };
 
@@ -573,3 +581,1030 @@ fn test_edge_cases_and_error_handling() -> Result<()> {
 
     Ok(())
 }
+
+/// Write, read back and reduce an array through the data source traits only
+async fn exercise_data_source<S>(source: &S) -> Result<()>
+where
+    S: DataReader + DataWriter + StreamingDataReader<ChunkStream = DataChunkStream> + Sync,
+{
+    let data = ArrayD::from_shape_vec(vec![4, 3], (0..12).map(|i| i as f32).collect())?;
+    let mut attributes = HashMap::new();
+    attributes.insert("units".to_string(), serde_json::json!("K"));
+    attributes.insert(
+        "_ARRAY_DIMENSIONS".to_string(),
+        serde_json::json!(["time", "lat"]),
+    );
+    source
+        .write_array("temperature", &data, Some(vec![2, 3]), Some(attributes))
+        .await?;
+
+    assert!(source
+        .list_arrays()
+        .await?
+        .contains(&"temperature".to_string()));
+    let metadata = source.get_metadata("temperature").await?;
+    assert_eq!(metadata.shape, vec![4, 3]);
+    assert_eq!(metadata.dimensions, vec!["time", "lat"]);
+    assert_eq!(
+        metadata.attributes.get("units"),
+        Some(&serde_json::json!("K"))
+    );
+    assert_eq!(metadata.dimension_index("lat")?, 1);
+
+    assert_eq!(source.read_array("temperature").await?, data);
+    let slice = source.read_slice("temperature", &[(1, 3), (0, 2)]).await?;
+    assert_eq!(
+        slice.iter().copied().collect::<Vec<_>>(),
+        vec![3.0, 4.0, 6.0, 7.0]
+    );
+    assert!(source
+        .read_slice("temperature", &[(0, 5), (0, 3)])
+        .await
+        .is_err());
+
+    let mut reassembled = ArrayD::<f32>::zeros(data.raw_dim());
+    let mut chunks = source.stream_chunks("temperature");
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk?;
+        reassembled
+            .slice_each_axis_mut(|ax| {
+                let (start, end) = chunk.region()[ax.axis.index()];
+                ndarray::Slice::from(start..end)
+            })
+            .assign(&chunk.data);
+    }
+    assert_eq!(reassembled, data);
+
+    let time_axis = metadata.dimension_index("time")?;
+    let mean = data.mean_axis(Axis(time_axis)).unwrap();
+    source
+        .write_statistical_result(
+            "temperature_mean",
+            &mean,
+            &["lat".to_string()],
+            "mean",
+            "temperature",
+            Some(&metadata),
+        )
+        .await?;
+    let result = source.get_metadata("temperature_mean").await?;
+    assert_eq!(result.dimensions, vec!["lat"]);
+    assert_eq!(
+        result.attributes.get("operation"),
+        Some(&serde_json::json!("mean"))
+    );
+    assert_eq!(source.read_array("temperature_mean").await?, mean);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_data_source_traits_on_both_formats() -> Result<()> {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+
+    let netcdf_source = NetCDFDataSource::create(temp_dir.path().join("source.nc"))?;
+    exercise_data_source(&netcdf_source).await?;
+
+    let zarr_path = temp_dir.path().join("source.zarr");
+    std::fs::create_dir_all(&zarr_path)?;
+    let zarr_source =
+        ZarrDataSource::new(ZarrSource::from_path_str(zarr_path.to_str().unwrap())?).await?;
+    exercise_data_source(&zarr_source).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_netcdf_data_source_metadata_and_lazy_reads() -> Result<()> {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let file_path = temp_dir.path().join("lazy.nc");
+
+    {
+        let mut file = create(&file_path)?;
+        file.add_dimension("time", 4)?;
+        file.add_dimension("lat", 3)?;
+        file.add_dimension("lon", 2)?;
+
+        let mut var = file.add_variable::<f32>("temperature", &["time", "lat", "lon"])?;
+        var.put_attribute("units", "degrees_C")?;
+        var.put_attribute("scale", 2.5f64)?;
+        var.put_attribute("valid_range", vec![-50i32, 50])?;
+        var.put_attribute("flag_values", vec![1i8, 2, 4])?;
+        var.put_attribute("checksum", u64::MAX)?;
+        let data_array = Array3::from_shape_vec((4, 3, 2), (0..24).map(|i| i as f32).collect())?;
+        var.put(data_array.view(), ..)?;
+    }
+
+    assert!(NetCDFDataSource::open(temp_dir.path().join("missing.nc")).is_err());
+    let source = NetCDFDataSource::open(&file_path)?;
+    assert_eq!(source.list_arrays().await?, vec!["temperature"]);
+
+    let metadata = source.get_metadata("temperature").await?;
+    assert_eq!(metadata.dimensions, vec!["time", "lat", "lon"]);
+    assert_eq!(metadata.shape, vec![4, 3, 2]);
+    assert_eq!(metadata.dtype, "<f4");
+    assert_eq!(metadata.attributes["units"], serde_json::json!("degrees_C"));
+    assert_eq!(metadata.attributes["scale"], serde_json::json!(2.5));
+    assert_eq!(
+        metadata.attributes["valid_range"],
+        serde_json::json!([-50, 50])
+    );
+    assert_eq!(
+        metadata.attributes["flag_values"],
+        serde_json::json!([1, 2, 4])
+    );
+    assert_eq!(metadata.attributes["checksum"], serde_json::json!(u64::MAX));
+    assert!(matches!(
+        source.get_metadata("pressure").await,
+        Err(RuNeVisError::VariableNotFound { .. })
+    ));
+
+    let mut lazy = source.lazy_load("temperature").await?;
+    assert!(!lazy.is_loaded());
+    assert_eq!(lazy.shape(), &[4, 3, 2]);
+    let window = lazy.get(&[(3, 4), (1, 3), (1, 2)]).await?;
+    assert_eq!(window.iter().copied().collect::<Vec<_>>(), vec![21.0, 23.0]);
+
+    lazy.load().await?;
+    assert!(lazy.is_loaded());
+    let window = lazy.get(&[(3, 4), (1, 3), (1, 2)]).await?;
+    assert_eq!(window.iter().copied().collect::<Vec<_>>(), vec![21.0, 23.0]);
+
+    // Contiguous variables stream one time step at a time
+    let chunks: Vec<_> = source.stream_chunks("temperature").collect().await;
+    assert_eq!(chunks.len(), 4);
+    let last = chunks[3].as_ref().unwrap();
+    assert_eq!(last.offset, vec![3, 0, 0]);
+    assert_eq!(last.shape(), &[1, 3, 2]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reduce_over_dimension_on_both_formats() -> Result<()> {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let data = ArrayD::from_shape_vec(
+        vec![2, 3, 2],
+        vec![
+            1.0, 8.0, 3.0, 4.0, 5.0, 6.0, //
+            7.0, 2.0, 9.0, 10.0, 11.0, 0.0,
+        ],
+    )?;
+    let mut attributes = HashMap::new();
+    attributes.insert(
+        "_ARRAY_DIMENSIONS".to_string(),
+        serde_json::json!(["time", "lat", "lon"]),
+    );
+
+    let netcdf_path = temp_dir.path().join("stats.nc");
+    let netcdf_source = NetCDFDataSource::create(&netcdf_path)?;
+    netcdf_source
+        .write_array("temperature", &data, None, Some(attributes.clone()))
+        .await?;
+
+    let zarr_path = temp_dir.path().join("stats.zarr");
+    std::fs::create_dir_all(&zarr_path)?;
+    let zarr_source =
+        ZarrDataSource::new(ZarrSource::from_path_str(zarr_path.to_str().unwrap())?).await?;
+    zarr_source
+        .write_array("temperature", &data, Some(vec![1, 2, 2]), Some(attributes))
+        .await?;
+
+    for operation in [
+        StatOperation::Mean,
+        StatOperation::Sum,
+        StatOperation::Min,
+        StatOperation::Max,
+        StatOperation::Median,
+    ] {
+        let from_netcdf =
+            reduce_over_dimension(&netcdf_source, "temperature", "time", operation).await?;
+        let from_zarr =
+            reduce_over_dimension(&zarr_source, "temperature", "time", operation).await?;
+
+        assert_eq!(from_netcdf.shape(), &[3, 2]);
+        assert_eq!(from_netcdf.remaining_dimensions, vec!["lat", "lon"]);
+        assert_eq!(from_netcdf.operation, operation);
+        assert_eq!(from_netcdf.data, from_zarr.data);
+        assert_eq!(
+            from_netcdf.remaining_dimensions,
+            from_zarr.remaining_dimensions
+        );
+        assert_eq!(from_netcdf.output_name(), from_zarr.output_name());
+
+        // 8 bytes hold a single line along time: the array is read slice by slice
+        for sliced in [
+            reduce_over_dimension_with_budget(&netcdf_source, "temperature", "time", operation, 8)
+                .await?,
+            reduce_over_dimension_with_budget(&zarr_source, "temperature", "time", operation, 8)
+                .await?,
+        ] {
+            assert_eq!(sliced.data, from_netcdf.data);
+            assert_eq!(
+                sliced.remaining_dimensions,
+                from_netcdf.remaining_dimensions
+            );
+        }
+    }
+
+    let mean =
+        reduce_over_dimension(&zarr_source, "temperature", "lon", StatOperation::Mean).await?;
+    assert_eq!(
+        mean.data.iter().copied().collect::<Vec<_>>(),
+        vec![4.5, 3.5, 5.5, 4.5, 9.5, 5.5]
+    );
+    assert_eq!(mean.output_name(), "temperature_mean_over_lon");
+
+    // The NetCDF entry point and the data source one agree
+    let file = open(&netcdf_path)?;
+    let (legacy, legacy_dims, legacy_name) = mean_over_dimension(&file, "temperature", "time")?;
+    let generic =
+        reduce_over_dimension(&netcdf_source, "temperature", "time", StatOperation::Mean).await?;
+    assert_eq!(legacy, generic.data);
+    assert_eq!(legacy_dims, generic.remaining_dimensions);
+    assert_eq!(legacy_name, generic.output_name());
+
+    let (max, max_dims, max_name) =
+        zarr_max_over_dimension(&zarr_source.reader, "temperature", "time").await?;
+    assert_eq!(
+        max.iter().copied().collect::<Vec<_>>(),
+        vec![7.0, 8.0, 9.0, 10.0, 11.0, 6.0]
+    );
+    assert_eq!(max_dims, vec!["lat", "lon"]);
+    assert_eq!(max_name, "temperature_maximum_over_time");
+
+    assert!(matches!(
+        reduce_over_dimension(&zarr_source, "temperature", "depth", StatOperation::Mean).await,
+        Err(RuNeVisError::DimensionNotFound { .. })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_netcdf_zarr_round_trip_conversion() -> Result<()> {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let netcdf_path = temp_dir.path().join("archive.nc");
+    let temperature = Array3::from_shape_vec((4, 3, 2), (0..24).map(|i| i as f32).collect())?;
+
+    {
+        let mut file = create(&netcdf_path)?;
+        file.add_dimension("time", 4)?;
+        file.add_dimension("lat", 3)?;
+        file.add_dimension("lon", 2)?;
+        file.add_attribute("title", "Conversion test")?;
+
+        let mut time = file.add_variable::<i32>("time", &["time"])?;
+        time.put_attribute("units", "days since 2000-01-01")?;
+        time.put(ndarray::arr1(&[0, 1, 2, 3]).view(), ..)?;
+
+        let mut lat = file.add_variable::<f64>("lat", &["lat"])?;
+        lat.put(ndarray::arr1(&[-10.0, 0.0, 10.0]).view(), ..)?;
+
+        let mut var = file.add_variable::<f32>("temperature", &["time", "lat", "lon"])?;
+        var.put_attribute("units", "K")?;
+        var.put_attribute("_FillValue", -999.0f32)?;
+        var.put(temperature.view(), ..)?;
+
+        let mut station = file.add_string_variable("station", &["lat"])?;
+        station.put_string("north", [0])?;
+    }
+
+    // NetCDF -> Zarr with chunking and compression
+    let zarr_path = temp_dir.path().join("archive.zarr");
+    std::fs::create_dir_all(&zarr_path)?;
+    let zarr_source = ZarrSource::from_path_str(zarr_path.to_str().unwrap())?;
+    let netcdf_source = NetCDFDataSource::open(&netcdf_path)?;
+    let options = ConversionOptions {
+        chunk_shape: Some(vec![2, 3, 2]),
+        compressor: Some(Compressor::Zlib { level: 5 }),
+        ..ConversionOptions::default()
+    };
+    let report = netcdf_to_zarr(
+        &netcdf_source,
+        &ZarrWriter::new(zarr_source.clone()).await?,
+        &options,
+    )
+    .await?;
+    assert_eq!(report.converted.len(), 3);
+    assert_eq!(
+        report.skipped,
+        vec![("station".to_string(), "|O".to_string())]
+    );
+    assert!(!report.is_complete());
+
+    let reader = ZarrReader::new(zarr_source).await?;
+    assert!(reader.is_consolidated());
+    assert_eq!(
+        reader.get_group_attributes("").await?.get("title"),
+        Some(&serde_json::json!("Conversion test"))
+    );
+    let metadata = reader.get_array_metadata("temperature").await?;
+    assert_eq!(metadata.dimensions, vec!["time", "lat", "lon"]);
+    assert_eq!(metadata.chunks, vec![2, 3, 2]);
+    assert_eq!(
+        metadata.attributes.get("units"),
+        Some(&serde_json::json!("K"))
+    );
+    assert!(!metadata.attributes.contains_key("_FillValue"));
+    assert_eq!(reader.get_fill_value("temperature").await?, -999.0);
+    assert_eq!(reader.get_array_metadata("time").await?.dtype, "<i4");
+    assert_eq!(reader.get_array_metadata("lat").await?.dtype, "<f8");
+    assert_eq!(
+        reader.read_array("temperature").await?,
+        temperature.clone().into_dyn()
+    );
+    let zarray: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(zarr_path.join("temperature").join(".zarray")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(zarray["compressor"]["id"], "zlib");
+
+    // Zarr -> NetCDF with deflate compression
+    let round_trip_path = temp_dir.path().join("round_trip.nc");
+    let round_trip = NetCDFDataSource::create(&round_trip_path)?;
+    let options = ConversionOptions {
+        deflate_level: Some(4),
+        ..ConversionOptions::default()
+    };
+    let report = zarr_to_netcdf(&reader, &round_trip, &options).await?;
+    assert!(report.is_complete());
+
+    let mut arrays = round_trip.list_arrays().await?;
+    arrays.sort();
+    assert_eq!(arrays, vec!["lat", "temperature", "time"]);
+    let mut dimensions = round_trip.dimensions()?;
+    dimensions.sort();
+    assert_eq!(
+        dimensions,
+        vec![
+            ("lat".to_string(), 3),
+            ("lon".to_string(), 2),
+            ("time".to_string(), 4)
+        ]
+    );
+    assert_eq!(
+        round_trip.global_attributes()?.get("title"),
+        Some(&serde_json::json!("Conversion test"))
+    );
+    let metadata = round_trip.get_metadata("temperature").await?;
+    assert_eq!(metadata.dimensions, vec!["time", "lat", "lon"]);
+    assert_eq!(metadata.attributes["units"], serde_json::json!("K"));
+    assert_eq!(metadata.attributes["_FillValue"], serde_json::json!(-999.0));
+    assert_eq!(metadata.dtype, "<f4");
+    assert_eq!(round_trip.get_metadata("time").await?.dtype, "<i4");
+    assert_eq!(round_trip.get_metadata("lat").await?.dtype, "<f8");
+    assert_eq!(
+        round_trip.read_array("temperature").await?,
+        temperature.into_dyn()
+    );
+    assert_eq!(
+        round_trip
+            .read_array("time")
+            .await?
+            .iter()
+            .copied()
+            .collect::<Vec<_>>(),
+        vec![0.0, 1.0, 2.0, 3.0]
+    );
+
+    // The converter trait copies with the default options
+    let copy_path = temp_dir.path().join("copy.zarr");
+    std::fs::create_dir_all(&copy_path)?;
+    let copy = ZarrDataSource::new(ZarrSource::from_path_str(copy_path.to_str().unwrap())?).await?;
+    copy.convert_from(&netcdf_source)?;
+    let reader = ZarrReader::new(ZarrSource::from_path_str(copy_path.to_str().unwrap())?).await?;
+    assert_eq!(
+        reader.list_arrays().await?,
+        vec!["lat", "temperature", "time"]
+    );
+    assert_eq!(
+        reader.get_array_metadata("temperature").await?.chunks,
+        vec![4, 3, 2]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_conversion_copies_values_f32_cannot_represent() -> Result<()> {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let netcdf_path = temp_dir.path().join("exact.nc");
+    let offsets =
+        ndarray::Array2::from_shape_fn((3, 5), |(i, j)| 1.7e9 + 0.123 + (i * 5 + j) as f64);
+    let counts = ndarray::arr1(&[(1i64 << 40) + 1, (1 << 53) + 1, -(1 << 53) - 1, i64::MAX, 3]);
+
+    {
+        let mut file = create(&netcdf_path)?;
+        file.add_dimension("y", 3)?;
+        file.add_dimension("x", 5)?;
+        let mut var = file.add_variable::<f64>("offset", &["y", "x"])?;
+        var.put_attribute("_FillValue", f64::NAN)?;
+        var.put(offsets.view(), ..)?;
+        let mut var = file.add_variable::<i64>("count", &["x"])?;
+        var.put(counts.view(), ..)?;
+    }
+
+    // A budget of 8 elements copies and chunks the arrays row by row
+    let options = ConversionOptions {
+        memory_budget: 64,
+        ..ConversionOptions::default()
+    };
+    let zarr_path = temp_dir.path().join("exact.zarr");
+    std::fs::create_dir_all(&zarr_path)?;
+    let zarr_source = ZarrSource::from_path_str(zarr_path.to_str().unwrap())?;
+    let report = netcdf_to_zarr(
+        &NetCDFDataSource::open(&netcdf_path)?,
+        &ZarrWriter::new(zarr_source.clone()).await?,
+        &options,
+    )
+    .await?;
+    assert!(report.is_complete());
+
+    let reader = ZarrReader::new(zarr_source).await?;
+    let metadata = reader.get_array_metadata("offset").await?;
+    assert_eq!(metadata.dtype, "<f8");
+    assert_eq!(metadata.chunks, vec![1, 5]);
+    assert!(reader.get_fill_value("offset").await?.is_nan());
+    assert_eq!(
+        reader
+            .read_slice_as::<f64>("offset", &[(0, 3), (0, 5)])
+            .await?,
+        offsets.clone().into_dyn()
+    );
+    assert_eq!(reader.get_array_metadata("count").await?.dtype, "<i8");
+    assert_eq!(
+        reader.read_slice_as::<i64>("count", &[(0, 5)]).await?,
+        counts.clone().into_dyn()
+    );
+
+    let round_trip_path = temp_dir.path().join("exact_round_trip.nc");
+    let round_trip = NetCDFDataSource::create(&round_trip_path)?;
+    zarr_to_netcdf(&reader, &round_trip, &options).await?;
+
+    let file = open(&round_trip_path)?;
+    let var = file.variable("offset").expect("offset variable");
+    assert_eq!(var.get::<f64, _>(..)?, offsets.into_dyn());
+    match var.attribute_value("_FillValue").transpose()? {
+        Some(netcdf::AttributeValue::Double(fill)) => assert!(fill.is_nan()),
+        other => panic!("Unexpected fill value {:?}", other),
+    }
+    let var = file.variable("count").expect("count variable");
+    assert_eq!(var.get::<i64, _>(..)?, counts.into_dyn());
+    assert!(var.attribute("_FillValue").is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_conversion_maps_groups_and_explicit_zero_fill() -> Result<()> {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let netcdf_path = temp_dir.path().join("groups.nc");
+    let values = ndarray::arr1(&[1.0f32, 2.0, 3.0]);
+
+    {
+        let mut file = create(&netcdf_path)?;
+        file.add_dimension("time", 3)?;
+        let mut group = file.add_group("forecast")?;
+        group.add_attribute("model", "ensemble")?;
+        let mut var = group.add_variable::<f32>("temperature", &["time"])?;
+        var.put(values.view(), ..)?;
+        let mut surface = group.add_group("surface")?;
+        surface.add_attribute("level", 0i32)?;
+    }
+
+    // NetCDF groups become Zarr groups, their variables arrays named by path;
+    // metadata stays unconsolidated so an array can be added below
+    let zarr_path = temp_dir.path().join("groups.zarr");
+    std::fs::create_dir_all(&zarr_path)?;
+    let zarr_source = ZarrSource::from_path_str(zarr_path.to_str().unwrap())?;
+    let options = ConversionOptions {
+        consolidate: false,
+        ..ConversionOptions::default()
+    };
+    let report = netcdf_to_zarr(
+        &NetCDFDataSource::open(&netcdf_path)?,
+        &ZarrWriter::new(zarr_source.clone()).await?,
+        &options,
+    )
+    .await?;
+    assert!(report.is_complete());
+    assert_eq!(report.converted, vec!["forecast/temperature"]);
+
+    let reader = ZarrReader::new(zarr_source.clone()).await?;
+    assert_eq!(
+        reader.list_groups().await?,
+        vec!["forecast", "forecast/surface"]
+    );
+    assert_eq!(
+        reader.get_group_attributes("forecast").await?.get("model"),
+        Some(&serde_json::json!("ensemble"))
+    );
+    assert_eq!(
+        reader.read_array("forecast/temperature").await?,
+        values.clone().into_dyn()
+    );
+    // Without a _FillValue the array has no fill value
+    assert_eq!(
+        reader
+            .get_declared_fill_value("forecast/temperature")
+            .await?,
+        None
+    );
+
+    // An explicit fill value of 0 survives the way back
+    let writer = ZarrWriter::new(zarr_source.clone()).await?;
+    let write_options = WriteOptions {
+        fill_value: Some(0.0),
+        dimension_names: Some(vec!["time".to_string()]),
+        ..Default::default()
+    };
+    writer
+        .write_array_with_options(
+            "forecast/precipitation",
+            &values.clone().into_dyn(),
+            &write_options,
+        )
+        .await?;
+
+    let reader = ZarrReader::new(zarr_source).await?;
+    let round_trip_path = temp_dir.path().join("groups_round_trip.nc");
+    let round_trip = NetCDFDataSource::create(&round_trip_path)?;
+    let report = zarr_to_netcdf(&reader, &round_trip, &options).await?;
+    assert!(report.is_complete());
+
+    assert_eq!(round_trip.groups()?, vec!["forecast", "forecast/surface"]);
+    assert_eq!(
+        round_trip
+            .group_attributes("forecast/surface")?
+            .get("level"),
+        Some(&serde_json::json!(0))
+    );
+    let mut arrays = round_trip.list_arrays().await?;
+    arrays.sort();
+    assert_eq!(
+        arrays,
+        vec!["forecast/precipitation", "forecast/temperature"]
+    );
+    assert_eq!(
+        round_trip.read_array("forecast/temperature").await?,
+        values.into_dyn()
+    );
+
+    let file = open(&round_trip_path)?;
+    let var = file
+        .variable("forecast/precipitation")
+        .expect("precipitation variable");
+    assert_eq!(
+        var.attribute_value("_FillValue").transpose()?,
+        Some(netcdf::AttributeValue::Float(0.0))
+    );
+    let var = file
+        .variable("forecast/temperature")
+        .expect("temperature variable");
+    assert!(var.attribute("_FillValue").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_streaming_netcdf_reduction_matches_in_memory() -> Result<()> {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let file_path = temp_dir.path().join("large.nc");
+
+    {
+        let mut file = create(&file_path)?;
+        file.add_dimension("time", 6)?;
+        file.add_dimension("lat", 5)?;
+        file.add_dimension("lon", 4)?;
+        let mut var = file.add_variable::<f32>("temperature", &["time", "lat", "lon"])?;
+        let mut values: Vec<f32> = (0..120).map(|i| ((i * 37) % 101) as f32 / 3.0).collect();
+        values[7] = f32::NAN;
+        let data_array = Array3::from_shape_vec((6, 5, 4), values)?;
+        var.put(data_array.view(), ..)?;
+
+        file.add_dimension("step", 150)?;
+        file.add_dimension("station", 2)?;
+        let mut var = file.add_variable::<f32>("series", &["step", "station"])?;
+        let values: Vec<f32> = (0..300).map(|i| ((i * 53) % 97) as f32 / 4.0).collect();
+        let series = ndarray::Array2::from_shape_vec((150, 2), values)?;
+        var.put(series.view(), ..)?;
+    }
+
+    let file = open(&file_path)?;
+    let operations = [
+        StatOperation::Mean,
+        StatOperation::Sum,
+        StatOperation::Min,
+        StatOperation::Max,
+        StatOperation::Median,
+        StatOperation::Std { ddof: 1 },
+        StatOperation::Var { ddof: 0 },
+    ];
+    for dim in ["time", "lat", "lon"] {
+        for operation in operations {
+            let (in_memory, dims, name) =
+                stat_over_dimension_with_budget(&file, "temperature", dim, operation, usize::MAX)?;
+            // 64 bytes hold 16 values, far less than the 480 bytes of the variable
+            let (streamed, streamed_dims, streamed_name) =
+                stat_over_dimension_with_budget(&file, "temperature", dim, operation, 64)?;
+
+            assert_eq!(streamed.shape(), in_memory.shape());
+            assert_eq!(streamed_dims, dims);
+            assert_eq!(streamed_name, name);
+            for (a, b) in streamed.iter().zip(in_memory.iter()) {
+                assert!(
+                    (a.is_nan() && b.is_nan()) || (a - b).abs() <= 1e-4 * b.abs().max(1.0),
+                    "{operation:?} over {dim}: {a} != {b}"
+                );
+            }
+        }
+    }
+
+    // A budget below one line of 600 bytes along the reduced dimension splits
+    // that dimension too, while half of it holds the partial results
+    for operation in [StatOperation::Max, StatOperation::Mean] {
+        let (streamed, _, _) =
+            stat_over_dimension_with_budget(&file, "series", "step", operation, 256)?;
+        let (in_memory, _, _) =
+            stat_over_dimension_with_budget(&file, "series", "step", operation, usize::MAX)?;
+        for (a, b) in streamed.iter().zip(in_memory.iter()) {
+            assert!((a - b).abs() <= 1e-4 * b.abs().max(1.0));
+        }
+    }
+
+    // Budgets too small for the partial results are refused
+    assert!(matches!(
+        stat_over_dimension_with_budget(&file, "series", "step", StatOperation::Median, 256),
+        Err(RuNeVisError::StatisticsError(_))
+    ));
+    assert!(matches!(
+        stat_over_dimension_with_budget(&file, "temperature", "time", StatOperation::Max, 1),
+        Err(RuNeVisError::StatisticsError(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_streaming_zarr_reduction_matches_in_memory() -> Result<()> {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let zarr_path = temp_dir.path().join("streaming.zarr");
+    std::fs::create_dir_all(&zarr_path)?;
+    let source =
+        ZarrDataSource::new(ZarrSource::from_path_str(zarr_path.to_str().unwrap())?).await?;
+
+    let mut data = ArrayD::from_shape_fn(vec![24, 5, 4], |idx| {
+        ((idx[0] * 7 + idx[1] * 3 + idx[2]) % 11) as f32 - 2.5
+    });
+    data[[3, 1, 2]] = f32::NAN;
+    for t in 0..24 {
+        data[[t, 4, 3]] = f32::NAN;
+    }
+    let mut attributes = HashMap::new();
+    attributes.insert(
+        "_ARRAY_DIMENSIONS".to_string(),
+        serde_json::json!(["time", "lat", "lon"]),
+    );
+    source
+        .write_array("temperature", &data, Some(vec![5, 2, 3]), Some(attributes))
+        .await?;
+
+    for dim in ["time", "lat", "lon"] {
+        for operation in [
+            StatOperation::Mean,
+            StatOperation::Sum,
+            StatOperation::Min,
+            StatOperation::Max,
+            StatOperation::Median,
+        ] {
+            let streamed =
+                streaming_reduce_over_dimension(&source, "temperature", dim, operation).await?;
+            let in_memory = reduce_over_dimension(&source, "temperature", dim, operation).await?;
+
+            assert_eq!(streamed.shape(), in_memory.shape());
+            assert_eq!(
+                streamed.remaining_dimensions,
+                in_memory.remaining_dimensions
+            );
+            assert_eq!(streamed.output_name(), in_memory.output_name());
+            for (a, b) in streamed.data.iter().zip(in_memory.data.iter()) {
+                assert!(
+                    (a.is_nan() && b.is_nan()) || (a - b).abs() < 1e-4,
+                    "{} over {dim}: {a} != {b}",
+                    operation.as_str()
+                );
+            }
+        }
+    }
+
+    // 1000 bytes hold slices of 24 x 2 x 4 values, two whole chunks, and
+    // medians stay exact
+    for dim in ["time", "lat"] {
+        let sliced = reduce_over_dimension_with_budget(
+            &source,
+            "temperature",
+            dim,
+            StatOperation::Median,
+            1000,
+        )
+        .await?;
+        let in_memory =
+            reduce_over_dimension(&source, "temperature", dim, StatOperation::Median).await?;
+        assert_eq!(sliced.data, in_memory.data);
+    }
+
+    // Median sketches of 20 output elements do not fit in 1000 bytes, sums do
+    assert!(matches!(
+        streaming_reduce_over_dimension_with_budget(
+            &source,
+            "temperature",
+            "time",
+            StatOperation::Median,
+            1000,
+        )
+        .await,
+        Err(RuNeVisError::StatisticsError(_))
+    ));
+    streaming_reduce_over_dimension_with_budget(
+        &source,
+        "temperature",
+        "time",
+        StatOperation::Sum,
+        1000,
+    )
+    .await?;
+
+    // Accumulators over disjoint chunks merge into the whole reduction
+    let chunks: Vec<_> = source.stream_chunks("temperature").collect().await;
+    let mut first = ChunkAccumulator::new(&[24, 5, 4], 0, StatOperation::Median)?;
+    let mut second = first.clone();
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let target = if i % 2 == 0 { &mut first } else { &mut second };
+        target.add_chunk(&chunk?)?;
+    }
+    first.merge(&second)?;
+    let merged = first.finish();
+    let expected =
+        reduce_over_dimension(&source, "temperature", "time", StatOperation::Median).await?;
+    assert!(merged[[4, 3]].is_nan());
+    assert_eq!(merged[[0, 0]], expected.data[[0, 0]]);
+    assert_eq!(merged[[1, 2]], expected.data[[1, 2]]);
+
+    // Lanes of far more than 200 values: the Zarr entry points give exact medians
+    let long = ArrayD::from_shape_fn(vec![1001, 2], |idx| {
+        ((idx[0] * 7919 + idx[1] * 13) % 1009) as f32 / 7.0
+    });
+    let mut attributes = HashMap::new();
+    attributes.insert(
+        "_ARRAY_DIMENSIONS".to_string(),
+        serde_json::json!(["time", "lat"]),
+    );
+    source
+        .write_array("long", &long, Some(vec![100, 2]), Some(attributes))
+        .await?;
+    let (median, _, _) = zarr_median_over_dimension(&source.reader, "long", "time").await?;
+    assert_eq!(median, long.reduce_along_axis(0, StatOperation::Median)?);
+
+    let mut mismatched = ChunkAccumulator::new(&[24, 5, 4], 1, StatOperation::Median)?;
+    assert!(mismatched.merge(&second).is_err());
+    assert!(ChunkAccumulator::new(&[24, 5, 4], 3, StatOperation::Mean).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_quantile_sketch_accuracy_and_merge() {
+    let mut empty = QuantileSketch::default();
+    assert!(empty.is_empty());
+    assert_eq!(empty.quantile(0.5), None);
+
+    // Few values are summarised exactly
+    let mut exact = QuantileSketch::default();
+    for value in [4.0, 1.0, f64::NAN, 3.0, 2.0] {
+        exact.add(value);
+    }
+    assert_eq!(exact.count(), 4);
+    assert_eq!(exact.quantile(0.5), Some(2.5));
+    assert_eq!(exact.quantile(0.0), Some(1.0));
+    assert_eq!(exact.quantile(1.0), Some(4.0));
+
+    // Many values stay within a small rank error, also once sketches merge
+    let n = 100_000;
+    let mut whole = QuantileSketch::default();
+    let mut even = QuantileSketch::default();
+    let mut odd = QuantileSketch::default();
+    for i in 0..n {
+        // Shuffle the values deterministically
+        let value = ((i * 7919) % n) as f64;
+        whole.add(value);
+        if i % 2 == 0 {
+            even.add(value);
+        } else {
+            odd.add(value);
+        }
+    }
+    even.merge(&odd);
+    assert_eq!(even.count(), n as u64);
+
+    for q in [0.01, 0.25, 0.5, 0.75, 0.99] {
+        let expected = q * (n - 1) as f64;
+        for sketch in [&mut whole, &mut even] {
+            let estimate = sketch.quantile(q).unwrap();
+            assert!(
+                (estimate - expected).abs() < 0.01 * n as f64,
+                "quantile {q}: {estimate} vs {expected}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_moments_are_stable_and_mergeable() {
+    // Large offset: naive sum-of-squares variance loses every digit here
+    let values: Vec<f64> = (0..1000).map(|i| 1e9 + f64::from(i % 10)).collect();
+    let mut whole = Moments::new();
+    let mut left = Moments::new();
+    let mut right = Moments::new();
+    for (i, &value) in values.iter().enumerate() {
+        whole.add(value);
+        if i < 337 {
+            left.add(value);
+        } else {
+            right.add(value);
+        }
+    }
+    left.merge(&right);
+
+    for moments in [whole, left] {
+        assert_eq!(moments.count(), 1000);
+        assert!((moments.mean().unwrap() - (1e9 + 4.5)).abs() < 1e-5);
+        assert!((moments.variance(0).unwrap() - 8.25).abs() < 1e-4);
+        assert!((moments.variance(1).unwrap() - 8.25 * 1000.0 / 999.0).abs() < 1e-4);
+    }
+
+    let mut single = Moments::new();
+    single.add(3.0);
+    assert_eq!(single.variance(0), Some(0.0));
+    assert_eq!(single.variance(1), None);
+    assert_eq!(Moments::new().mean(), None);
+    assert_eq!(Moments::new().std(0), None);
+}
+
+#[tokio::test]
+async fn test_std_and_var_reductions() -> Result<()> {
+    let mut data = ArrayD::from_shape_vec(
+        vec![4, 3],
+        vec![
+            1.0,
+            2.0,
+            5.0, //
+            3.0,
+            2.0,
+            f32::NAN, //
+            5.0,
+            2.0,
+            f32::NAN, //
+            7.0,
+            2.0,
+            f32::NAN,
+        ],
+    )?;
+
+    let var = data.reduce_along_axis(0, StatOperation::Var { ddof: 0 })?;
+    assert_eq!(var.shape(), &[3]);
+    assert!((var[[0]] - 5.0).abs() < 1e-6);
+    assert_eq!(var[[1]], 0.0);
+    assert_eq!(var[[2]], 0.0);
+
+    let sample_var = data.reduce_along_axis(0, StatOperation::Var { ddof: 1 })?;
+    assert!((sample_var[[0]] - 20.0 / 3.0).abs() < 1e-5);
+    // A single finite value leaves no degrees of freedom
+    assert!(sample_var[[2]].is_nan());
+    assert!(matches!(
+        parallel_var_axis(&data, 2, 0),
+        Err(RuNeVisError::StatisticsError(_))
+    ));
+    assert!(matches!(
+        parallel_std_axis(&data, 2, 1),
+        Err(RuNeVisError::StatisticsError(_))
+    ));
+
+    let std = data.reduce_along_axis(0, StatOperation::Std { ddof: 1 })?;
+    assert!((std[[0]] - (20.0_f32 / 3.0).sqrt()).abs() < 1e-5);
+
+    // Reducing to a single value splits the lane and merges the blocks
+    let line = ArrayD::from_shape_fn(vec![10_001], |idx| idx[0] as f32);
+    let line_var = line.reduce_along_axis(0, StatOperation::Var { ddof: 0 })?;
+    let expected = (10_001.0_f64 * 10_001.0 - 1.0) / 12.0;
+    let line_var = f64::from(line_var.iter().copied().next().unwrap());
+    assert!((line_var - expected).abs() / expected < 1e-6);
+
+    assert_eq!(
+        StatOperation::Std { ddof: 1 }.as_str(),
+        "standard_deviation"
+    );
+    assert_eq!(StatOperation::Var { ddof: 1 }.as_str(), "variance");
+    assert_eq!(StatOperation::Var { ddof: 0 }.ddof(), Some(0));
+    assert_eq!(StatOperation::Mean.ddof(), None);
+    assert_eq!(
+        StatOperation::Std { ddof: 1 }.to_string(),
+        "standard_deviation (ddof=1)"
+    );
+    assert_eq!(StatOperation::Median.to_string(), "median");
+
+    // Both formats, in memory and streamed, agree
+    data[[1, 2]] = 4.0;
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let mut attributes = HashMap::new();
+    attributes.insert(
+        "_ARRAY_DIMENSIONS".to_string(),
+        serde_json::json!(["time", "lat"]),
+    );
+
+    let netcdf_path = temp_dir.path().join("spread.nc");
+    let netcdf_source = NetCDFDataSource::create(&netcdf_path)?;
+    netcdf_source
+        .write_array("temperature", &data, None, Some(attributes.clone()))
+        .await?;
+
+    let zarr_path = temp_dir.path().join("spread.zarr");
+    std::fs::create_dir_all(&zarr_path)?;
+    let zarr_source =
+        ZarrDataSource::new(ZarrSource::from_path_str(zarr_path.to_str().unwrap())?).await?;
+    zarr_source
+        .write_array("temperature", &data, Some(vec![1, 2]), Some(attributes))
+        .await?;
+
+    let file = open(&netcdf_path)?;
+    let (netcdf_std, dims, name) = std_over_dimension(&file, "temperature", "time", 1)?;
+    assert_eq!(dims, vec!["lat"]);
+    assert_eq!(name, "temperature_standard_deviation_over_time");
+    let (zarr_std, zarr_dims, zarr_name) =
+        zarr_std_over_dimension(&zarr_source.reader, "temperature", "time", 1).await?;
+    assert_eq!(zarr_dims, dims);
+    assert_eq!(zarr_name, name);
+    for (a, b) in netcdf_std.iter().zip(zarr_std.iter()) {
+        assert!((a.is_nan() && b.is_nan()) || (a - b).abs() < 1e-5);
+    }
+
+    let (netcdf_var, _, _) = var_over_dimension(&file, "temperature", "time", 0)?;
+    let streamed = streaming_reduce_over_dimension(
+        &zarr_source,
+        "temperature",
+        "time",
+        StatOperation::Var { ddof: 0 },
+    )
+    .await?;
+    for (a, b) in netcdf_var.iter().zip(streamed.data.iter()) {
+        assert!((a.is_nan() && b.is_nan()) || (a - b).abs() < 1e-5);
+    }
+    assert_eq!(streamed.output_name(), "temperature_variance_over_time");
+
+    // The writers record the degrees of freedom next to the operation
+    let metadata = zarr_source.get_metadata("temperature").await?;
+    zarr_source
+        .write_stat_result(&streamed, Some(&metadata))
+        .await?;
+    let written = zarr_source
+        .get_metadata("temperature_variance_over_time")
+        .await?;
+    assert_eq!(written.dimensions, vec!["lat"]);
+    assert_eq!(
+        written.attributes.get("operation"),
+        Some(&serde_json::json!("variance"))
+    );
+    assert_eq!(written.attributes.get("ddof"), Some(&serde_json::json!(0)));
+
+    let zarr_result = reduce_over_dimension(
+        &zarr_source,
+        "temperature",
+        "time",
+        StatOperation::Std { ddof: 1 },
+    )
+    .await?;
+    assert_eq!(zarr_result.operation.ddof(), Some(1));
+    zarr_source.write_stat_result(&zarr_result, None).await?;
+    let written = zarr_source.get_metadata(&name).await?;
+    assert_eq!(written.attributes.get("ddof"), Some(&serde_json::json!(1)));
+
+    let output_path = temp_dir.path().join("std.nc");
+    write_moment_to_netcdf(
+        &netcdf_std,
+        &dims,
+        &name,
+        "temperature",
+        &file,
+        &output_path,
+        StatOperation::Std { ddof: 1 },
+    )?;
+    let output = open(&output_path)?;
+    let output_var = output.variable(&name).expect("Result variable not found");
+    let ddof = output_var
+        .attribute("ddof")
+        .expect("ddof attribute not found")
+        .value()?;
+    assert!(matches!(ddof, netcdf::AttributeValue::Int(1)));
+
+    Ok(())
+}
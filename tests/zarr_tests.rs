@@ -1,4 +1,4 @@
-use ru_ne_vis::zarr_io::{ZarrReader, ZarrWriter, ZarrSource};
+use ru_ne_vis::zarr_io::{BloscShuffle, ChunkOrder, Compressor, DataType, Filter, WriteOptions, ZarrFormat, ZarrReader, ZarrWriter, ZarrSource};
 use ndarray::ArrayD;
 use tempfile::tempdir;
 use futures::StreamExt;
@@ -47,6 +47,50 @@ async fn test_lazy_loading() {
     assert!(lazy_array.is_loaded());
 }
 
+#[tokio::test]
+async fn test_lazy_array_windowed_reads() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let array = ArrayD::from_shape_vec(vec![8, 8], (0..64).map(|x| x as f32).collect()).unwrap();
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    writer.write_array("lazy", &array, Some(vec![4, 4]), None).await.unwrap();
+
+    // A budget of a single 4 x 4 f32 chunk
+    let reader = ZarrReader::new(source).await.unwrap();
+    let mut lazy = reader.lazy_load_array("lazy").await.unwrap().with_cache_budget(64);
+
+    let window = lazy.get(&[(1, 3), (1, 3)]).await.unwrap();
+    assert_eq!(window, array.slice(ndarray::s![1..3, 1..3]).to_owned().into_dyn());
+    assert!(!lazy.is_loaded());
+    let stats = lazy.cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.chunks, stats.bytes), (0, 1, 1, 64));
+
+    // Cached chunks are not read again, even once gone from the store
+    std::fs::remove_file(test_dir.path().join("lazy/0.0")).unwrap();
+    let window = lazy.get(&[(0, 2), (0, 2)]).await.unwrap();
+    assert_eq!(window.as_slice().unwrap(), [0.0, 1.0, 8.0, 9.0]);
+    assert_eq!(lazy.cache_stats().hits, 1);
+
+    // Reading another chunk evicts the least recently used one
+    let window = lazy.get(&[(4, 6), (6, 8)]).await.unwrap();
+    assert_eq!(window.as_slice().unwrap(), [38.0, 39.0, 46.0, 47.0]);
+    assert!(lazy.is_chunk_cached(&[1, 1]));
+    assert!(!lazy.is_chunk_cached(&[0, 0]));
+    assert_eq!(lazy.cache_stats().bytes, 64);
+    let window = lazy.get(&[(0, 1), (0, 2)]).await.unwrap();
+    assert_eq!(window.as_slice().unwrap(), [0.0, 0.0], "evicted chunk is read from the store again");
+
+    // Windows larger than the budget are still assembled completely
+    let window = lazy.get(&[(2, 6), (2, 6)]).await.unwrap();
+    let mut expected = array.slice(ndarray::s![2..6, 2..6]).to_owned().into_dyn();
+    expected.slice_mut(ndarray::s![0..2, 0..2]).fill(0.0);
+    assert_eq!(window, expected);
+    assert!(lazy.cache_stats().bytes <= 64);
+
+    assert!(lazy.get(&[(0, 9), (0, 8)]).await.is_err());
+    assert!(lazy.get(&[(0, 1)]).await.is_err());
+}
+
 #[tokio::test]
 async fn test_stream_loading() {
     let test_dir = tempdir().unwrap();
@@ -55,23 +99,1109 @@ async fn test_stream_loading() {
 
     // Test data to write
     let data: Vec<f32> = (0..100).map(|x| x as f32).collect();
-    let array = ArrayD::from_shape_vec(vec![10, 10], data.clone()).unwrap();
+    let array = ArrayD::from_shape_vec(vec![10, 10], data).unwrap();
 
-    // Test writing
+    // Test writing, with edge chunks along both dimensions
     let writer = ZarrWriter::new(source.clone()).await.unwrap();
-    writer.write_array("stream_array", &array, Some(vec![10, 10]), None).await.unwrap();
+    writer.write_array("stream_array", &array, Some(vec![4, 3]), None).await.unwrap();
 
     // Test streaming
     let reader = ZarrReader::new(source.clone()).await.unwrap();
     let mut stream = reader.stream_chunks("stream_array");
 
-    let mut collected_data = Vec::new();
-
+    let mut reassembled = ArrayD::from_elem(vec![10, 10], f32::NAN);
+    let mut grid_indices = Vec::new();
     while let Some(chunk) = stream.next().await {
         let chunk = chunk.unwrap();
-        collected_data.extend_from_slice(chunk.as_slice().unwrap());
+        assert_eq!(chunk.offset, vec![chunk.grid_index[0] * 4, chunk.grid_index[1] * 3]);
+        let region = chunk.region();
+        reassembled
+            .slice_mut(ndarray::s![region[0].0..region[0].1, region[1].0..region[1].1])
+            .assign(&chunk.data);
+        grid_indices.push(chunk.grid_index);
+    }
+
+    // Chunks arrive in C order of the 3 x 4 chunk grid
+    let expected_indices: Vec<Vec<usize>> = (0..3).flat_map(|i| (0..4).map(move |j| vec![i, j])).collect();
+    assert_eq!(grid_indices, expected_indices);
+    assert_eq!(reassembled, array);
+
+    // Edge chunks are clipped to the array bounds
+    let last = reader.stream_chunks("stream_array").skip(11).next().await.unwrap().unwrap();
+    assert_eq!(last.shape(), &[2, 1]);
+    assert_eq!(last.offset, vec![8, 9]);
+    assert_eq!(last.data.as_slice().unwrap(), &[89.0, 99.0]);
+}
+
+
+/// Write a Zarr v2 array by hand, one chunk file per `(key, values)` entry
+fn write_raw_array(root: &std::path::Path, name: &str, zarray: serde_json::Value, chunks: &[(String, Vec<f32>)]) {
+    let array_dir = root.join(name);
+    std::fs::create_dir_all(&array_dir).unwrap();
+    std::fs::write(array_dir.join(".zarray"), zarray.to_string()).unwrap();
+    for (key, values) in chunks {
+        let chunk_path = array_dir.join(key);
+        std::fs::create_dir_all(chunk_path.parent().unwrap()).unwrap();
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        std::fs::write(chunk_path, bytes).unwrap();
+    }
+}
+
+/// Assert that the uncompressed little-endian `f32` chunk files of an array hold `chunks`
+fn assert_raw_chunks(array_dir: &std::path::Path, chunks: &[(String, Vec<f32>)]) {
+    for (key, values) in chunks {
+        let bytes = std::fs::read(array_dir.join(key)).unwrap();
+        let stored: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(&stored, values, "chunk {}", key);
+    }
+}
+
+/// Chunk files for a 5x4 array with 2x3 chunks where element (i, j) = i * 10 + j
+fn raw_chunks_5x4(fortran_order: bool, separator: &str) -> Vec<(String, Vec<f32>)> {
+    let mut chunks = Vec::new();
+    for ci in 0..3 {
+        for cj in 0..2 {
+            let mut values = Vec::new();
+            let element = |r: usize, c: usize| {
+                let (i, j) = (ci * 2 + r, cj * 3 + c);
+                // Edge chunks are padded with a sentinel that must never be read back
+                if i < 5 && j < 4 { (i * 10 + j) as f32 } else { -1.0 }
+            };
+            if fortran_order {
+                for c in 0..3 {
+                    for r in 0..2 {
+                        values.push(element(r, c));
+                    }
+                }
+            } else {
+                for r in 0..2 {
+                    for c in 0..3 {
+                        values.push(element(r, c));
+                    }
+                }
+            }
+            chunks.push((format!("{}{}{}", ci, separator, cj), values));
+        }
+    }
+    chunks
+}
+
+fn expected_5x4() -> ArrayD<f32> {
+    ArrayD::from_shape_fn(vec![5, 4], |idx| (idx[0] * 10 + idx[1]) as f32)
+}
+
+#[tokio::test]
+async fn test_read_spec_compliant_chunks() {
+    let test_dir = tempdir().unwrap();
+    let zarray = serde_json::json!({
+        "chunks": [2, 3], "compressor": null, "dtype": "<f4", "fill_value": 0.0,
+        "filters": null, "order": "C", "shape": [5, 4], "zarr_format": 2
+    });
+    write_raw_array(test_dir.path(), "c_order", zarray, &raw_chunks_5x4(false, "."));
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let reader = ZarrReader::new(source).await.unwrap();
+    let loaded = reader.read_array("c_order").await.unwrap();
+    assert_eq!(loaded, expected_5x4());
+}
+
+#[tokio::test]
+async fn test_read_fortran_order_and_slash_separator() {
+    let test_dir = tempdir().unwrap();
+    let zarray = serde_json::json!({
+        "chunks": [2, 3], "compressor": null, "dtype": "<f4", "fill_value": 0.0,
+        "filters": null, "order": "F", "shape": [5, 4], "zarr_format": 2,
+        "dimension_separator": "/"
+    });
+    write_raw_array(test_dir.path(), "f_order", zarray, &raw_chunks_5x4(true, "/"));
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let reader = ZarrReader::new(source).await.unwrap();
+    let loaded = reader.read_array("f_order").await.unwrap();
+    assert_eq!(loaded, expected_5x4());
+}
+
+#[tokio::test]
+async fn test_read_missing_chunks_and_slice() {
+    let test_dir = tempdir().unwrap();
+    let zarray = serde_json::json!({
+        "chunks": [2, 3], "compressor": null, "dtype": "<f4", "fill_value": "NaN",
+        "filters": null, "order": "C", "shape": [5, 4], "zarr_format": 2
+    });
+    // Drop the last chunk so its region falls back to the fill value
+    let mut chunks = raw_chunks_5x4(false, ".");
+    chunks.retain(|(key, _)| key != "2.1");
+    write_raw_array(test_dir.path(), "sparse", zarray, &chunks);
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let reader = ZarrReader::new(source).await.unwrap();
+
+    let loaded = reader.read_array("sparse").await.unwrap();
+    assert!(loaded[[4, 3]].is_nan());
+    assert_eq!(loaded[[4, 2]], 42.0);
+
+    let slice = reader.read_slice("sparse", &[(1, 4), (2, 4)]).await.unwrap();
+    let expected = expected_5x4().slice(ndarray::s![1..4, 2..4]).to_owned().into_dyn();
+    assert_eq!(slice, expected);
+
+    let out_of_bounds = reader.read_slice("sparse", &[(0, 6), (0, 4)]).await;
+    assert!(out_of_bounds.is_err());
+}
+
+#[tokio::test]
+async fn test_write_hyperrectangular_chunks() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let array = expected_5x4();
+
+    let options = WriteOptions {
+        chunk_shape: Some(vec![2, 3]),
+        fill_value: Some(-1.0),
+        ..WriteOptions::default()
+    };
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    writer.write_array_with_options("tiled", &array, &options).await.unwrap();
+
+    // Chunk files must match the layout produced by other Zarr implementations
+    let array_dir = test_dir.path().join("tiled");
+    assert_raw_chunks(&array_dir, &raw_chunks_5x4(false, "."));
+
+    let zarray: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(array_dir.join(".zarray")).unwrap()).unwrap();
+    assert_eq!(zarray["chunks"], serde_json::json!([2, 3]));
+    assert_eq!(zarray["shape"], serde_json::json!([5, 4]));
+    assert_eq!(zarray["fill_value"], serde_json::json!(-1.0));
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    assert_eq!(reader.read_array("tiled").await.unwrap(), array);
+}
+
+#[tokio::test]
+async fn test_write_nested_fortran_chunks() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let array = expected_5x4();
+
+    let options = WriteOptions {
+        chunk_shape: Some(vec![2, 3]),
+        dimension_separator: Some("/".to_string()),
+        order: ChunkOrder::F,
+        fill_value: Some(-1.0),
+        ..WriteOptions::default()
+    };
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    writer.write_array_with_options("nested", &array, &options).await.unwrap();
+
+    let array_dir = test_dir.path().join("nested");
+    assert_raw_chunks(&array_dir, &raw_chunks_5x4(true, "/"));
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    assert_eq!(reader.read_array("nested").await.unwrap(), array);
+}
+
+#[tokio::test]
+async fn test_compressor_round_trips() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let array = ArrayD::from_shape_fn(vec![40, 30], |idx| ((idx[0] * 7 + idx[1]) % 13) as f32 * 0.5);
+
+    let compressors = vec![
+        ("blosc_lz4", Compressor::blosc("lz4", 5)),
+        ("blosc_zstd_bitshuffle", Compressor::Blosc {
+            cname: "zstd".to_string(),
+            clevel: 3,
+            shuffle: BloscShuffle::BitShuffle,
+            blocksize: 0,
+        }),
+        ("blosc_zlib", Compressor::blosc("zlib", 1)),
+        ("zstd", Compressor::zstd(3)),
+        ("gzip", Compressor::gzip(5)),
+        ("zlib", Compressor::Zlib { level: 6 }),
+        ("lz4", Compressor::lz4()),
+    ];
+
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    let reader = ZarrReader::new(source).await.unwrap();
+    for (name, compressor) in compressors {
+        let options = WriteOptions {
+            chunk_shape: Some(vec![16, 16]),
+            compressor: Some(compressor.clone()),
+            ..WriteOptions::default()
+        };
+        writer.write_array_with_options(name, &array, &options).await.unwrap();
+
+        let zarray: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(test_dir.path().join(name).join(".zarray")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(zarray["compressor"], compressor.to_json(), "{}", name);
+
+        // Repetitive data must actually shrink on disk
+        let stored = std::fs::metadata(test_dir.path().join(name).join("0.0")).unwrap().len();
+        assert!(stored < 16 * 16 * 4, "{} stored {} bytes", name, stored);
+
+        assert_eq!(reader.read_array(name).await.unwrap(), array, "{}", name);
+    }
+}
+
+#[test]
+fn test_blosc_codecs_shuffles_and_blocks() {
+    // 16 KiB of slowly varying floats, as in a typical climate field
+    let values: Vec<f32> = (0..4096).map(|i| 280.0 + (i / 64) as f32 * 0.5).collect();
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let header_u32 = |buffer: &[u8], pos: usize| u32::from_le_bytes(buffer[pos..pos + 4].try_into().unwrap());
+
+    let codecs = [("blosclz", 0u8), ("lz4", 1), ("lz4hc", 1), ("zlib", 3), ("zstd", 4)];
+    let shuffles = [BloscShuffle::NoShuffle, BloscShuffle::Shuffle, BloscShuffle::BitShuffle];
+    for (cname, code) in codecs {
+        for shuffle in shuffles {
+            // Default blocks (a single block here) and 16 blocks of 1 KiB
+            for blocksize in [0usize, 1024] {
+                let compressor = Compressor::Blosc { cname: cname.to_string(), clevel: 5, shuffle, blocksize };
+                let encoded = compressor.encode(&bytes, 4).unwrap();
+                let case = format!("{} {:?} blocksize {}", cname, shuffle, blocksize);
+
+                let flags = encoded[2];
+                assert_eq!(encoded[0], 2, "{}: format version", case);
+                assert_eq!(flags >> 5, code, "{}: compressor code", case);
+                assert_eq!(flags & 0x01 != 0, shuffle == BloscShuffle::Shuffle, "{}: byte shuffle flag", case);
+                assert_eq!(flags & 0x04 != 0, shuffle == BloscShuffle::BitShuffle, "{}: bit shuffle flag", case);
+                assert_eq!(flags & 0x02, 0, "{}: must not be memcpyed", case);
+                assert_eq!(encoded[3], 4, "{}: type size", case);
+                assert_eq!(header_u32(&encoded, 4) as usize, bytes.len(), "{}: uncompressed size", case);
+                assert_eq!(header_u32(&encoded, 12) as usize, encoded.len(), "{}: compressed size", case);
+                if blocksize > 0 {
+                    assert_eq!(header_u32(&encoded, 8) as usize, blocksize, "{}: block size", case);
+                }
+                assert!(encoded.len() < bytes.len() / 2, "{}: stored {} bytes", case, encoded.len());
+
+                let decoded = compressor.decode(&encoded).unwrap();
+                let decoded: Vec<f32> = decoded.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+                assert_eq!(decoded, values, "{}", case);
+            }
+        }
+    }
+
+    // LZ4 blocks of 256 four-byte elements are split into one stream per
+    // byte; Zstd blocks never are
+    let split = Compressor::Blosc { cname: "lz4".to_string(), clevel: 5, shuffle: BloscShuffle::Shuffle, blocksize: 1024 };
+    assert_eq!(split.encode(&bytes, 4).unwrap()[2] & 0x10, 0);
+    let unsplit = Compressor::Blosc { cname: "zstd".to_string(), clevel: 5, shuffle: BloscShuffle::Shuffle, blocksize: 1024 };
+    assert_ne!(unsplit.encode(&bytes, 4).unwrap()[2] & 0x10, 0);
+}
+
+#[test]
+fn test_blosc_memcpyed_buffers() {
+    let values = [1.5f32, -2.0, 3.25, 1.0e6];
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+
+    // Buffers below the Blosc minimum are stored uncompressed after the header
+    let compressor = Compressor::blosc("lz4", 5);
+    let encoded = compressor.encode(&bytes, 4).unwrap();
+    assert_ne!(encoded[2] & 0x02, 0, "memcpyed flag");
+    assert_eq!(encoded.len(), 16 + bytes.len());
+    assert_eq!(&encoded[16..], &bytes[..]);
+    assert_eq!(compressor.decode(&encoded).unwrap(), bytes);
+
+    // A memcpyed buffer as laid out in the Blosc 1.x format description
+    let mut buffer = vec![2u8, 1, 0x02, 4];
+    buffer.extend_from_slice(&16u32.to_le_bytes()); // uncompressed size
+    buffer.extend_from_slice(&16u32.to_le_bytes()); // block size
+    buffer.extend_from_slice(&32u32.to_le_bytes()); // compressed size
+    buffer.extend_from_slice(&bytes);
+    assert_eq!(Compressor::blosc("blosclz", 5).decode(&buffer).unwrap(), bytes);
+
+    // Truncated buffers are rejected rather than read out of bounds
+    assert!(compressor.decode(&buffer[..20]).is_err());
+    assert!(compressor.decode(&buffer[..8]).is_err());
+}
+
+#[tokio::test]
+async fn test_filter_round_trips() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    let reader = ZarrReader::new(source).await.unwrap();
+
+    let float32 = DataType::parse("<f4").unwrap();
+    let data: Vec<f32> = (0..60).map(|x| x as f32 * 0.25 + 10.0).collect();
+    let array = ArrayD::from_shape_vec(vec![6, 10], data).unwrap();
+
+    let filters = [
+        ("delta", Filter::Delta { dtype: float32, astype: float32 }),
+        ("shuffle", Filter::Shuffle { elementsize: 4 }),
+        ("quantize", Filter::Quantize { digits: 3, dtype: float32, astype: float32 }),
+        (
+            "fixedscaleoffset",
+            Filter::FixedScaleOffset {
+                scale: 4.0,
+                offset: 10.0,
+                dtype: float32,
+                astype: DataType::parse("|u1").unwrap(),
+            },
+        ),
+    ];
+
+    for (name, filter) in filters {
+        let options = WriteOptions {
+            chunk_shape: Some(vec![4, 4]),
+            filters: vec![filter.clone()],
+            compressor: Some(Compressor::zstd(3)),
+            ..WriteOptions::default()
+        };
+        writer.write_array_with_options(name, &array, &options).await.unwrap();
+
+        let zarray: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(test_dir.path().join(name).join(".zarray")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(zarray["filters"], serde_json::json!([filter.to_json()]), "{}", name);
+
+        // All values are exactly representable by every filter configuration
+        assert_eq!(reader.read_array(name).await.unwrap(), array, "{}", name);
+    }
+}
+
+#[tokio::test]
+async fn test_read_fixedscaleoffset_archive() {
+    let test_dir = tempdir().unwrap();
+
+    // Values 0.0, 0.1, ..., 0.5 packed as |u1 with scale 10 and offset 0
+    let zarray = serde_json::json!({
+        "chunks": [6], "compressor": null, "dtype": "<f4", "fill_value": 0.0,
+        "filters": [{"id": "fixedscaleoffset", "scale": 10, "offset": 0, "dtype": "<f4", "astype": "|u1"}],
+        "order": "C", "shape": [6], "zarr_format": 2
+    });
+    let array_dir = test_dir.path().join("packed");
+    std::fs::create_dir_all(&array_dir).unwrap();
+    std::fs::write(array_dir.join(".zarray"), serde_json::to_string(&zarray).unwrap()).unwrap();
+    std::fs::write(array_dir.join("0"), [0u8, 1, 2, 3, 4, 5]).unwrap();
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let reader = ZarrReader::new(source).await.unwrap();
+    let values = reader.read_array("packed").await.unwrap();
+
+    let expected = [0.0f32, 0.1, 0.2, 0.3, 0.4, 0.5];
+    for (value, expected) in values.iter().zip(expected) {
+        assert!((value - expected).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_quantize_filter_json() {
+    let config = serde_json::json!({"id": "quantize", "digits": 2, "dtype": "<f8"});
+    let filter = Filter::from_json(&config).unwrap();
+    let float64 = DataType::parse("<f8").unwrap();
+    assert_eq!(filter, Filter::Quantize { digits: 2, dtype: float64, astype: float64 });
+
+    let raw: Vec<u8> = [1.23456f64, -9.87654].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let quantized = float64.decode(&filter.encode(&raw).unwrap()).unwrap();
+    assert!((quantized[0] - 1.23456).abs() < 0.01);
+    assert!((quantized[1] + 9.87654).abs() < 0.01);
+
+    assert!(Filter::from_json(&serde_json::json!({"id": "bitround"})).is_err());
+}
+
+#[tokio::test]
+async fn test_zarr_v3_round_trip() {
+    let test_dir = tempdir().unwrap();
+    let test_path = test_dir.path().to_str().unwrap();
+    let source = ZarrSource::from_path_str(test_path).unwrap().with_format(ZarrFormat::V3);
+
+    let data: Vec<f32> = (0..20).map(|x| x as f32).collect();
+    let array = ArrayD::from_shape_vec(vec![5, 4], data).unwrap();
+
+    let options = WriteOptions {
+        chunk_shape: Some(vec![2, 3]),
+        order: ChunkOrder::F,
+        compressor: Some(Compressor::blosc("zstd", 3)),
+        dimension_names: Some(vec!["y".to_string(), "x".to_string()]),
+        ..WriteOptions::default()
+    };
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    writer.write_array_with_options("v3_array", &array, &options).await.unwrap();
+
+    // Zarr v3 chunk keys default to the "/" separator
+    let array_dir = test_dir.path().join("v3_array");
+    assert!(array_dir.join("c/2/1").exists());
+    assert!(!array_dir.join(".zarray").exists());
+
+    let doc: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(array_dir.join("zarr.json")).unwrap()).unwrap();
+    assert_eq!(doc["zarr_format"], 3);
+    assert_eq!(doc["data_type"], "float32");
+    assert_eq!(doc["chunk_grid"]["configuration"]["chunk_shape"], serde_json::json!([2, 3]));
+    assert_eq!(doc["chunk_key_encoding"], serde_json::json!({"name": "default", "configuration": {"separator": "/"}}));
+    assert_eq!(doc["dimension_names"], serde_json::json!(["y", "x"]));
+    let codec_names: Vec<&str> = doc["codecs"].as_array().unwrap().iter().map(|c| c["name"].as_str().unwrap()).collect();
+    assert_eq!(codec_names, ["transpose", "bytes", "blosc"]);
+    assert_eq!(doc["codecs"][2]["configuration"]["shuffle"], "shuffle");
+
+    // The root group document lets the format be detected from the path alone
+    let detected = ZarrSource::from_path_str(test_path).unwrap();
+    assert_eq!(detected.format, ZarrFormat::V3);
+
+    let reader = ZarrReader::new(detected).await.unwrap();
+    assert_eq!(reader.list_arrays().await.unwrap(), vec!["v3_array".to_string()]);
+    assert_eq!(reader.get_array_metadata("v3_array").await.unwrap().dtype, "float32");
+    assert_eq!(reader.read_array("v3_array").await.unwrap(), array);
+}
+
+/// CRC32C (Castagnoli) checksum of a buffer, bit by bit
+fn crc32c(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[tokio::test]
+async fn test_read_zarr_v3_codec_chain() {
+    let test_dir = tempdir().unwrap();
+    let array_dir = test_dir.path().join("chained");
+    std::fs::create_dir_all(&array_dir).unwrap();
+
+    // 3x2 array with a single 2x2 chunk in F order, gzip-compressed and checksummed
+    let zarr_json = serde_json::json!({
+        "zarr_format": 3,
+        "node_type": "array",
+        "shape": [3, 2],
+        "data_type": "float32",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [2, 2]}},
+        "chunk_key_encoding": {"name": "v2", "configuration": {"separator": "."}},
+        "fill_value": "NaN",
+        "codecs": [
+            {"name": "transpose", "configuration": {"order": [1, 0]}},
+            {"name": "bytes", "configuration": {"endian": "little"}},
+            {"name": "gzip", "configuration": {"level": 5}},
+            {"name": "crc32c"}
+        ],
+        "attributes": {"units": "K"}
+    });
+    std::fs::write(array_dir.join("zarr.json"), serde_json::to_string(&zarr_json).unwrap()).unwrap();
+
+    // Chunk (0, 0) holds [[0, 1], [10, 11]] stored column by column; chunk (1, 0) is missing
+    let raw: Vec<u8> = [0.0f32, 10.0, 1.0, 11.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let mut stored = Compressor::gzip(5).encode(&raw, 4).unwrap();
+    stored.extend_from_slice(&crc32c(&stored).to_le_bytes());
+    std::fs::write(array_dir.join("0.0"), &stored).unwrap();
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    assert_eq!(source.format, ZarrFormat::V2);
+    let reader = ZarrReader::new(source).await.unwrap();
+
+    let metadata = reader.get_array_metadata("chained").await.unwrap();
+    assert_eq!(metadata.attributes["units"], "K");
+
+    let values = reader.read_array("chained").await.unwrap();
+    assert_eq!(values.shape(), &[3, 2]);
+    assert_eq!(values[[0, 0]], 0.0);
+    assert_eq!(values[[0, 1]], 1.0);
+    assert_eq!(values[[1, 0]], 10.0);
+    assert_eq!(values[[1, 1]], 11.0);
+    assert!(values[[2, 0]].is_nan() && values[[2, 1]].is_nan());
+
+    // A corrupted checksum is reported instead of returning bad data
+    stored[0] ^= 0xFF;
+    std::fs::write(array_dir.join("0.0"), &stored).unwrap();
+    assert!(reader.read_array("chained").await.is_err());
+}
+
+#[tokio::test]
+async fn test_zarr_v3_bytes_to_bytes_filters() {
+    let test_dir = tempdir().unwrap();
+    let array_dir = test_dir.path().join("shuffled");
+    std::fs::create_dir_all(&array_dir).unwrap();
+
+    // zarr.json as written by zarr-python 3 for
+    // `zarr.create_array(..., serializer="auto", compressors=[Shuffle(elementsize=4), Zstd(level=0)])`
+    let zarr_json = r#"{
+        "shape": [2, 3],
+        "data_type": "float32",
+        "chunk_grid": {"name": "regular", "configuration": {"chunk_shape": [2, 3]}},
+        "chunk_key_encoding": {"name": "default", "configuration": {"separator": "/"}},
+        "fill_value": 0.0,
+        "codecs": [
+            {"name": "bytes", "configuration": {"endian": "little"}},
+            {"name": "numcodecs.shuffle", "configuration": {"elementsize": 4}},
+            {"name": "zstd", "configuration": {"level": 0, "checksum": false}}
+        ],
+        "attributes": {},
+        "zarr_format": 3,
+        "node_type": "array",
+        "storage_transformers": []
+    }"#;
+    std::fs::write(array_dir.join("zarr.json"), zarr_json).unwrap();
+
+    let values = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let raw: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let shuffled = Filter::Shuffle { elementsize: 4 }.encode(&raw).unwrap();
+    let stored = Compressor::zstd(0).encode(&shuffled, 4).unwrap();
+    std::fs::create_dir_all(array_dir.join("c/0")).unwrap();
+    std::fs::write(array_dir.join("c/0/0"), &stored).unwrap();
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap().with_format(ZarrFormat::V3);
+    let reader = ZarrReader::new(source.clone()).await.unwrap();
+    let array = reader.read_array("shuffled").await.unwrap();
+    assert_eq!(array, ArrayD::from_shape_vec(vec![2, 3], values.to_vec()).unwrap());
+
+    // Writing puts array-to-array filters before `bytes` and shuffle after it
+    let float32 = DataType::parse("<f4").unwrap();
+    let options = WriteOptions {
+        filters: vec![Filter::Delta { dtype: float32, astype: float32 }, Filter::Shuffle { elementsize: 4 }],
+        compressor: Some(Compressor::zstd(0)),
+        ..WriteOptions::default()
+    };
+    let writer = ZarrWriter::new(source).await.unwrap();
+    writer.write_array_with_options("rewritten", &array, &options).await.unwrap();
+    let doc: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(test_dir.path().join("rewritten/zarr.json")).unwrap()).unwrap();
+    let codec_names: Vec<&str> = doc["codecs"].as_array().unwrap().iter().map(|c| c["name"].as_str().unwrap()).collect();
+    assert_eq!(codec_names, ["numcodecs.delta", "bytes", "numcodecs.shuffle", "zstd"]);
+    assert_eq!(reader.read_array("rewritten").await.unwrap(), array);
+
+    // Element filters cannot follow a byte filter in a v3 chain
+    let misordered = WriteOptions {
+        filters: vec![Filter::Shuffle { elementsize: 4 }, Filter::Delta { dtype: float32, astype: float32 }],
+        ..WriteOptions::default()
+    };
+    assert!(writer.write_array_with_options("misordered", &array, &misordered).await.is_err());
+
+    // Codecs on the wrong side of `bytes` are rejected when reading
+    for codecs in [
+        r#"[{"name": "numcodecs.shuffle", "configuration": {"elementsize": 4}}, {"name": "bytes"}]"#,
+        r#"[{"name": "bytes"}, {"name": "numcodecs.delta", "configuration": {"dtype": "<f4"}}]"#,
+        r#"[{"name": "bytes"}, {"name": "zstd", "configuration": {"level": 0}}, {"name": "numcodecs.shuffle"}]"#,
+    ] {
+        let mut doc: serde_json::Value = serde_json::from_str(zarr_json).unwrap();
+        doc["codecs"] = serde_json::from_str(codecs).unwrap();
+        std::fs::write(array_dir.join("zarr.json"), doc.to_string()).unwrap();
+        assert!(reader.read_array("shuffled").await.is_err(), "{}", codecs);
+    }
+}
+
+#[tokio::test]
+async fn test_zarr_v3_sharding() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_format(ZarrFormat::V3);
+
+    let data: Vec<f32> = (0..48).map(|x| x as f32).collect();
+    let array = ArrayD::from_shape_vec(vec![6, 8], data).unwrap();
+
+    // 4x4 shards of 2x2 inner chunks; the last row of shards is a padded edge
+    let options = WriteOptions {
+        chunk_shape: Some(vec![4, 4]),
+        inner_chunk_shape: Some(vec![2, 2]),
+        order: ChunkOrder::F,
+        compressor: Some(Compressor::zstd(3)),
+        ..WriteOptions::default()
+    };
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    writer.write_array_with_options("sharded", &array, &options).await.unwrap();
+
+    let array_dir = test_dir.path().join("sharded");
+    let doc: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(array_dir.join("zarr.json")).unwrap()).unwrap();
+    let sharding = &doc["codecs"][0];
+    assert_eq!(sharding["name"], "sharding_indexed");
+    assert_eq!(sharding["configuration"]["chunk_shape"], serde_json::json!([2, 2]));
+    assert_eq!(sharding["configuration"]["index_location"], "end");
+    assert_eq!(sharding["configuration"]["codecs"][0]["name"], "transpose");
+
+    // One object per shard, not per inner chunk
+    for key in ["c/0/0", "c/0/1", "c/1/0", "c/1/1"] {
+        assert!(array_dir.join(key).is_file(), "{}", key);
     }
+    assert!(!array_dir.join("c/0/2").exists());
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    assert_eq!(reader.read_array("sharded").await.unwrap(), array);
+    let slice = reader.read_slice("sharded", &[(1, 5), (3, 6)]).await.unwrap();
+    assert_eq!(slice, array.slice(ndarray::s![1..5, 3..6]).into_dyn());
+
+    // Corrupt the first inner chunk of shard (0, 0); slices of other inner chunks never read it
+    let shard_path = array_dir.join("c/0/0");
+    let mut shard = std::fs::read(&shard_path).unwrap();
+    shard[0] ^= 0xFF;
+    std::fs::write(&shard_path, &shard).unwrap();
+
+    let slice = reader.read_slice("sharded", &[(2, 4), (2, 4)]).await.unwrap();
+    assert_eq!(slice, array.slice(ndarray::s![2..4, 2..4]).into_dyn());
+    assert!(reader.read_slice("sharded", &[(0, 1), (0, 1)]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_sharding_requires_zarr_v3() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let writer = ZarrWriter::new(source).await.unwrap();
+    let array = ArrayD::from_elem(vec![4, 4], 1.0f32);
+
+    let options = WriteOptions {
+        chunk_shape: Some(vec![4, 4]),
+        inner_chunk_shape: Some(vec![2, 2]),
+        ..WriteOptions::default()
+    };
+    assert!(writer.write_array_with_options("v2_sharded", &array, &options).await.is_err());
+
+    let uneven = WriteOptions {
+        inner_chunk_shape: Some(vec![3, 3]),
+        ..options
+    };
+    let v3_writer = ZarrWriter::new(
+        ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap().with_format(ZarrFormat::V3),
+    )
+    .await
+    .unwrap();
+    assert!(v3_writer.write_array_with_options("uneven", &array, &uneven).await.is_err());
+}
+
+#[tokio::test]
+async fn test_statistical_result_attributes_round_trip() {
+    use ru_ne_vis::data_source::DataReader;
+
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+
+    let result = ArrayD::from_elem(vec![3, 4], 2.5f32);
+    let dims = vec!["lat".to_string(), "lon".to_string()];
+    writer
+        .write_statistical_result("temp_mean", &result, &dims, "mean", "temperature", None)
+        .await
+        .unwrap();
+    assert!(test_dir.path().join("temp_mean").join(".zattrs").is_file());
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    let metadata = DataReader::get_metadata(&reader, "temp_mean").await.unwrap();
+    assert_eq!(metadata.attributes["operation"], "mean");
+    assert_eq!(metadata.attributes["source_array"], "temperature");
+    assert_eq!(metadata.attributes["dimensions"], serde_json::json!(["lat", "lon"]));
 
-    assert_eq!(collected_data, data);
+    // Rewriting without attributes drops the stale document
+    writer.write_array("temp_mean", &result, None, None).await.unwrap();
+    assert!(reader.get_array_metadata("temp_mean").await.unwrap().attributes.is_empty());
 }
 
+#[tokio::test]
+async fn test_read_zattrs() {
+    let test_dir = tempdir().unwrap();
+    let zarray = serde_json::json!({
+        "chunks": [2], "compressor": null, "dtype": "<f4", "fill_value": 0.0,
+        "filters": null, "order": "C", "shape": [2], "zarr_format": 2
+    });
+    write_raw_array(test_dir.path(), "with_attrs", zarray, &[]);
+    std::fs::write(
+        test_dir.path().join("with_attrs").join(".zattrs"),
+        r#"{"units": "K", "scale_factor": 0.5, "valid_range": [0, 400]}"#,
+    )
+    .unwrap();
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let reader = ZarrReader::new(source).await.unwrap();
+    let metadata = reader.get_array_metadata("with_attrs").await.unwrap();
+    assert_eq!(metadata.attributes.len(), 3);
+    assert_eq!(metadata.attributes["units"], "K");
+    assert_eq!(metadata.attributes["scale_factor"], 0.5);
+    assert_eq!(metadata.attributes["valid_range"], serde_json::json!([0, 400]));
+
+    std::fs::write(test_dir.path().join("with_attrs").join(".zattrs"), "[1, 2]").unwrap();
+    assert!(reader.get_array_metadata("with_attrs").await.is_err());
+}
+
+#[tokio::test]
+async fn test_dimension_names_round_trip() {
+    use ru_ne_vis::data_source::DataReader;
+
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    let array = ArrayD::from_elem(vec![2, 3, 4], 1.0f32);
+
+    let options = WriteOptions {
+        dimension_names: Some(vec!["time".to_string(), "lat".to_string(), "lon".to_string()]),
+        ..WriteOptions::default()
+    };
+    writer.write_array_with_options("temperature", &array, &options).await.unwrap();
+
+    let zattrs: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(test_dir.path().join("temperature").join(".zattrs")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(zattrs["_ARRAY_DIMENSIONS"], serde_json::json!(["time", "lat", "lon"]));
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    let metadata = DataReader::get_metadata(&reader, "temperature").await.unwrap();
+    assert_eq!(metadata.dimensions, vec!["time", "lat", "lon"]);
+    assert!(!metadata.attributes.contains_key("_ARRAY_DIMENSIONS"));
+    assert_eq!(metadata.dimension_index("lat").unwrap(), 1);
+    assert!(metadata.dimension_index("depth").is_err());
+
+    // Statistical results carry the names of the kept dimensions
+    let mean = ArrayD::from_elem(vec![3, 4], 1.0f32);
+    let kept = vec!["lat".to_string(), "lon".to_string()];
+    writer
+        .write_statistical_result("temperature_mean", &mean, &kept, "mean", "temperature", None)
+        .await
+        .unwrap();
+    let metadata = reader.get_array_metadata("temperature_mean").await.unwrap();
+    assert_eq!(metadata.dimensions, kept);
+}
+
+#[tokio::test]
+async fn test_dimension_names_defaults_and_v3() {
+    let test_dir = tempdir().unwrap();
+    let zarray = serde_json::json!({
+        "chunks": [2, 2], "compressor": null, "dtype": "<f4", "fill_value": 0.0,
+        "filters": null, "order": "C", "shape": [2, 2], "zarr_format": 2
+    });
+    write_raw_array(test_dir.path(), "unnamed", zarray, &[]);
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let reader = ZarrReader::new(source.clone()).await.unwrap();
+    assert_eq!(reader.get_array_metadata("unnamed").await.unwrap().dimensions, vec!["dim_0", "dim_1"]);
+
+    // A name list of the wrong length is rejected
+    std::fs::write(
+        test_dir.path().join("unnamed").join(".zattrs"),
+        r#"{"_ARRAY_DIMENSIONS": ["x"]}"#,
+    )
+    .unwrap();
+    assert!(reader.get_array_metadata("unnamed").await.is_err());
+
+    // Zarr v3 names may be null for individual dimensions
+    let writer = ZarrWriter::new(source.with_format(ZarrFormat::V3)).await.unwrap();
+    let options = WriteOptions {
+        dimension_names: Some(vec!["y".to_string(), "x".to_string()]),
+        ..WriteOptions::default()
+    };
+    writer
+        .write_array_with_options("named", &ArrayD::from_elem(vec![2, 2], 0.0f32), &options)
+        .await
+        .unwrap();
+    let zarr_json_path = test_dir.path().join("named").join("zarr.json");
+    let mut doc: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&zarr_json_path).unwrap()).unwrap();
+    assert_eq!(reader.get_array_metadata("named").await.unwrap().dimensions, vec!["y", "x"]);
+
+    doc["dimension_names"] = serde_json::json!([null, "x"]);
+    std::fs::write(&zarr_json_path, doc.to_string()).unwrap();
+    assert_eq!(reader.get_array_metadata("named").await.unwrap().dimensions, vec!["dim_0", "x"]);
+}
+
+#[tokio::test]
+async fn test_nested_groups() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+
+    let mut attributes = std::collections::HashMap::new();
+    attributes.insert("realm".to_string(), serde_json::json!("atmosphere"));
+    writer.create_group("atmos", Some(attributes)).await.unwrap();
+
+    let tas = ArrayD::from_elem(vec![2, 3], 280.0f32);
+    let sst = ArrayD::from_elem(vec![4], 290.0f32);
+    writer.write_array("atmos/tas", &tas, None, None).await.unwrap();
+    writer.write_array("ocean/surface/sst", &sst, None, None).await.unwrap();
+    writer.write_array("mask", &ArrayD::from_elem(vec![2], 1.0f32), None, None).await.unwrap();
+
+    // Missing intermediate groups are created on write
+    assert!(test_dir.path().join(".zgroup").is_file());
+    assert!(test_dir.path().join("ocean/.zgroup").is_file());
+    assert!(test_dir.path().join("ocean/surface/.zgroup").is_file());
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    assert_eq!(reader.list_arrays().await.unwrap(), vec!["atmos/tas", "mask", "ocean/surface/sst"]);
+    assert_eq!(reader.list_groups().await.unwrap(), vec!["atmos", "ocean", "ocean/surface"]);
+    assert_eq!(reader.get_group_attributes("atmos").await.unwrap()["realm"], "atmosphere");
+    assert!(reader.get_group_attributes("ocean").await.unwrap().is_empty());
+    assert!(reader.get_group_attributes("land").await.is_err());
+
+    assert_eq!(reader.read_array("atmos/tas").await.unwrap(), tas);
+    assert_eq!(reader.read_array("/ocean/surface/sst").await.unwrap(), sst);
+    assert_eq!(reader.get_array_metadata("atmos/tas").await.unwrap().shape, vec![2, 3]);
+
+    // Paths cannot escape the store or nest inside arrays
+    assert!(reader.read_array("../atmos/tas").await.is_err());
+    assert!(writer.write_array("mask/inner", &sst, None, None).await.is_err());
+    assert!(writer.create_group("atmos/tas", None).await.is_err());
+}
+
+#[tokio::test]
+async fn test_nested_groups_v3() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_format(ZarrFormat::V3);
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+
+    let mut attributes = std::collections::HashMap::new();
+    attributes.insert("title".to_string(), serde_json::json!("reanalysis"));
+    writer.create_group("", Some(attributes)).await.unwrap();
+    writer.write_array("atmos/tas", &ArrayD::from_elem(vec![3], 1.0f32), None, None).await.unwrap();
+
+    let group: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(test_dir.path().join("atmos/zarr.json")).unwrap()).unwrap();
+    assert_eq!(group["node_type"], "group");
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    assert_eq!(reader.list_arrays().await.unwrap(), vec!["atmos/tas"]);
+    assert_eq!(reader.list_groups().await.unwrap(), vec!["atmos"]);
+    assert_eq!(reader.get_group_attributes("").await.unwrap()["title"], "reanalysis");
+}
+
+#[tokio::test]
+async fn test_consolidated_metadata() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+
+    let tas = ArrayD::from_elem(vec![2, 2], 280.0f32);
+    let options = WriteOptions {
+        dimension_names: Some(vec!["lat".to_string(), "lon".to_string()]),
+        consolidate: true,
+        ..WriteOptions::default()
+    };
+    writer.write_array_with_options("atmos/tas", &tas, &options).await.unwrap();
+
+    let zmetadata: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(test_dir.path().join(".zmetadata")).unwrap()).unwrap();
+    assert_eq!(zmetadata["zarr_consolidated_format"], 1);
+    assert_eq!(zmetadata["metadata"]["atmos/tas/.zarray"]["shape"], serde_json::json!([2, 2]));
+    assert!(zmetadata["metadata"]["atmos/.zgroup"].is_object());
+
+    // Later writes keep existing consolidated metadata up to date
+    writer.write_array("ocean/sst", &ArrayD::from_elem(vec![3], 290.0f32), None, None).await.unwrap();
+
+    // The reader only needs the consolidated document: remove the per-node ones
+    for document in ["atmos/tas/.zarray", "atmos/tas/.zattrs", "atmos/.zgroup", "ocean/sst/.zarray"] {
+        std::fs::remove_file(test_dir.path().join(document)).unwrap();
+    }
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    assert!(reader.is_consolidated());
+    assert_eq!(reader.list_arrays().await.unwrap(), vec!["atmos/tas", "ocean/sst"]);
+    assert_eq!(reader.list_groups().await.unwrap(), vec!["atmos", "ocean"]);
+    let metadata = reader.get_array_metadata("atmos/tas").await.unwrap();
+    assert_eq!(metadata.dimensions, vec!["lat", "lon"]);
+    assert_eq!(reader.read_array("atmos/tas").await.unwrap(), tas);
+    assert!(matches!(
+        reader.get_array_metadata("atmos/missing").await,
+        Err(ru_ne_vis::errors::RuNeVisError::ArrayNotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_consolidated_metadata_v3() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_format(ZarrFormat::V3);
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    writer.write_array("atmos/tas", &ArrayD::from_elem(vec![4], 1.0f32), None, None).await.unwrap();
+    writer.consolidate_metadata().await.unwrap();
+
+    let root: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(test_dir.path().join("zarr.json")).unwrap()).unwrap();
+    let consolidated = &root["consolidated_metadata"];
+    assert_eq!(consolidated["kind"], "inline");
+    assert_eq!(consolidated["metadata"]["atmos"]["node_type"], "group");
+    assert_eq!(consolidated["metadata"]["atmos/tas"]["node_type"], "array");
+
+    // Rewriting the root group attributes keeps the consolidated metadata
+    let mut attributes = std::collections::HashMap::new();
+    attributes.insert("title".to_string(), serde_json::json!("test"));
+    writer.create_group("", Some(attributes)).await.unwrap();
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    assert!(reader.is_consolidated());
+    assert_eq!(reader.list_arrays().await.unwrap(), vec!["atmos/tas"]);
+    assert_eq!(reader.get_group_attributes("").await.unwrap()["title"], "test");
+}
+
+#[tokio::test]
+async fn test_read_v2_dtypes() {
+    let test_dir = tempdir().unwrap();
+
+    // Raw chunk bytes of [-2, 0, 3] (or [true, false, true]) in each storage dtype
+    let cases: Vec<(&str, Vec<u8>, [f32; 3])> = vec![
+        ("<f8", [-2.0f64, 0.0, 3.0].iter().flat_map(|v| v.to_le_bytes()).collect(), [-2.0, 0.0, 3.0]),
+        (">f4", [-2.0f32, 0.0, 3.0].iter().flat_map(|v| v.to_be_bytes()).collect(), [-2.0, 0.0, 3.0]),
+        ("<i2", [-2i16, 0, 3].iter().flat_map(|v| v.to_le_bytes()).collect(), [-2.0, 0.0, 3.0]),
+        (">i2", [-2i16, 0, 3].iter().flat_map(|v| v.to_be_bytes()).collect(), [-2.0, 0.0, 3.0]),
+        ("<i8", [-2i64, 0, 3].iter().flat_map(|v| v.to_le_bytes()).collect(), [-2.0, 0.0, 3.0]),
+        ("<u4", [2u32, 0, 3].iter().flat_map(|v| v.to_le_bytes()).collect(), [2.0, 0.0, 3.0]),
+        ("|u1", vec![254, 0, 3], [254.0, 0.0, 3.0]),
+        ("|b1", vec![1, 0, 1], [1.0, 0.0, 1.0]),
+    ];
+    for (i, (dtype, bytes, _)) in cases.iter().enumerate() {
+        let array_dir = test_dir.path().join(format!("a{}", i));
+        std::fs::create_dir_all(&array_dir).unwrap();
+        let zarray = serde_json::json!({
+            "chunks": [3], "compressor": null, "dtype": dtype, "fill_value": 0,
+            "filters": null, "order": "C", "shape": [3], "zarr_format": 2
+        });
+        std::fs::write(array_dir.join(".zarray"), zarray.to_string()).unwrap();
+        std::fs::write(array_dir.join("0"), bytes).unwrap();
+    }
+
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let reader = ZarrReader::new(source).await.unwrap();
+    for (i, (dtype, _, expected)) in cases.iter().enumerate() {
+        let name = format!("a{}", i);
+        assert_eq!(reader.get_array_metadata(&name).await.unwrap().dtype, *dtype);
+        let values = reader.read_array(&name).await.unwrap();
+        assert_eq!(values.as_slice().unwrap(), expected, "dtype {}", dtype);
+    }
+
+    assert!(DataType::parse("<c8").is_err());
+    assert!(DataType::parse("|b2").is_err());
+}
+
+#[tokio::test]
+async fn test_write_non_float32_dtypes() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    let array = ArrayD::from_shape_vec(vec![2, 3], vec![-3.7f32, 0.0, 1.2, 250.0, -1.0, 7.9]).unwrap();
+
+    let int16 = WriteOptions {
+        dtype: DataType::parse(">i2").unwrap(),
+        chunk_shape: Some(vec![2, 2]),
+        fill_value: Some(-9999.0),
+        ..WriteOptions::default()
+    };
+    writer.write_array_with_options("counts", &array, &int16).await.unwrap();
+    let float64 = WriteOptions { dtype: DataType::parse("<f8").unwrap(), ..WriteOptions::default() };
+    writer.write_array_with_options("precise", &array, &float64).await.unwrap();
+
+    let zarray: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(test_dir.path().join("counts/.zarray")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(zarray["dtype"], ">i2");
+    assert_eq!(zarray["fill_value"], -9999);
+    // Big-endian int16 elements, truncated towards zero
+    let chunk = std::fs::read(test_dir.path().join("counts/0.0")).unwrap();
+    assert_eq!(chunk, [0xff, 0xfd, 0x00, 0x00, 0x00, 0xfa, 0xff, 0xff]);
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    let counts = reader.read_array("counts").await.unwrap();
+    assert_eq!(counts.as_slice().unwrap(), [-3.0, 0.0, 1.0, 250.0, -1.0, 7.0]);
+    assert_eq!(reader.get_array_metadata("precise").await.unwrap().dtype, "<f8");
+    assert_eq!(reader.read_array("precise").await.unwrap(), array);
+}
+
+#[tokio::test]
+async fn test_zarr_v3_big_endian_dtypes() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_format(ZarrFormat::V3);
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    let array = ArrayD::from_shape_vec(vec![4], vec![1.0f32, -2.0, 300.0, 0.0]).unwrap();
+
+    let options = WriteOptions {
+        dtype: DataType::parse(">i2").unwrap(),
+        compressor: Some(Compressor::blosc("lz4", 5)),
+        ..WriteOptions::default()
+    };
+    writer.write_array_with_options("levels", &array, &options).await.unwrap();
+    let sharded = WriteOptions {
+        dtype: DataType::parse("|u1").unwrap(),
+        chunk_shape: Some(vec![4]),
+        inner_chunk_shape: Some(vec![2]),
+        ..WriteOptions::default()
+    };
+    writer.write_array_with_options("flags", &array.mapv(f32::abs), &sharded).await.unwrap();
+
+    let doc: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(test_dir.path().join("levels/zarr.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(doc["data_type"], "int16");
+    assert_eq!(doc["codecs"][0], serde_json::json!({"name": "bytes", "configuration": {"endian": "big"}}));
+    assert_eq!(doc["codecs"][1]["configuration"]["typesize"], 2);
+
+    let reader = ZarrReader::new(source).await.unwrap();
+    assert_eq!(reader.read_array("levels").await.unwrap(), array);
+    assert_eq!(reader.get_array_metadata("flags").await.unwrap().dtype, "uint8");
+    assert_eq!(reader.read_array("flags").await.unwrap().as_slice().unwrap(), [1.0, 2.0, 44.0, 0.0]);
+}
+
+#[tokio::test]
+async fn test_append_resize_and_write_slice() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap();
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    let initial = ArrayD::from_shape_vec(vec![3, 4], (0..12).map(|x| x as f32).collect()).unwrap();
+    writer.write_array("series", &initial, Some(vec![2, 3]), None).await.unwrap();
+
+    // Append two timesteps, filling the padded edge chunk row and a new one
+    let extra = ArrayD::from_shape_vec(vec![2, 4], (12..20).map(|x| x as f32).collect()).unwrap();
+    writer.append("series", &extra, 0).await.unwrap();
+    let reader = ZarrReader::new(source.clone()).await.unwrap();
+    let mut expected = ArrayD::from_shape_vec(vec![5, 4], (0..20).map(|x| x as f32).collect()).unwrap();
+    assert_eq!(reader.get_array_metadata("series").await.unwrap().shape, vec![5, 4]);
+    assert_eq!(reader.read_array("series").await.unwrap(), expected);
+    assert!(writer.append("series", &extra, 1).await.is_err());
+    assert!(writer.append("series", &ArrayD::from_elem(vec![1, 3], 0.0), 0).await.is_err());
+
+    // Overwrite a region spanning four chunks
+    let patch = ArrayD::from_shape_vec(vec![3, 2], vec![100.0, 101.0, 102.0, 103.0, 104.0, 105.0]).unwrap();
+    writer.write_slice("series", &[(1, 4), (2, 4)], &patch).await.unwrap();
+    expected.slice_mut(ndarray::s![1..4, 2..4]).assign(&patch);
+    assert_eq!(reader.read_array("series").await.unwrap(), expected);
+    assert!(writer.write_slice("series", &[(1, 4), (2, 3)], &patch).await.is_err());
+    assert!(writer.write_slice("series", &[(4, 7), (2, 4)], &patch).await.is_err());
+
+    // Shrinking deletes the chunks outside the new shape...
+    writer.resize_array("series", &[2, 2]).await.unwrap();
+    assert_eq!(reader.read_array("series").await.unwrap(), expected.slice(ndarray::s![0..2, 0..2]).to_owned().into_dyn());
+    assert!(test_dir.path().join("series/0.0").exists());
+    assert!(!test_dir.path().join("series/0.1").exists());
+    assert!(!test_dir.path().join("series/1.0").exists());
+
+    // ...and growing again exposes fill values, not the old data
+    writer.resize_array("series", &[5, 4]).await.unwrap();
+    let mut regrown = ArrayD::zeros(vec![5, 4]);
+    regrown.slice_mut(ndarray::s![0..2, 0..2]).assign(&expected.slice(ndarray::s![0..2, 0..2]));
+    assert_eq!(reader.read_array("series").await.unwrap(), regrown);
+    assert!(writer.resize_array("series", &[5]).await.is_err());
+    assert!(writer.write_slice("missing", &[(0, 1)], &ArrayD::zeros(vec![1])).await.is_err());
+}
+
+#[tokio::test]
+async fn test_append_to_sharded_consolidated_array() {
+    let test_dir = tempdir().unwrap();
+    let source = ZarrSource::from_path_str(test_dir.path().to_str().unwrap())
+        .unwrap()
+        .with_format(ZarrFormat::V3);
+    let writer = ZarrWriter::new(source.clone()).await.unwrap();
+    let initial = ArrayD::from_shape_vec(vec![3, 4], (0..12).map(|x| x as f32).collect()).unwrap();
+    let options = WriteOptions {
+        chunk_shape: Some(vec![4, 4]),
+        inner_chunk_shape: Some(vec![2, 2]),
+        consolidate: true,
+        ..WriteOptions::default()
+    };
+    writer.write_array_with_options("atmos/tas", &initial, &options).await.unwrap();
+
+    let extra = ArrayD::from_shape_vec(vec![3, 4], (12..24).map(|x| x as f32).collect()).unwrap();
+    writer.append("atmos/tas", &extra, 0).await.unwrap();
+
+    // The consolidated metadata follows the new shape
+    let reader = ZarrReader::new(ZarrSource::from_path_str(test_dir.path().to_str().unwrap()).unwrap()).await.unwrap();
+    assert!(reader.is_consolidated());
+    assert_eq!(reader.get_array_metadata("atmos/tas").await.unwrap().shape, vec![6, 4]);
+    let expected = ArrayD::from_shape_vec(vec![6, 4], (0..24).map(|x| x as f32).collect()).unwrap();
+    assert_eq!(reader.read_array("atmos/tas").await.unwrap(), expected);
+}